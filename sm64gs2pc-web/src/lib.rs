@@ -5,6 +5,10 @@ use web_sys::HtmlInputElement;
 use web_sys::HtmlTextAreaElement;
 use yew::prelude::*;
 
+/// Maximum amount of lines in a GameShark code, so huge pastes aren't
+/// converted
+const MAX_CODE_LINES: usize = 1000;
+
 /// Main app component
 struct App {
     /// Name of the cheat
@@ -159,10 +163,9 @@ impl App {
     /// Generate output of patch conversion
     fn generate_output(&self) -> Result<String, String> {
         // Parse GameShark code
-        let code = self
-            .gameshark_code
-            .parse::<sm64gs2pc::gameshark::Code>()
-            .map_err(|err| err.to_string())?;
+        let code =
+            sm64gs2pc::gameshark::Code::parse_with_max_lines(&self.gameshark_code, MAX_CODE_LINES)
+                .map_err(|err| err.to_string())?;
 
         // Convert to patch
         let patch = sm64gs2pc::DECOMP_DATA_STATIC
@@ -0,0 +1,182 @@
+//! Checks for codes that set Mario's action to an unknown value
+
+use crate::decomp_data::DecompData;
+use crate::decomp_data::ToPatchError;
+use crate::gameshark;
+use crate::left_value::LeftValue;
+use crate::left_value::LeftValueKind;
+use crate::typ::Type;
+use crate::warning::Warning;
+use crate::warning::WarningKind;
+
+use std::collections::BTreeMap;
+
+/// Values of common actions from the decompilation's `include/sm64.h`
+///
+/// This isn't every action, but covers the ones that codes usually set.
+const KNOWN_ACTIONS: &[u32] = &[
+    0x00000000, // ACT_UNINITIALIZED
+    0x0C400201, // ACT_IDLE
+    0x0C400202, // ACT_START_SLEEPING
+    0x0C000203, // ACT_SLEEPING
+    0x0C000204, // ACT_WAKING_UP
+    0x0C400205, // ACT_PANTING
+    0x08000207, // ACT_HOLD_IDLE
+    0x08000208, // ACT_HOLD_HEAVY_IDLE
+    0x0C400209, // ACT_STANDING_AGAINST_WALL
+    0x0C40020A, // ACT_COUGHING
+    0x0C40020B, // ACT_SHIVERING
+    0x0002020D, // ACT_IN_QUICKSAND
+    0x0C008220, // ACT_CROUCHING
+    0x04000440, // ACT_WALKING
+    0x00000442, // ACT_HOLD_WALKING
+    0x00000443, // ACT_TURNING_AROUND
+    0x00000444, // ACT_FINISH_TURNING_AROUND
+    0x04000445, // ACT_BRAKING
+    0x20810446, // ACT_RIDING_SHELL_GROUND
+    0x00000447, // ACT_HOLD_HEAVY_WALKING
+    0x04008448, // ACT_CRAWLING
+    0x00020449, // ACT_BURNING_GROUND
+    0x0400044A, // ACT_DECELERATING
+    0x00840452, // ACT_BUTT_SLIDE
+    0x03000880, // ACT_JUMP
+    0x03000881, // ACT_DOUBLE_JUMP
+    0x01000882, // ACT_TRIPLE_JUMP
+    0x01000883, // ACT_BACKFLIP
+    0x03000885, // ACT_STEEP_JUMP
+    0x03000886, // ACT_WALL_KICK_AIR
+    0x01000887, // ACT_SIDE_FLIP
+    0x03000888, // ACT_LONG_JUMP
+    0x01000889, // ACT_WATER_JUMP
+    0x0188088A, // ACT_DIVE
+    0x0100088C, // ACT_FREEFALL
+    0x0300088D, // ACT_TOP_OF_POLE_JUMP
+    0x0300088E, // ACT_BUTT_SLIDE_AIR
+    0x03000894, // ACT_FLYING_TRIPLE_JUMP
+    0x00880898, // ACT_SHOT_FROM_CANNON
+    0x10880899, // ACT_FLYING
+    0x0281089A, // ACT_RIDING_SHELL_JUMP
+    0x0081089B, // ACT_RIDING_SHELL_FALL
+    0x1008089C, // ACT_VERTICAL_WIND
+    0x108008A4, // ACT_TWIRLING
+    0x008008A9, // ACT_GROUND_POUND
+    0x0000130F, // ACT_DEBUG_FREE_MOVE
+    0x380022C0, // ACT_WATER_IDLE
+    0x300024D0, // ACT_BREASTSTROKE
+    0x300024D2, // ACT_FLUTTER_KICK
+];
+
+impl DecompData {
+    /// Warn about writes that set Mario's action to an unknown value
+    ///
+    /// The game can crash if `action` of a `MarioState` isn't a valid action.
+    /// The bytes that a code writes to each `action` field are combined, so
+    /// a pair of 16-bit writes is checked as one value. If only some bytes are
+    /// written, they're checked against the same bytes of each known action.
+    /// Known actions are the common actions from the decompilation, so this
+    /// is a lint rather than proof that a code crashes.
+    ///
+    /// ## Return values
+    ///   * `Ok(warnings)` - One warning for each `action` field set to an
+    ///     unknown value
+    ///   * `Err(err)` - The address of a code line can't be resolved
+    pub fn check_actions(&self, code: &gameshark::Code) -> Result<Vec<String>, ToPatchError> {
+        let warnings = self.action_warnings(code)?;
+        Ok(warnings
+            .into_iter()
+            .map(|warning| warning.message)
+            .collect())
+    }
+
+    /// Warn about writes that set Mario's action to an unknown value, like
+    /// `check_actions`, as warnings
+    ///
+    /// Each warning is for the last line that writes to the `action` field.
+    pub(crate) fn action_warnings(
+        &self,
+        code: &gameshark::Code,
+    ) -> Result<Vec<Warning>, ToPatchError> {
+        // Mask of the written bits, the written value, and the last line that
+        // wrote to each action field
+        let mut actions = BTreeMap::<String, (u32, u32, Option<gameshark::CodeLine>)>::new();
+
+        for line in &code.expand_repeats().0 {
+            if line.is_check() {
+                continue;
+            }
+
+            for (byte_addr, byte) in line.bytes() {
+                let lvalue = match self.addr_to_lvalue(byte_addr + 0x80000000) {
+                    Ok(lvalue) => lvalue,
+                    // Memory without symbols isn't an action
+                    Err(ToPatchError::UnmappedRegion { .. }) => continue,
+                    Err(err) => return Err(err),
+                };
+                if !is_action(&lvalue) {
+                    continue;
+                }
+
+                // The N64 is big-endian, so the first byte is the highest
+                let shift = (3 - (byte_addr + 0x80000000 - lvalue.addr)) * 8;
+                let (mask, value, last_line) = actions.entry(lvalue.kind.to_string()).or_default();
+                *mask |= 0xff << shift;
+                *value = (*value & !(0xff << shift)) | ((byte as u32) << shift);
+                *last_line = Some(*line);
+            }
+        }
+
+        let warnings = actions
+            .into_iter()
+            .filter(|(_, (mask, value, _))| {
+                !KNOWN_ACTIONS
+                    .iter()
+                    .any(|action| action & mask == *value & mask)
+            })
+            .filter_map(|(lvalue, (mask, value, last_line))| {
+                Some(Warning {
+                    kind: WarningKind::UnknownAction,
+                    message: format!(
+                        "{} is set to {}, which isn't a known action; the game may crash",
+                        lvalue,
+                        format_partial(mask, value)
+                    ),
+                    line: last_line?,
+                })
+            })
+            .collect();
+
+        Ok(warnings)
+    }
+}
+
+/// Check if an lvalue is the `action` field of a `MarioState`
+fn is_action(lvalue: &LeftValue) -> bool {
+    match &lvalue.kind {
+        LeftValueKind::StructField {
+            struct_,
+            field_name,
+        } => {
+            field_name == "action"
+                && matches!(&struct_.typ, Type::Struct { name } if name == "MarioState")
+        }
+        _ => false,
+    }
+}
+
+/// Format a value with some known bytes in hex, with `??` for unknown bytes,
+/// like `0x0300????`
+fn format_partial(mask: u32, value: u32) -> String {
+    let bytes = (0..4)
+        .rev()
+        .map(|i| {
+            let shift = i * 8;
+            if (mask >> shift) & 0xff == 0xff {
+                format!("{:02x}", (value >> shift) & 0xff)
+            } else {
+                String::from("??")
+            }
+        })
+        .collect::<String>();
+
+    format!("0x{}", bytes)
+}
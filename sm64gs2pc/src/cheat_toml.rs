@@ -0,0 +1,174 @@
+//! Portable TOML descriptions of cheats, for ports that load cheats from
+//! config files
+
+use crate::decomp_data::DecompData;
+use crate::decomp_data::ToPatchError;
+use crate::explain::ComparedValue;
+use crate::gameshark;
+use crate::gameshark::CodeLine;
+use crate::typ::SizeInt;
+
+use serde::Serialize;
+use snafu::ResultExt;
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+pub enum ToTomlError {
+    #[snafu(display("Cheat can't be described: {}", source))]
+    Describe { source: ToPatchError },
+
+    #[snafu(display("Cheat can't be serialized as TOML: {}", source))]
+    Serialize { source: toml::ser::Error },
+}
+
+/// A cheat described by the fields that it writes, from
+/// `DecompData::cheat_description`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheatDescription {
+    /// Name of the cheat
+    pub name: String,
+    /// Each write to a field, in the order of the code
+    pub writes: Vec<FieldWrite>,
+}
+
+/// A write to a field, from `CheatDescription`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldWrite {
+    /// C expression of the field, like `gMarioStates[0].flags`
+    pub field: String,
+    /// Value from the code, before it's shifted to its position in the field
+    pub value: u64,
+    /// Amount of bits that the value is shifted left by to write it to the
+    /// field
+    pub shift: SizeInt,
+    /// Amount of bits written, which are the only bits of the field that
+    /// change
+    pub bits: SizeInt,
+    /// Whether the write only happens while the GameShark button is held
+    pub on_button: bool,
+    /// Checks that all have to pass for the write to happen
+    pub conditions: Vec<ComparedValue>,
+}
+
+impl DecompData {
+    /// Describe a cheat by the fields that it writes, which ports can apply
+    /// without compiling C
+    ///
+    /// Each write has the checks that guard it, from
+    /// `DecompData::compared_values`. Writes that span two fields give a write
+    /// for each field. Repeaters are expanded, and master codes are skipped.
+    ///
+    /// ## Parameters
+    ///   * `name` - Name of the cheat
+    ///   * `code` - GameShark code to describe
+    ///
+    /// ## Errors
+    /// This function fails if the address of a code line can't be resolved.
+    pub fn cheat_description(
+        &self,
+        name: &str,
+        code: &gameshark::Code,
+    ) -> Result<CheatDescription, ToPatchError> {
+        let mut writes = Vec::new();
+        let mut conditions = Vec::new();
+
+        for line in &code.expand_repeats().0 {
+            let (write_size, value, on_button) = match *line {
+                CodeLine::Write8 { value, .. } => {
+                    (gameshark::ValueSize::Bits8, value as u64, false)
+                }
+                CodeLine::Write16 { value, .. } => {
+                    (gameshark::ValueSize::Bits16, value as u64, false)
+                }
+                CodeLine::Write8OnBtn { value, .. } => {
+                    (gameshark::ValueSize::Bits8, value as u64, true)
+                }
+                CodeLine::Write16OnBtn { value, .. } => {
+                    (gameshark::ValueSize::Bits16, value as u64, true)
+                }
+                // A check guards the next line, which may be another check
+                _ if line.is_check() => {
+                    conditions.extend(self.compared_values(*line)?);
+                    continue;
+                }
+                _ => {
+                    conditions.clear();
+                    continue;
+                }
+            };
+
+            let conditions = std::mem::take(&mut conditions);
+            let mut addr = line.addr() + 0x80000000;
+            let mut write_size = write_size;
+            let mut value = value;
+            loop {
+                let lvalue = self.addr_to_lvalue(addr)?;
+                match self.lvalue_get_shift(&lvalue, write_size, addr)? {
+                    // Write is entirely within one lvalue
+                    Some(shift) => {
+                        writes.push(FieldWrite {
+                            field: lvalue.kind.to_string(),
+                            value,
+                            shift,
+                            bits: write_size.num_bytes() * 8,
+                            on_button,
+                            conditions,
+                        });
+                        break;
+                    }
+
+                    // Write overlaps multiple lvalues, like in
+                    // `DecompData::format_write`
+                    None => {
+                        writes.push(FieldWrite {
+                            field: lvalue.kind.to_string(),
+                            value: value >> 8,
+                            shift: 0,
+                            bits: 8,
+                            on_button,
+                            conditions: conditions.clone(),
+                        });
+                        addr += 1;
+                        write_size = gameshark::ValueSize::Bits8;
+                        value &= 0xff;
+                    }
+                }
+            }
+        }
+
+        Ok(CheatDescription {
+            name: name.to_owned(),
+            writes,
+        })
+    }
+
+    /// Convert a GameShark code to a TOML description of the cheat, from
+    /// `DecompData::cheat_description`
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark;
+    ///
+    /// let code = "8133B176 0015".parse::<gameshark::Code>().unwrap();
+    /// let toml = sm64gs2pc::DECOMP_DATA_STATIC
+    ///     .gs_code_to_toml("Always have Metal Cap", &code)
+    ///     .unwrap();
+    ///
+    /// assert!(toml.starts_with("name = \"Always have Metal Cap\"\n"));
+    /// ```
+    ///
+    /// ## Parameters
+    ///   * `name` - Name of the cheat
+    ///   * `code` - GameShark code to convert
+    ///
+    /// ## Errors
+    /// This function fails if the address of a code line can't be resolved,
+    /// or if the description can't be serialized.
+    pub fn gs_code_to_toml(
+        &self,
+        name: &str,
+        code: &gameshark::Code,
+    ) -> Result<String, ToTomlError> {
+        let description = self.cheat_description(name, code).context(DescribeSnafu)?;
+        toml::to_string(&description).context(SerializeSnafu)
+    }
+}
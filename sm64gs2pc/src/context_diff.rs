@@ -0,0 +1,153 @@
+//! Formatting patches as context diffs
+//!
+//! Context diffs are an older format than unified diffs, which some tools
+//! still expect. Each hunk lists the old lines, then the new lines:
+//!
+//! ```text
+//! *** a/file
+//! --- b/file
+//! ***************
+//! *** 4,5 ****
+//! --- 4,6 ----
+//!   context
+//! + added
+//!   context
+//! ```
+
+use std::fmt::Write;
+
+/// Format a patch as a context diff
+///
+/// Like the `Display` implementation of `patch::Patch`, the output doesn't end
+/// with a newline.
+pub(crate) fn format_context_diff(patch: &patch::Patch) -> String {
+    let mut out = String::new();
+
+    writeln!(out, "*** {}", patch.old.path).unwrap();
+    write!(out, "--- {}", patch.new.path).unwrap();
+
+    for hunk in &patch.hunks {
+        // Removed and added lines next to each other are changed lines
+        let mut is_change = vec![false; hunk.lines.len()];
+        let mut run_start = 0;
+        for i in 0..=hunk.lines.len() {
+            if i < hunk.lines.len() && !matches!(hunk.lines[i], patch::Line::Context(_)) {
+                continue;
+            }
+
+            let run = &hunk.lines[run_start..i];
+            let has_removal = run
+                .iter()
+                .any(|line| matches!(line, patch::Line::Remove(_)));
+            let has_addition = run.iter().any(|line| matches!(line, patch::Line::Add(_)));
+            if has_removal && has_addition {
+                is_change[run_start..i].fill(true);
+            }
+            run_start = i + 1;
+        }
+
+        write!(out, "\n***************").unwrap();
+
+        // Old lines, which are only listed if some were removed
+        write!(out, "\n*** {} ****", format_range(&hunk.old_range)).unwrap();
+        if hunk
+            .lines
+            .iter()
+            .any(|line| matches!(line, patch::Line::Remove(_)))
+        {
+            for (i, line) in hunk.lines.iter().enumerate() {
+                match line {
+                    patch::Line::Context(line) => write!(out, "\n  {}", line),
+                    patch::Line::Remove(line) if is_change[i] => write!(out, "\n! {}", line),
+                    patch::Line::Remove(line) => write!(out, "\n- {}", line),
+                    patch::Line::Add(_) => Ok(()),
+                }
+                .unwrap();
+            }
+        }
+
+        // New lines, which are only listed if some were added
+        write!(out, "\n--- {} ----", format_range(&hunk.new_range)).unwrap();
+        if hunk
+            .lines
+            .iter()
+            .any(|line| matches!(line, patch::Line::Add(_)))
+        {
+            for (i, line) in hunk.lines.iter().enumerate() {
+                match line {
+                    patch::Line::Context(line) => write!(out, "\n  {}", line),
+                    patch::Line::Add(line) if is_change[i] => write!(out, "\n! {}", line),
+                    patch::Line::Add(line) => write!(out, "\n+ {}", line),
+                    patch::Line::Remove(_) => Ok(()),
+                }
+                .unwrap();
+            }
+        }
+    }
+
+    if !patch.end_newline {
+        write!(out, "\n\\ No newline at end of file").unwrap();
+    }
+
+    out
+}
+
+/// Format the range of lines in a hunk, like `4,6`, or `4` if it's one line
+fn format_range(range: &patch::Range) -> String {
+    match range.count {
+        // An empty range is written as the line before it
+        0 | 1 => format!("{}", range.start),
+        count => format!("{},{}", range.start, range.start + count - 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::borrow::Cow;
+
+    #[test]
+    fn test_format_context_diff() {
+        let patch = patch::Patch {
+            old: patch::File {
+                path: Cow::from("a/f.c"),
+                meta: None,
+            },
+            new: patch::File {
+                path: Cow::from("b/f.c"),
+                meta: None,
+            },
+            hunks: vec![patch::Hunk {
+                old_range: patch::Range { start: 1, count: 4 },
+                new_range: patch::Range { start: 1, count: 4 },
+                lines: vec![
+                    patch::Line::Context("a"),
+                    patch::Line::Remove("b"),
+                    patch::Line::Add("B"),
+                    patch::Line::Context("c"),
+                    patch::Line::Remove("d"),
+                    patch::Line::Add("e"),
+                ],
+            }],
+            end_newline: true,
+        };
+
+        assert_eq!(
+            format_context_diff(&patch),
+            "*** a/f.c
+--- b/f.c
+***************
+*** 1,4 ****
+  a
+! b
+  c
+! d
+--- 1,4 ----
+  a
+! B
+  c
+! e"
+        );
+    }
+}
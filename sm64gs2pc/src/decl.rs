@@ -7,7 +7,7 @@ use serde::Deserialize;
 use serde::Serialize;
 
 /// A kind of C declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DeclKind {
     // A function
     Fn,
@@ -20,7 +20,7 @@ pub enum DeclKind {
 }
 
 /// A C declaration
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Decl {
     /// The kind of declaration
     pub kind: DeclKind,
@@ -5,22 +5,41 @@ use crate::decl::DeclKind;
 use crate::gameshark;
 use crate::left_value::LeftValue;
 use crate::left_value::LeftValueKind;
+use crate::options::PatchOptions;
+use crate::rom::RomSegment;
+use crate::target::HOOK;
+use crate::typ::ScalarInfo;
 use crate::typ::SizeInt;
 use crate::typ::Struct;
 use crate::typ::Type;
+#[cfg(feature = "loader")]
+use crate::version::Version;
 
 use std::borrow::Cow;
 use std::collections::BTreeMap;
-use std::collections::HashMap;
+use std::collections::BTreeSet;
 use std::iter::once;
 #[cfg(feature = "loader")]
 use std::path::Path;
 
 use serde::Deserialize;
 use serde::Serialize;
+use snafu::ensure;
 use snafu::OptionExt;
 use snafu::Snafu;
 
+/// Memory regions that the game uses but that have no symbols in the
+/// decompilation, as `(start, end, description)`
+///
+/// Writes to these can't be converted, since the PC ports don't lay out memory
+/// like the N64. They're emitted as commented-out raw writes instead.
+const UNMAPPED_REGIONS: &[(SizeInt, SizeInt, &str)] = &[
+    (0x80000000, 0x80000400, "N64 OS low memory"),
+    (0x80025c00, 0x8016f000, "main memory pool"),
+    (0x8038f800, 0x80400000, "framebuffers"),
+    (0x80400000, 0x80800000, "Expansion Pak memory"),
+];
+
 /// Symbol data from the [Super Mario 64 decompilation][1]
 ///
 /// This information is used for converting GameShark codes to PC port patches.
@@ -28,12 +47,36 @@ use snafu::Snafu;
 /// can be accessed at `DECOMP_DATA_STATIC`.
 ///
 /// [1]: https://github.com/n64decomp/sm64
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DecompData {
     /// Map from memory addresses to declarations
-    decls: BTreeMap<SizeInt, Decl>,
+    pub(crate) decls: BTreeMap<SizeInt, Decl>,
     /// Map from struct names to structs
-    structs: HashMap<String, Struct>,
+    ///
+    /// This is sorted, so serializations of the same data are identical.
+    pub(crate) structs: BTreeMap<String, Struct>,
+    /// ROM segments that are loaded to fixed RAM addresses
+    pub(crate) rom_segments: Vec<RomSegment>,
+    /// Map from memory addresses to names of declarations that were shadowed
+    /// by the declaration in `decls` at the same address
+    pub(crate) shadowed_names: BTreeMap<SizeInt, Vec<String>>,
+    /// Map from names of anonymous symbols, like `D_8033B170`, to readable
+    /// names used in comments and explanations
+    pub(crate) friendly_names: BTreeMap<String, String>,
+    /// Map from names of `const` pointers to the names of the symbols that
+    /// they're initialized to point to, like `&gMarioStates`
+    pub(crate) pointer_targets: BTreeMap<String, String>,
+}
+
+/// Counts of the data in a `DecompData`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DecompStats {
+    /// Amount of function declarations
+    pub num_fns: usize,
+    /// Amount of variable declarations
+    pub num_vars: usize,
+    /// Amount of structs
+    pub num_structs: usize,
 }
 
 #[derive(Debug, Clone, Snafu)]
@@ -61,6 +104,52 @@ pub enum ToPatchError {
 
     #[snafu(display("{:#x}: Code assigns to a pointer", addr))]
     PointerAssign { addr: SizeInt },
+
+    #[snafu(display("{:#x}: Address is in the {}, which has no symbols", addr, region))]
+    UnmappedRegion { addr: SizeInt, region: &'static str },
+
+    #[snafu(display("{:#x}: Code assigns to a whole struct, not one of its fields", addr))]
+    StructAssign { addr: SizeInt },
+
+    #[snafu(display("Code expands to more than {} operations", limit))]
+    ExpansionTooLarge { limit: usize },
+
+    #[snafu(display(
+        "{:#x}: Address is not in a segment loaded from ROM to a fixed address",
+        addr
+    ))]
+    NotStaticallyLocated { addr: SizeInt },
+
+    #[snafu(display("{:#x}: Checks can't be converted to ROM writes", addr))]
+    RomCheck { addr: SizeInt },
+
+    #[snafu(display("Self-contained patches don't define `{}`", name))]
+    SelfContainedUndefined { name: String },
+}
+
+impl ToPatchError {
+    /// Get a stable, machine-readable code for the kind of error, like
+    /// `"pointer-assign"`
+    ///
+    /// Unlike the display text, this doesn't change between versions, so
+    /// programs can handle kinds of errors without matching text.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ToPatchError::FnPatch { .. } => "fn-patch",
+            ToPatchError::IgnoredType => "ignored-type",
+            ToPatchError::NoDecl { .. } => "no-decl",
+            ToPatchError::NoStruct { .. } => "no-struct",
+            ToPatchError::NoField { .. } => "no-field",
+            ToPatchError::ArrayOutOfBounds { .. } => "array-oob",
+            ToPatchError::PointerAssign { .. } => "pointer-assign",
+            ToPatchError::UnmappedRegion { .. } => "unmapped-region",
+            ToPatchError::StructAssign { .. } => "struct-assign",
+            ToPatchError::ExpansionTooLarge { .. } => "expansion-too-large",
+            ToPatchError::NotStaticallyLocated { .. } => "not-statically-located",
+            ToPatchError::RomCheck { .. } => "rom-check",
+            ToPatchError::SelfContainedUndefined { .. } => "self-contained-undefined",
+        }
+    }
 }
 
 impl DecompData {
@@ -80,6 +169,24 @@ impl DecompData {
     /// This function panics if any of its operations fail.
     #[cfg(feature = "loader")]
     pub fn load(base_rom: &Path, repo: &Path) -> Self {
+        Self::load_version(base_rom, repo, Version::Us)
+    }
+
+    /// Load a version of the game from the SM64 decompilation codebase
+    ///
+    /// This works like `DecompData::load`, but builds `version` instead of the
+    /// US version.
+    ///
+    /// ## Parameters
+    ///   * `base_rom` - Path to a base ROM of `version`, like
+    ///     `baserom.eu.z64`
+    ///   * `repo` - Path where the SM64 decompilation repo should be cloned
+    ///   * `version` - Version of the game to load
+    ///
+    /// ## Panics
+    /// This function panics if any of its operations fail.
+    #[cfg(feature = "loader")]
+    pub fn load_version(base_rom: &Path, repo: &Path, version: Version) -> Self {
         use std::ffi::OsStr;
         use std::fs::File;
         use std::io::BufRead;
@@ -105,20 +212,31 @@ impl DecompData {
         }
 
         // Copy ROM into repo
-        std::fs::copy(base_rom, repo.join("baserom.us.z64")).unwrap();
+        std::fs::copy(
+            base_rom,
+            repo.join(format!("baserom.{}.z64", version.name())),
+        )
+        .unwrap();
 
         // Compile code
         assert!(Command::new("make")
+            .arg(format!("VERSION={}", version.name()))
             .current_dir(&repo)
             .status()
             .unwrap()
             .success());
 
+        let build_dir = repo.join("build").join(version.name());
+        let version_define = format!("-DVERSION_{}", version.name().to_uppercase());
+
         // Map from symbol name to address
         let mut syms = BTreeMap::<String, SizeInt>::new();
 
+        // Segments loaded from ROM to fixed RAM addresses
+        let mut rom_segments = Vec::<RomSegment>::new();
+
         // Iterate over `.map` files
-        for entry in WalkDir::new(repo.join("build/us")) {
+        for entry in WalkDir::new(&build_dir) {
             let entry = entry.unwrap();
             let path = entry.path();
             if path.extension() != Some(OsStr::new("map")) {
@@ -130,6 +248,34 @@ impl DecompData {
             let file = BufReader::new(file);
             for line in file.lines() {
                 let line = line.unwrap();
+
+                // Load segment, like
+                // `.main 0x0000000080246000 0xf4580 load address 0x0000000000001000`
+                if let [name, ram_start, size, "load", "address", rom_start] =
+                    *line.split_whitespace().collect::<Vec<&str>>().as_slice()
+                {
+                    let parse = |s: &str| {
+                        s.strip_prefix("0x")
+                            .and_then(|s| u64::from_str_radix(s, 0x10).ok())
+                    };
+
+                    // Zeroed memory isn't loaded from ROM
+                    let is_loaded = !name.ends_with("noload") && !name.ends_with("bss");
+
+                    if let (true, Some(ram_start), Some(size), Some(rom_start)) =
+                        (is_loaded, parse(ram_start), parse(size), parse(rom_start))
+                    {
+                        if size > 0 && ram_start >= 0x80000000 {
+                            rom_segments.push(RomSegment {
+                                name: name.to_owned(),
+                                ram_start: ram_start as SizeInt,
+                                ram_end: (ram_start + size) as SizeInt,
+                                rom_start: rom_start as SizeInt,
+                            });
+                        }
+                    }
+                }
+
                 let items = line.split("                ").collect::<Vec<&str>>();
 
                 // Load symbol and address
@@ -154,7 +300,10 @@ impl DecompData {
             }
         }
 
-        let mut decomp_data = DecompData::default();
+        let mut decomp_data = DecompData {
+            rom_segments,
+            ..Default::default()
+        };
 
         let ctx = clang::Clang::new().unwrap();
         let index = clang::Index::new(&ctx, false, true);
@@ -193,7 +342,7 @@ impl DecompData {
                     "-nostdinc",
                     "-nostdlib",
                     "-fno-builtin",
-                    "-DVERSION_US",
+                    version_define.as_str(),
                     "-DF3D_OLD",
                     "-DTARGET_N64",
                     "-D_LANGUAGE_C",
@@ -205,9 +354,9 @@ impl DecompData {
                     "-I",
                     repo.join("include/libc").to_str().unwrap(),
                     "-I",
-                    repo.join("build/us").to_str().unwrap(),
+                    build_dir.to_str().unwrap(),
                     "-I",
-                    repo.join("build/us/include").to_str().unwrap(),
+                    build_dir.join("include").to_str().unwrap(),
                     "-I",
                     repo.join("src").to_str().unwrap(),
                     "-I",
@@ -245,8 +394,33 @@ impl DecompData {
                     },
                     _ => unimplemented!("clang entity: {:?}", entity),
                 };
+
+                // Track `const` pointers to named symbols, like
+                // `struct MarioState *const gMarioState = &gMarioStates[0];`
+                let is_const_pointer = entity.get_type().is_some_and(|typ| {
+                    typ.get_kind() == clang::TypeKind::Pointer && typ.is_const_qualified()
+                });
+                if is_const_pointer {
+                    if let Some(target) = entity
+                        .get_children()
+                        .last()
+                        .and_then(|init| pointer_target(*init))
+                    {
+                        decomp_data.pointer_targets.insert(name.clone(), target);
+                    }
+                }
+
                 let decl = Decl { kind, name, addr };
-                decomp_data.decls.insert(addr, decl);
+
+                // Keep the names of other declarations at the same address
+                if let Some(old_decl) = decomp_data.decls.insert(addr, decl) {
+                    let names = decomp_data.shadowed_names.entry(addr).or_default();
+                    if old_decl.name != decomp_data.decls[&addr].name
+                        && !names.contains(&old_decl.name)
+                    {
+                        names.push(old_decl.name);
+                    }
+                }
             }
 
             // Iterate over structs in C file
@@ -260,6 +434,108 @@ impl DecompData {
         decomp_data
     }
 
+    /// Count the loaded declarations and structs
+    ///
+    /// This helps confirm that the data loaded fully.
+    ///
+    /// ```
+    /// let stats = sm64gs2pc::DECOMP_DATA_STATIC.stats();
+    /// assert!(stats.num_vars > 0);
+    /// ```
+    pub fn stats(&self) -> DecompStats {
+        let num_fns = self
+            .decls
+            .values()
+            .filter(|decl| matches!(decl.kind, DeclKind::Fn))
+            .count();
+
+        DecompStats {
+            num_fns,
+            num_vars: self.decls.len() - num_fns,
+            num_structs: self.structs.len(),
+        }
+    }
+
+    /// Get the name and amount of fields of the largest struct, by size in
+    /// bytes
+    ///
+    /// This helps sanity check regenerated data, since large structs, like
+    /// `SaveBuffer`, should be loaded. Structs whose size is unknown, because
+    /// they have ignored fields, are skipped. Of structs with the same size,
+    /// the first by name is the largest.
+    ///
+    /// ```
+    /// let (name, num_fields) = sm64gs2pc::DECOMP_DATA_STATIC.largest_struct().unwrap();
+    /// assert!(num_fields > 0);
+    /// ```
+    ///
+    /// ## Return values
+    ///   * `Some((name, num_fields))` - The largest struct
+    ///   * `None` - No struct has a known size
+    pub fn largest_struct(&self) -> Option<(&str, usize)> {
+        self.structs
+            .iter()
+            .rev()
+            .filter_map(|(name, struct_)| {
+                let size = self.size_of_struct(struct_).ok()?;
+                Some((size, name.as_str(), struct_.fields.len()))
+            })
+            .max_by_key(|(size, _, _)| *size)
+            .map(|(_, name, num_fields)| (name, num_fields))
+    }
+
+    /// Find struct names that are referenced but have no loaded struct
+    ///
+    /// Every named struct reachable from a variable declaration or a struct
+    /// field should be in the struct map, otherwise converting a code that
+    /// touches it fails with `ToPatchError::NoStruct`. Pointee types aren't
+    /// checked, because pointers are never followed during conversion.
+    ///
+    /// Returns the dangling struct names, sorted and without duplicates.
+    pub fn validate_references(&self) -> Vec<String> {
+        let mut dangling = BTreeSet::new();
+
+        let decl_types = self.decls.values().filter_map(|decl| match &decl.kind {
+            DeclKind::Fn => None,
+            DeclKind::Var { typ } => Some(typ),
+        });
+        let field_types = self
+            .structs
+            .values()
+            .flat_map(|struct_| struct_.fields.iter().map(|field| &field.typ));
+
+        for typ in decl_types.chain(field_types) {
+            self.collect_dangling_structs(typ, &mut dangling);
+        }
+
+        dangling.into_iter().collect()
+    }
+
+    /// Add names of structs referenced by `typ` that aren't loaded to
+    /// `dangling`
+    fn collect_dangling_structs(&self, typ: &Type, dangling: &mut BTreeSet<String>) {
+        match typ {
+            Type::AnonStruct(struct_) => {
+                for field in &struct_.fields {
+                    self.collect_dangling_structs(&field.typ, dangling);
+                }
+            }
+            Type::Struct { name } => {
+                if !self.structs.contains_key(name) {
+                    dangling.insert(name.clone());
+                }
+            }
+            Type::Array { element_type, .. } => {
+                self.collect_dangling_structs(element_type, dangling)
+            }
+            Type::Int { .. }
+            | Type::Pointer { .. }
+            | Type::Float
+            | Type::Double
+            | Type::Ignored => {}
+        }
+    }
+
     /// Get the size of the type `typ` in bytes
     ///
     /// ## Errors
@@ -280,8 +556,10 @@ impl DecompData {
                 .size_of_type(element_type)
                 .map(|size| size * num_elements),
             Type::Int { num_bytes, .. } => Ok(*num_bytes),
-            Type::Pointer { .. } => Ok(8),
+            // The N64 is 32-bit, and the data is loaded with `-m32`
+            Type::Pointer { .. } => Ok(4),
             Type::Float => Ok(4),
+            Type::Double => Ok(8),
             Type::Ignored => Err(ToPatchError::IgnoredType),
         }
     }
@@ -302,18 +580,81 @@ impl DecompData {
             .sum()
     }
 
+    /// Get the address of a symbol, like `gMarioStates`, from its name
+    ///
+    /// This is the reverse of `resolve_address` for whole declarations, so
+    /// only bare symbol names match, not expressions like
+    /// `gMarioStates[0].flags`.
+    ///
+    /// ```
+    /// assert_eq!(
+    ///     sm64gs2pc::DECOMP_DATA_STATIC.symbol_address("gMarioStates"),
+    ///     Some(0x8033B170),
+    /// );
+    /// ```
+    pub fn symbol_address(&self, name: &str) -> Option<SizeInt> {
+        self.decls
+            .values()
+            .find(|decl| decl.name == name)
+            .map(|decl| decl.addr)
+    }
+
+    /// Get the name and address of each symbol, sorted by address
+    pub fn symbols(&self) -> impl Iterator<Item = (&str, SizeInt)> {
+        self.decls
+            .values()
+            .map(|decl| (decl.name.as_str(), decl.addr))
+    }
+
+    /// Get the last declaration that starts at or before the address, which
+    /// is the declaration containing it if any does
+    fn decl_before(&self, addr: SizeInt) -> Option<&Decl> {
+        self.decls.range(..=addr).next_back().map(|(_, decl)| decl)
+    }
+
+    /// Get the lvalue of the scalar at an address, without creating a patch
+    ///
+    /// The lvalue displays as its C expression, and has the scalar's type.
+    ///
+    /// ```
+    /// use sm64gs2pc::Type;
+    ///
+    /// let lvalue = sm64gs2pc::DECOMP_DATA_STATIC
+    ///     .resolve_address(0x8033B176)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(lvalue.to_string(), "gMarioStates[0].flags");
+    /// assert_eq!(
+    ///     lvalue.typ,
+    ///     Type::Int {
+    ///         signed: false,
+    ///         num_bytes: 4,
+    ///     }
+    /// );
+    /// ```
+    ///
+    /// ## Errors
+    /// This function fails if the address doesn't resolve to a scalar, like
+    /// an address in a function or in memory without symbols.
+    pub fn resolve_address(&self, addr: SizeInt) -> Result<LeftValue, ToPatchError> {
+        self.addr_to_lvalue(addr)
+    }
+
     /// Get the lvalue corresponding to the address
     ///
     /// For example, if `addr` is `0x8033B176`, the lvalue is
     /// `gMarioStates[0].flags`.
-    fn addr_to_lvalue(&self, addr: SizeInt) -> Result<LeftValue, ToPatchError> {
+    pub(crate) fn addr_to_lvalue(&self, addr: SizeInt) -> Result<LeftValue, ToPatchError> {
+        // Check for memory that isn't covered by symbols
+        if let Some((_, _, region)) = UNMAPPED_REGIONS
+            .iter()
+            .find(|(start, end, _)| (*start..*end).contains(&addr))
+        {
+            return Err(ToPatchError::UnmappedRegion { addr, region });
+        }
+
         // Get the declaration containing the address
-        let decl = self
-            .decls
-            .values()
-            .rev()
-            .find(|decl| decl.addr <= addr)
-            .context(NoDeclSnafu { addr })?;
+        let decl = self.decl_before(addr).context(NoDeclSnafu { addr })?;
 
         // Get the declaration's type
         let typ = match &decl.kind {
@@ -334,7 +675,139 @@ impl DecompData {
             addr: decl.addr,
         };
 
-        self.addr_accum_to_lvalue(accum, addr, decl.addr)
+        let lvalue = self.addr_accum_to_lvalue(accum, addr, decl.addr)?;
+
+        // Only scalars can be written or read by a code line
+        match lvalue.typ {
+            Type::Int { .. } | Type::Float | Type::Double => Ok(lvalue),
+            _ => Err(ToPatchError::StructAssign { addr }),
+        }
+    }
+
+    /// Get the lvalue that a write to the address changes
+    ///
+    /// This is like `DecompData::addr_to_lvalue`, but if
+    /// `options.resolve_const_pointers` is set, writes to a `const` pointer to
+    /// a known symbol resolve through the pointer, at the same offset in the
+    /// symbol it points to.
+    fn addr_to_written_lvalue(
+        &self,
+        addr: SizeInt,
+        options: &PatchOptions,
+    ) -> Result<LeftValue, ToPatchError> {
+        if !options.resolve_const_pointers {
+            return self.addr_to_lvalue(addr);
+        }
+
+        let decl = self.decl_before(addr).context(NoDeclSnafu { addr })?;
+        let target = match &decl.kind {
+            DeclKind::Var {
+                typ: typ @ Type::Pointer { .. },
+            } if addr - decl.addr < self.size_of_type(typ)? => self
+                .pointer_targets
+                .get(&decl.name)
+                .and_then(|target| self.decls.values().find(|decl| &decl.name == target))
+                .filter(|target| {
+                    !matches!(
+                        &target.kind,
+                        DeclKind::Var {
+                            typ: Type::Pointer { .. }
+                        }
+                    )
+                }),
+            _ => None,
+        };
+
+        match target {
+            Some(target) => {
+                let lvalue = self.addr_to_lvalue(target.addr + (addr - decl.addr))?;
+                Ok(lvalue.rebased(target.addr, decl.addr))
+            }
+            None => self.addr_to_lvalue(addr),
+        }
+    }
+
+    /// Get the width and kind of the scalar at an address
+    ///
+    /// This is the scalar that contains the address, so an address in the
+    /// middle of an integer gives the whole integer.
+    ///
+    /// ```
+    /// let info = sm64gs2pc::DECOMP_DATA_STATIC
+    ///     .scalar_info_at(0x8033B1BC)
+    ///     .unwrap();
+    ///
+    /// assert!(info.is_float);
+    /// assert_eq!(info.num_bytes, 4);
+    /// ```
+    ///
+    /// ## Errors
+    /// This function fails if the address doesn't resolve to a scalar.
+    pub fn scalar_info_at(&self, addr: SizeInt) -> Result<ScalarInfo, ToPatchError> {
+        let lvalue = self.addr_to_lvalue(addr)?;
+
+        match lvalue.typ {
+            Type::Int { signed, num_bytes } => Ok(ScalarInfo {
+                signed,
+                num_bytes,
+                is_float: false,
+            }),
+            Type::Float => Ok(ScalarInfo {
+                signed: true,
+                num_bytes: 4,
+                is_float: true,
+            }),
+            Type::Double => Ok(ScalarInfo {
+                signed: true,
+                num_bytes: 8,
+                is_float: true,
+            }),
+            _ => Err(ToPatchError::StructAssign { addr }),
+        }
+    }
+
+    /// Get the names of all declarations at exactly an address
+    ///
+    /// Several symbols can share an address, like a variable and an alias of
+    /// it. An lvalue resolved at such an address uses the first name, so the
+    /// others mean the lvalue is ambiguous.
+    ///
+    /// ## Return values
+    /// The name of the declaration used to resolve lvalues, followed by the
+    /// names of any other declarations at the address. This is empty if no
+    /// declaration starts at the address.
+    pub fn aliases_at(&self, addr: SizeInt) -> Vec<&str> {
+        let decl_name = self.decls.get(&addr).map(|decl| decl.name.as_str());
+        let shadowed_names = self
+            .shadowed_names
+            .get(&addr)
+            .into_iter()
+            .flatten()
+            .map(String::as_str);
+
+        decl_name.into_iter().chain(shadowed_names).collect()
+    }
+
+    /// Give a symbol a readable name, like for anonymous data such as
+    /// `D_8033B170`
+    ///
+    /// Verbose comments and explanations use the readable name instead of
+    /// the symbol's name. The generated C source still uses the symbol's name,
+    /// since that's what the port defines. By default, symbols have no
+    /// readable names.
+    ///
+    /// ## Parameters
+    ///   * `name` - Name of the symbol
+    ///   * `friendly_name` - Readable name of the symbol
+    pub fn set_friendly_name(&mut self, name: &str, friendly_name: &str) {
+        self.friendly_names
+            .insert(name.to_owned(), friendly_name.to_owned());
+    }
+
+    /// Format an lvalue for comments and explanations, with its symbol's
+    /// readable name if it has one
+    pub(crate) fn friendly_lvalue(&self, lvalue: &LeftValue) -> String {
+        lvalue.renamed(&self.friendly_names).kind.to_string()
     }
 
     fn addr_and_struct_to_lvalue(
@@ -344,6 +817,11 @@ impl DecompData {
         struct_: &Struct,
         accum_addr: SizeInt,
     ) -> Result<LeftValue, ToPatchError> {
+        // A struct without fields has nothing to resolve to
+        if struct_.fields.is_empty() {
+            return Ok(accum);
+        }
+
         let field = struct_
             .fields
             .iter()
@@ -381,12 +859,18 @@ impl DecompData {
                 let struct_ = self.structs.get(&name).context(NoStructSnafu { name })?;
                 self.addr_and_struct_to_lvalue(accum, addr, struct_, accum_addr)
             }
-            Type::Int { .. } | Type::Float => Ok(accum),
+            Type::Int { .. } | Type::Float | Type::Double => Ok(accum),
             Type::Array {
                 element_type,
                 num_elements,
             } => {
                 let element_type_size = self.size_of_type(&element_type)?;
+
+                // Elements without size, like empty structs, have nothing to
+                // resolve to
+                if element_type_size == 0 {
+                    return Ok(accum);
+                }
                 let index = (addr - accum_addr) / element_type_size;
 
                 if index >= num_elements {
@@ -415,124 +899,1024 @@ impl DecompData {
     }
 
     /// Convert a GameShark code line to a line of C source code
-    fn gs_line_to_c(&self, code: gameshark::CodeLine) -> Result<String, ToPatchError> {
+    ///
+    /// ## Parameters
+    ///   * `code` - GameShark code line to convert
+    ///   * `options` - Conversion options
+    ///   * `constants` - Names and values of constants defined so far, which
+    ///     written values are added to if `options.named_constants` is set
+    pub(crate) fn gs_line_to_c(
+        &self,
+        code: gameshark::CodeLine,
+        options: &PatchOptions,
+        constants: &mut Vec<(String, u64)>,
+    ) -> Result<String, ToPatchError> {
         let addr = code.addr() + 0x80000000;
 
-        let c_source = match code {
-            gameshark::CodeLine::Write8 { value, .. } => {
-                self.format_write(gameshark::ValueSize::Bits8, value as u64, addr)
-            }
-            gameshark::CodeLine::Write16 { value, .. } => {
-                self.format_write(gameshark::ValueSize::Bits16, value as u64, addr)
+        // Button-activated writes are writes guarded by the button
+        let (write, on_button) = match code {
+            gameshark::CodeLine::Write8OnBtn { addr, value } => {
+                (gameshark::CodeLine::Write8 { addr, value }, true)
             }
-            gameshark::CodeLine::IfEq8 { value, .. } => {
-                self.format_check(gameshark::ValueSize::Bits8, value as u64, addr, true)
+            gameshark::CodeLine::Write16OnBtn { addr, value } => {
+                (gameshark::CodeLine::Write16 { addr, value }, true)
             }
-            gameshark::CodeLine::IfEq16 { value, .. } => {
-                self.format_check(gameshark::ValueSize::Bits16, value as u64, addr, true)
+            code => (code, false),
+        };
+
+        let c_source = match write {
+            gameshark::CodeLine::Write8 { value, .. } => self.format_write(
+                gameshark::ValueSize::Bits8,
+                value as u64,
+                addr,
+                options,
+                constants,
+            ),
+            gameshark::CodeLine::Write16 { value, .. } => self.format_write(
+                gameshark::ValueSize::Bits16,
+                value as u64,
+                addr,
+                options,
+                constants,
+            ),
+            gameshark::CodeLine::Write8OnBtn { .. } | gameshark::CodeLine::Write16OnBtn { .. } => {
+                unreachable!("button-activated write wasn't converted to a write")
             }
-            gameshark::CodeLine::IfNotEq8 { value, .. } => {
-                self.format_check(gameshark::ValueSize::Bits8, value as u64, addr, false)
+            gameshark::CodeLine::Repeat { .. } => unreachable!("repeater wasn't expanded"),
+            // Master codes configure the GameShark, so there's nothing to do.
+            // This is an empty statement, so a check before it doesn't guard
+            // the next line.
+            gameshark::CodeLine::Master { .. } => Ok(String::from("/* ignored: master code */;")),
+            gameshark::CodeLine::IfEq8 { value, .. } => self.format_check(
+                gameshark::ValueSize::Bits8,
+                value as u64,
+                addr,
+                true,
+                options,
+            ),
+            gameshark::CodeLine::IfEq16 { value, .. } => self.format_check(
+                gameshark::ValueSize::Bits16,
+                value as u64,
+                addr,
+                true,
+                options,
+            ),
+            gameshark::CodeLine::IfNotEq8 { value, .. } => self.format_check(
+                gameshark::ValueSize::Bits8,
+                value as u64,
+                addr,
+                false,
+                options,
+            ),
+            gameshark::CodeLine::IfNotEq16 { value, .. } => self.format_check(
+                gameshark::ValueSize::Bits16,
+                value as u64,
+                addr,
+                false,
+                options,
+            ),
+        };
+
+        // Keep writes to memory without symbols as a commented-out raw write,
+        // so the rest of the code can still be converted. Checks can't be
+        // commented out, since that would make the next line unconditional.
+        let c_source = match (c_source, write) {
+            (
+                Err(ToPatchError::UnmappedRegion { region, .. }),
+                gameshark::CodeLine::Write8 { value, .. },
+            ) => Ok(format_unmapped_write(region, "uint8_t", value as u64, addr)),
+            (
+                Err(ToPatchError::UnmappedRegion { region, .. }),
+                gameshark::CodeLine::Write16 { value, .. },
+            ) => Ok(format_unmapped_write(
+                region,
+                "uint16_t",
+                value as u64,
+                addr,
+            )),
+            // Write the bytes of a symbol whose type isn't supported
+            (Err(ToPatchError::IgnoredType), gameshark::CodeLine::Write8 { value, .. })
+                if options.raw_ignored_writes =>
+            {
+                self.format_ignored_write(&[value], addr)
             }
-            gameshark::CodeLine::IfNotEq16 { value, .. } => {
-                self.format_check(gameshark::ValueSize::Bits16, value as u64, addr, false)
+            (Err(ToPatchError::IgnoredType), gameshark::CodeLine::Write16 { value, .. })
+                if options.raw_ignored_writes =>
+            {
+                self.format_ignored_write(&value.to_be_bytes(), addr)
             }
+            (c_source, _) => c_source,
         }?;
 
-        let c_source = format!("/* {} */ {}", code, c_source);
-        Ok(c_source)
+        // Commented-out writes don't need the guard, which would guard the
+        // next line instead
+        let c_source = if on_button && !c_source.starts_with("/*") {
+            format!("if ({}) {}", options.target.button_check, c_source)
+        } else {
+            c_source
+        };
+
+        Ok(format!("{}{}", self.line_comment(code, options), c_source))
     }
 
-    /// Convert GameShark code to a patch in the unified diff format
+    /// Convert a chain of GameShark checks, each guarding the next, to one
+    /// line of C source code that checks all of their conditions
+    ///
+    /// For example, two checks become `if (... && ...)`. The line starts with
+    /// the comment of each check.
     ///
     /// ## Parameters
-    ///   * `name` - Name of cheat to be included in comment in patch
-    ///   * `code` - GameShark code to convert
-    pub fn gs_code_to_patch(
+    ///   * `checks` - Checks to convert
+    ///   * `options` - Conversion options
+    fn gs_checks_to_c(
         &self,
-        name: &str,
-        code: gameshark::Code,
+        checks: &[gameshark::CodeLine],
+        options: &PatchOptions,
     ) -> Result<String, ToPatchError> {
-        // Comment with name of cheat
-        let name_comment = format!("    /* {} */", name);
+        let mut comments = String::new();
+        let mut conditions = Vec::new();
+
+        for &code in checks {
+            let addr = code.addr() + 0x80000000;
+            let (read_size, value, check_eq) = match code {
+                gameshark::CodeLine::IfEq8 { value, .. } => {
+                    (gameshark::ValueSize::Bits8, value as u64, true)
+                }
+                gameshark::CodeLine::IfEq16 { value, .. } => {
+                    (gameshark::ValueSize::Bits16, value as u64, true)
+                }
+                gameshark::CodeLine::IfNotEq8 { value, .. } => {
+                    (gameshark::ValueSize::Bits8, value as u64, false)
+                }
+                gameshark::CodeLine::IfNotEq16 { value, .. } => {
+                    (gameshark::ValueSize::Bits16, value as u64, false)
+                }
+                gameshark::CodeLine::Write8 { .. }
+                | gameshark::CodeLine::Write16 { .. }
+                | gameshark::CodeLine::Write8OnBtn { .. }
+                | gameshark::CodeLine::Write16OnBtn { .. }
+                | gameshark::CodeLine::Repeat { .. }
+                | gameshark::CodeLine::Master { .. } => {
+                    unreachable!("write in chain of checks")
+                }
+            };
 
-        // Added C source code cheat lines
-        let cheat_lines = code
-            .0
-            .into_iter()
-            .map(|code_line| {
-                // Convert to C and indent
-                let line = self.gs_line_to_c(code_line)?;
-                let line = format!("    {}", line);
-                Ok(line)
-            })
-            // Have to create owned `String`s since `patch::Line` requires
-            // `&str` which needs an owned value to reference
-            .collect::<Result<Vec<String>, ToPatchError>>()?;
+            comments.push_str(&self.line_comment(code, options));
+            conditions.extend(self.format_conditions(read_size, value, addr, check_eq, options)?);
+        }
 
-        // Added C source code cheat `patch::Line`s
-        let cheat_lines = cheat_lines.iter().map(|line| patch::Line::Add(line));
+        Ok(format!("{}if ({})", comments, conditions.join(" && ")))
+    }
 
-        // All lines of patch
-        let lines = once(patch::Line::Context("void run_gameshark_cheats(void) {"))
-            // Add blank line between cheats
-            .chain(once(patch::Line::Add("")))
-            // Add comment
-            .chain(once(patch::Line::Add(&name_comment)))
-            // Add cheat
-            .chain(cheat_lines)
-            // Detect blank line between cheats
-            .chain(once(patch::Line::Context("")))
-            .collect::<Vec<patch::Line>>();
+    /// Create the comment at the start of the C source code for a code line,
+    /// like `/* 8133B176 0015 */ `
+    ///
+    /// The comment ends with a space, and is empty if
+    /// `options.line_comments` isn't set.
+    fn line_comment(&self, code: gameshark::CodeLine, options: &PatchOptions) -> String {
+        if !options.line_comments {
+            return String::new();
+        }
 
-        let patch = patch::Patch {
-            old: patch::File {
-                path: Cow::from("a/src/game/gameshark.c"),
-                meta: None,
-            },
-            new: patch::File {
-                path: Cow::from("b/src/game/gameshark.c"),
-                meta: None,
-            },
-            hunks: vec![patch::Hunk {
-                old_range: patch::Range { start: 4, count: 2 },
-                new_range: patch::Range {
-                    start: 4,
-                    count: lines.len() as u64,
-                },
-                lines,
-            }],
-            end_newline: true,
+        // Comment with the code line, and optionally where it resolves to
+        let addr = code.addr() + 0x80000000;
+        let resolves = !matches!(code, gameshark::CodeLine::Master { .. });
+        let comment = if options.verbose_comments && resolves {
+            match self.addr_to_lvalue(addr) {
+                Ok(lvalue) => format!(
+                    "{} -> 0x{:08X} {}",
+                    code,
+                    addr,
+                    self.friendly_lvalue(&lvalue)
+                ),
+                Err(_) => format!("{} -> 0x{:08X}", code, addr),
+            }
+        } else {
+            code.to_string()
+        };
+
+        format!("/* {} */ ", comment)
+    }
+
+    /// Check that each line of a GameShark code resolves, without creating a
+    /// patch
+    ///
+    /// Lines are resolved like with `DecompData::validate_line`, so a code is
+    /// valid if `gs_code_to_patch` can convert it without dropping any write.
+    ///
+    /// ## Return values
+    /// The error of each line that can't be resolved, in order. This is empty
+    /// if the code is valid.
+    pub fn validate(&self, code: &gameshark::Code) -> Vec<ToPatchError> {
+        code.expand_repeats()
+            .0
+            .iter()
+            .filter_map(|line| self.validate_line(*line).err())
+            .collect()
+    }
+
+    /// Check that a GameShark code line resolves with the default options
+    ///
+    /// Writes to memory without symbols are only kept as a comment when
+    /// converting, so they fail with `ToPatchError::UnmappedRegion` here.
+    pub fn validate_line(&self, line: gameshark::CodeLine) -> Result<(), ToPatchError> {
+        self.gs_line_to_c(line, &PatchOptions::default(), &mut Vec::new())?;
+
+        if line.is_write() {
+            if let Err(err @ ToPatchError::UnmappedRegion { .. }) =
+                self.addr_to_lvalue(line.addr() + 0x80000000)
+            {
+                return Err(err);
+            }
         }
-        .to_string();
 
-        Ok(patch)
+        Ok(())
     }
 
-    /// Create a line of C source code that does a write to an address
+    /// Convert GameShark code to a patch in the unified diff format
     ///
     /// ## Parameters
-    ///   * `write_size` - Size of value to write
-    ///   * `value` - Value to write
-    ///   * `addr` - Address to write value
-    fn format_write(
+    ///   * `name` - Name of cheat to be included in comment in patch
+    ///   * `code` - GameShark code to convert
+    pub fn gs_code_to_patch(
         &self,
-        write_size: gameshark::ValueSize,
-        value: u64,
-        addr: SizeInt,
+        name: &str,
+        code: gameshark::Code,
     ) -> Result<String, ToPatchError> {
-        let lvalue = self.addr_to_lvalue(addr)?;
+        self.gs_code_to_patch_with_options(name, code, &PatchOptions::default())
+    }
 
-        // Get bit shift amount
-        let shift = self.lvalue_get_shift(&lvalue, write_size, addr)?;
+    /// Convert GameShark code to a patch in the unified diff format, with
+    /// options
+    ///
+    /// ## Parameters
+    ///   * `name` - Name of cheat to be included in comment in patch
+    ///   * `code` - GameShark code to convert
+    ///   * `options` - Options for the conversion
+    pub fn gs_code_to_patch_with_options(
+        &self,
+        name: &str,
+        code: gameshark::Code,
+        options: &PatchOptions,
+    ) -> Result<String, ToPatchError> {
+        self.gs_codes_to_patch_multi(&[(None, name.to_owned(), code)], options)
+    }
 
-        // Update variables and do recursion if the write overlaps multiple
-        // lvalues.
+    /// Convert multiple GameShark codes to one patch in the unified diff
+    /// format, like from `gameshark::parse_multi`
+    ///
+    /// This is `gs_codes_to_patch_multi` with the default options and no
+    /// categories, so each cheat is under its `/* name */` comment in one
+    /// hunk.
+    ///
+    /// ## Parameters
+    ///   * `cheats` - Name and code of each cheat
+    pub fn gs_cheats_to_patch(
+        &self,
+        cheats: &[(String, gameshark::Code)],
+    ) -> Result<String, ToPatchError> {
+        let cheats = cheats
+            .iter()
+            .map(|(name, code)| (None, name.clone(), code.clone()))
+            .collect::<Vec<(Option<String>, String, gameshark::Code)>>();
+        self.gs_codes_to_patch_multi(&cheats, &PatchOptions::default())
+    }
+
+    /// Convert multiple GameShark codes to one patch in the unified diff
+    /// format
+    ///
+    /// All cheats are inserted in one hunk, so the patch doesn't conflict with
+    /// itself like stacked single-cheat patches do.
+    ///
+    /// If `options.section_comments` is set, cheats are grouped by category
+    /// under `/* ===== Category ===== */` comments. Categories are in the
+    /// order that they first appear, and cheats without a category are under
+    /// `Uncategorized` at the end.
+    ///
+    /// ## Parameters
+    ///   * `cheats` - Category, name, and code of each cheat
+    ///   * `options` - Options for the conversion
+    pub fn gs_codes_to_patch_multi(
+        &self,
+        cheats: &[(Option<String>, String, gameshark::Code)],
+        options: &PatchOptions,
+    ) -> Result<String, ToPatchError> {
+        check_expansion(cheats.iter().map(|(_, _, code)| code), options)?;
+        self.gs_codes_to_file_patch(cheats, &options.target.path, options)
+    }
+
+    /// Convert multiple GameShark codes to one patch that inserts each cheat
+    /// into its own target file
+    ///
+    /// Ports that split cheats into several source files, like one per
+    /// category, have the cheat hook in each file. The patch has a section for
+    /// each file, in the order that the files first appear, with the file's
+    /// cheats converted like `gs_codes_to_patch_multi`. `options.self_contained`
+    /// is ignored, since it creates `gameshark.c` itself.
+    ///
+    /// ## Parameters
+    ///   * `cheats` - Target file path, category, name, and code of each
+    ///     cheat. The path is relative to the port's root, like
+    ///     `src/game/gameshark.c`.
+    ///   * `options` - Options for the conversion
+    pub fn gs_codes_to_patch_files(
+        &self,
+        cheats: &[(String, Option<String>, String, gameshark::Code)],
+        options: &PatchOptions,
+    ) -> Result<String, ToPatchError> {
+        let options = PatchOptions {
+            self_contained: false,
+            ..options.clone()
+        };
+
+        check_expansion(cheats.iter().map(|(_, _, _, code)| code), &options)?;
+
+        let mut paths = Vec::<&str>::new();
+        for (path, _, _, _) in cheats {
+            if !paths.contains(&path.as_str()) {
+                paths.push(path);
+            }
+        }
+
+        let patches = paths
+            .iter()
+            .map(|path| {
+                let file_cheats = cheats
+                    .iter()
+                    .filter(|(cheat_path, _, _, _)| cheat_path == path)
+                    .map(|(_, category, name, code)| (category.clone(), name.clone(), code.clone()))
+                    .collect::<Vec<(Option<String>, String, gameshark::Code)>>();
+                self.gs_codes_to_file_patch(&file_cheats, path, &options)
+            })
+            .collect::<Result<Vec<String>, ToPatchError>>()?;
+
+        Ok(patches.join("\n"))
+    }
+
+    /// Convert multiple GameShark codes to one patch of a target file, like
+    /// `gs_codes_to_patch_multi`
+    ///
+    /// ## Parameters
+    ///   * `cheats` - Category, name, and code of each cheat
+    ///   * `path` - Path of the target file, relative to the port's root
+    ///   * `options` - Options for the conversion
+    fn gs_codes_to_file_patch(
+        &self,
+        cheats: &[(Option<String>, String, gameshark::Code)],
+        path: &str,
+        options: &PatchOptions,
+    ) -> Result<String, ToPatchError> {
+        // Cheats registered with a cheat manager or added to a cheat table are
+        // wrapped in functions
+        let wrapped_options;
+        let options = if registration_block(options).is_some() && !options.wrap_in_function {
+            wrapped_options = PatchOptions {
+                wrap_in_function: true,
+                ..options.clone()
+            };
+            &wrapped_options
+        } else {
+            options
+        };
+
+        // Group cheats into sections
+        let sections = if options.section_comments {
+            let mut categories = Vec::<&str>::new();
+            for (category, _, _) in cheats {
+                let category = category.as_deref().unwrap_or("Uncategorized");
+                if !categories.contains(&category) {
+                    categories.push(category);
+                }
+            }
+
+            // Keep uncategorized cheats at the end
+            if let Some(i) = categories.iter().position(|c| *c == "Uncategorized") {
+                let uncategorized = categories.remove(i);
+                categories.push(uncategorized);
+            }
+
+            categories
+                .into_iter()
+                .map(|category| {
+                    let section_cheats = cheats
+                        .iter()
+                        .filter(|(cheat_category, _, _)| {
+                            cheat_category.as_deref().unwrap_or("Uncategorized") == category
+                        })
+                        .collect::<Vec<_>>();
+                    (Some(category), section_cheats)
+                })
+                .collect::<Vec<_>>()
+        } else {
+            vec![(None, cheats.iter().collect::<Vec<_>>())]
+        };
+
+        // Lines added by the patch
+        let mut added = Vec::<String>::new();
+        let mut constants = Vec::new();
+        for (category, section_cheats) in sections {
+            if let Some(category) = category {
+                let divider = format!("/* ===== {} ===== */", category);
+                if options.wrap_in_function {
+                    added.push(divider);
+                } else {
+                    added.push(String::new());
+                    added.push(format!("    {}", divider));
+                }
+            }
+
+            for (_, name, code) in section_cheats {
+                added.extend(self.cheat_to_lines(name, code, options, &mut constants)?);
+            }
+        }
+
+        // Toggle variables of cheats inserted into the hook have to be defined
+        // before the hook, so the port can set them
+        let toggle_definitions = if options.toggle_variables && !options.wrap_in_function {
+            cheats
+                .iter()
+                .map(|(_, name, _)| toggle_definition(name))
+                .chain(once(String::new()))
+                .collect::<Vec<String>>()
+        } else {
+            vec![]
+        };
+
+        // Lines that register each cheat's function with the cheat manager,
+        // or add it to the cheat table
+        let registrations = cheats
+            .iter()
+            .filter_map(|(_, name, _)| registration(name, options))
+            .collect::<Vec<String>>();
+
+        if options.self_contained {
+            // The created files don't define the checks of the in-game guard
+            // and of button-activated writes
+            let has_button_writes = cheats.iter().any(|(_, _, code)| {
+                code.expand_repeats().0.iter().any(|line| {
+                    matches!(
+                        line,
+                        gameshark::CodeLine::Write8OnBtn { .. }
+                            | gameshark::CodeLine::Write16OnBtn { .. }
+                    )
+                })
+            });
+            ensure!(
+                !options.in_game_guard,
+                SelfContainedUndefinedSnafu {
+                    name: &options.target.in_game_check
+                }
+            );
+            ensure!(
+                !has_button_writes,
+                SelfContainedUndefinedSnafu {
+                    name: &options.target.button_check
+                }
+            );
+
+            return Ok(self_contained_patch(
+                &toggle_definitions,
+                &added,
+                &registrations,
+                options,
+            ));
+        }
+
+        // Context lines before and after the insertion point. These have to be
+        // owned `String`s since `patch::Line` requires `&str` which needs an
+        // owned value to reference.
+        let hook = options.target.hook.clone();
+        let (leading_context, trailing_context) = if options.wrap_in_function {
+            // Define cheat functions before the hook
+            (vec![], vec![hook])
+        } else {
+            // Line after the insertion point
+            let trailing_context = match &options.target.first_statement {
+                Some(first_statement) if options.compact => first_statement.clone(),
+                // Detect blank line between cheats, by default
+                _ => options.target.trailing_context.clone(),
+            };
+
+            (vec![hook], vec![trailing_context])
+        };
+
+        // All lines of patch
+        let lines = toggle_definitions
+            .iter()
+            .map(|line| patch::Line::Add(line))
+            .chain(
+                leading_context
+                    .iter()
+                    .map(|line| patch::Line::Context(line)),
+            )
+            .chain(added.iter().map(|line| patch::Line::Add(line)))
+            .chain(
+                trailing_context
+                    .iter()
+                    .map(|line| patch::Line::Context(line)),
+            )
+            .collect::<Vec<patch::Line>>();
+
+        let hook_line = options.target.hook_line;
+        let num_old_lines = leading_context.len() + trailing_context.len();
+        let mut hunks = vec![patch::Hunk {
+            old_range: patch::Range {
+                start: hook_line,
+                count: num_old_lines as u64,
+            },
+            new_range: patch::Range {
+                start: hook_line,
+                count: lines.len() as u64,
+            },
+            lines,
+        }];
+
+        // Insert the registrations at the start of the registration hook or
+        // the cheat table, which is somewhere after the cheat hook
+        if let Some((registration_start, _)) = registration_block(options) {
+            let old_start = hook_line + num_old_lines as u64;
+            let new_start = hook_line + hunks[0].new_range.count;
+            hunks.push(patch::Hunk {
+                old_range: patch::Range {
+                    start: old_start,
+                    count: 1,
+                },
+                new_range: patch::Range {
+                    start: new_start,
+                    count: registrations.len() as u64 + 1,
+                },
+                lines: once(patch::Line::Context(registration_start))
+                    .chain(registrations.iter().map(|line| patch::Line::Add(line)))
+                    .collect(),
+            });
+        }
+
+        let patch = patch::Patch {
+            old: patch::File {
+                path: Cow::from(format!("a/{}", path)),
+                meta: None,
+            },
+            new: patch::File {
+                path: Cow::from(format!("b/{}", path)),
+                meta: None,
+            },
+            hunks,
+            end_newline: options.end_newline,
+        };
+
+        Ok(options.patch_format.format(&patch))
+    }
+
+    /// Convert a GameShark code to the lines that a patch adds for it
+    ///
+    /// ## Parameters
+    ///   * `name` - Name of cheat to be included in comment
+    ///   * `code` - GameShark code to convert
+    ///   * `options` - Options for the conversion
+    ///   * `constants` - Names and values of constants defined by earlier
+    ///     cheats
+    fn cheat_to_lines(
+        &self,
+        name: &str,
+        code: &gameshark::Code,
+        options: &PatchOptions,
+        constants: &mut Vec<(String, u64)>,
+    ) -> Result<Vec<String>, ToPatchError> {
+        // C source code cheat lines, with repeaters expanded into the writes
+        // that they repeat
+        let num_old_constants = constants.len();
+        let code_lines = code.expand_repeats();
+        let cheat_lines = if options.merge_guards {
+            let mut cheat_lines = Vec::new();
+            // Lines that aren't in a merged block yet
+            let mut pending = Vec::new();
+            for run in code_lines.guarded_runs() {
+                match run.guard {
+                    // Check once, then do all of the writes in a block
+                    Some(guard) if run.lines.len() > 1 => {
+                        cheat_lines.extend(self.code_lines_to_c(&pending, options, constants)?);
+                        pending.clear();
+
+                        if options.bounds_asserts {
+                            cheat_lines.extend(self.bounds_asserts(&run.lines)?);
+                        }
+                        let guard = self.gs_line_to_c(guard, options, constants)?;
+                        cheat_lines.push(format!("{} {{", guard));
+                        for code_line in run.lines {
+                            let line = self.gs_line_to_c(code_line, options, constants)?;
+                            if options.clang_format {
+                                cheat_lines.extend(
+                                    clang_format_line(&line, 0)
+                                        .into_iter()
+                                        .map(|line| format!("    {}", line)),
+                                );
+                            } else {
+                                cheat_lines.push(format!("    {}", line));
+                            }
+                        }
+                        cheat_lines.push(String::from("}"));
+                    }
+                    guard => pending.extend(guard.into_iter().chain(run.lines)),
+                }
+            }
+            cheat_lines.extend(self.code_lines_to_c(&pending, options, constants)?);
+            cheat_lines
+        } else {
+            self.code_lines_to_c(&code_lines.0, options, constants)?
+        };
+
+        // Define constants that this cheat added
+        let defines = constants[num_old_constants..]
+            .iter()
+            .map(|(name, value)| format!("#define {} {}", name, options.format_literal(*value)))
+            .collect::<Vec<String>>();
+
+        // Log the first time that the cheat runs
+        let cheat_lines = match &options.activation_log {
+            Some(log_fn) => activation_log_lines(name, log_fn)
+                .into_iter()
+                .chain(cheat_lines)
+                .collect::<Vec<String>>(),
+            None => cheat_lines,
+        };
+
+        // Guard the cheat so it only runs during gameplay
+        let cheat_lines = if options.in_game_guard {
+            once(format!("if ({}) {{", options.target.in_game_check))
+                .chain(cheat_lines.iter().map(|line| format!("    {}", line)))
+                .chain(once(String::from("}")))
+                .collect::<Vec<String>>()
+        } else {
+            cheat_lines
+        };
+
+        // Only run the cheat if its toggle variable is set
+        let cheat_lines = if options.toggle_variables {
+            once(format!("if ({}) {{", toggle_variable(name)))
+                .chain(cheat_lines.iter().map(|line| format!("    {}", line)))
+                .chain(once(String::from("}")))
+                .collect::<Vec<String>>()
+        } else {
+            cheat_lines
+        };
+
+        // Machine-readable comment before the cheat
+        let metadata_header = if options.metadata_headers {
+            Some(format!(
+                "/* {} name=\"{}\" lines={} fingerprint={:016x} */",
+                METADATA_TAG,
+                name.escape_default().to_string().replace("*/", "*\\/"),
+                code.0.len(),
+                code.fingerprint()
+            ))
+        } else {
+            None
+        };
+
+        let lines = if options.wrap_in_function {
+            let fn_name = format!("cheat_{}", c_identifier(name));
+
+            // The toggle variable is defined right before the function
+            let toggle_definition = if options.toggle_variables {
+                Some(toggle_definition(name))
+            } else {
+                None
+            };
+
+            // Comment with name of cheat, then the cheat's function
+            metadata_header
+                .into_iter()
+                .chain(once(format!(
+                    "/* {}: register {}() with the port's cheat list */",
+                    name, fn_name
+                )))
+                .chain(defines)
+                .chain(toggle_definition)
+                .chain(once(format!("void {}(void) {{", fn_name)))
+                .chain(cheat_lines.iter().map(|line| format!("    {}", line)))
+                .chain(once(String::from("}")))
+                .chain(once(String::new()))
+                .collect::<Vec<String>>()
+        } else {
+            // Add blank line between cheats and comment with name of cheat,
+            // then the indented cheat
+            once(String::new())
+                .chain(metadata_header.map(|line| format!("    {}", line)))
+                .chain(once(format!("    /* {} */", name)))
+                .chain(defines.iter().map(|line| format!("    {}", line)))
+                .chain(cheat_lines.iter().map(|line| format!("    {}", line)))
+                .collect::<Vec<String>>()
+        };
+
+        Ok(lines)
+    }
+
+    /// Convert GameShark code lines to lines of C source code
+    ///
+    /// If `options.fold_checks` is set, each chain of checks is one line. If
+    /// `options.array_fill_loops` or `options.array_initializers` is set, each
+    /// run of writes that fills an array is one line.
+    ///
+    /// ## Parameters
+    ///   * `code_lines` - Code lines to convert
+    ///   * `options` - Conversion options
+    ///   * `constants` - Names and values of constants defined so far
+    fn code_lines_to_c(
+        &self,
+        code_lines: &[gameshark::CodeLine],
+        options: &PatchOptions,
+        constants: &mut Vec<(String, u64)>,
+    ) -> Result<Vec<String>, ToPatchError> {
+        let mut lines = Vec::new();
+        // Amount of checks that guard the next line
+        let mut num_guards = 0;
+        // Index in `lines` before the checks that guard the next line, where
+        // its asserts go so they don't split the checks from it
+        let mut guards_start = 0;
+        let mut i = 0;
+        while i < code_lines.len() {
+            let start = i;
+
+            let num_checks = code_lines[i..]
+                .iter()
+                .take_while(|code_line| code_line.is_check())
+                .count();
+
+            // A write right after a check is guarded by it, so it can't start
+            // a fill
+            let is_guarded = i > 0 && code_lines[i - 1].is_check();
+            let fills_arrays = options.array_fill_loops || options.array_initializers;
+            let array_fill = if fills_arrays && !is_guarded {
+                self.format_array_fill(&code_lines[i..], options, constants)?
+            } else {
+                None
+            };
+
+            let (line, is_check) = if options.fold_checks && num_checks > 1 {
+                let line = self.gs_checks_to_c(&code_lines[i..i + num_checks], options)?;
+                i += num_checks;
+                (line, true)
+            } else if let Some((line, num_code_lines)) = array_fill {
+                i += num_code_lines;
+                (line, false)
+            } else {
+                let line = self.gs_line_to_c(code_lines[i], options, constants)?;
+                i += 1;
+                (line, code_lines[i - 1].is_check())
+            };
+
+            if options.clang_format {
+                lines.extend(clang_format_line(&line, num_guards));
+                num_guards = if is_check { num_guards + 1 } else { 0 };
+            } else {
+                lines.push(line);
+            }
+
+            if options.bounds_asserts {
+                let asserts = self.bounds_asserts(&code_lines[start..i])?;
+                lines.splice(guards_start..guards_start, asserts);
+            }
+            if !is_check {
+                guards_start = lines.len();
+            }
+        }
+
+        Ok(lines)
+    }
+
+    /// Create `assert` lines that check that the lvalues written by code
+    /// lines have the offset from the start of their declaration and the size
+    /// that the code expects
+    ///
+    /// Each lvalue is only checked once, and checks and writes to memory
+    /// without symbols aren't checked.
+    ///
+    /// ## Parameters
+    ///   * `code_lines` - Code lines to check the writes of
+    fn bounds_asserts(
+        &self,
+        code_lines: &[gameshark::CodeLine],
+    ) -> Result<Vec<String>, ToPatchError> {
+        let mut asserts = Vec::new();
+        for code_line in code_lines.iter().filter(|code_line| !code_line.is_check()) {
+            for (addr, _) in code_line.bytes() {
+                let lvalue = match self.addr_to_lvalue(addr + 0x80000000) {
+                    Ok(lvalue) => lvalue,
+                    Err(ToPatchError::UnmappedRegion { .. }) => continue,
+                    Err(err) => return Err(err),
+                };
+
+                // Declaration that the lvalue is part of
+                let mut decl = &lvalue;
+                while let LeftValueKind::ArrayIndex { array: parent, .. }
+                | LeftValueKind::StructField {
+                    struct_: parent, ..
+                } = &decl.kind
+                {
+                    decl = parent;
+                }
+
+                let assert = format!(
+                    "assert((char *) &{} - (char *) &{} == {:#x} && sizeof({}) == {});",
+                    lvalue.kind,
+                    decl.kind,
+                    lvalue.addr - decl.addr,
+                    lvalue.kind,
+                    self.size_of_type(&lvalue.typ)?
+                );
+                if !asserts.contains(&assert) {
+                    asserts.push(assert);
+                }
+            }
+        }
+
+        Ok(asserts)
+    }
+
+    /// Create a line of C source code that fills an array, if the first code
+    /// lines write each element of an integer array
+    ///
+    /// With `options.array_fill_loops`, the same value written to each element
+    /// is a loop, like
+    /// `for (int i = 0; i < 25; i++) gSaveBuffer.files[0][0].courseStars[i] = 0xff;`.
+    /// With `options.array_initializers`, the values written to a small array
+    /// are copied from a compound literal, like
+    /// `memcpy(&x, (uint8_t[]){0x1, 0x2, 0x3}, sizeof(x));`.
+    ///
+    /// The line starts with the comment of each code line. Bytes that the last
+    /// code line writes past the end of the array are written after the fill.
+    ///
+    /// ## Parameters
+    ///   * `code_lines` - Code lines to convert, starting with the fill
+    ///   * `options` - Conversion options
+    ///   * `constants` - Names and values of constants defined so far, which
+    ///     written values are added to if `options.named_constants` is set
+    ///
+    /// ## Return values
+    ///   * `Ok(Some((line, num_code_lines)))` - The line, and the amount of
+    ///     code lines it covers, which is at least 2
+    ///   * `Ok(None)` - The code lines don't start with an array fill
+    ///   * `Err(err)` - Bytes past the end of the array can't be written
+    fn format_array_fill(
+        &self,
+        code_lines: &[gameshark::CodeLine],
+        options: &PatchOptions,
+        constants: &mut Vec<(String, u64)>,
+    ) -> Result<Option<(String, usize)>, ToPatchError> {
+        let addr = match code_lines.first() {
+            Some(code_line) if code_line.is_write() => code_line.addr() + 0x80000000,
+            _ => return Ok(None),
+        };
+
+        // Find an array of integers that starts at the address
+        let element_lvalue = match self.addr_to_lvalue(addr) {
+            Ok(lvalue) => lvalue,
+            Err(_) => return Ok(None),
+        };
+        let mut array = element_lvalue.clone();
+        let (element_size, num_elements) = loop {
+            if array.addr != addr {
+                return Ok(None);
+            }
+            if let Type::Array {
+                element_type,
+                num_elements,
+            } = &array.typ
+            {
+                if let Type::Int { num_bytes, .. } = **element_type {
+                    break (num_bytes, *num_elements);
+                }
+            }
+            array = match array.kind {
+                LeftValueKind::ArrayIndex { array, .. } => *array,
+                LeftValueKind::StructField { struct_, .. } => *struct_,
+                LeftValueKind::Ident { .. } => return Ok(None),
+            };
+        };
+        let array_size = (element_size * num_elements) as usize;
+
+        // Collect bytes of contiguous writes until the array is covered
+        let mut bytes = Vec::<u8>::new();
+        let mut num_code_lines = 0;
+        while bytes.len() < array_size {
+            let (write_addr, value, write_size) = match code_lines.get(num_code_lines) {
+                Some(gameshark::CodeLine::Write8 { addr, value }) => {
+                    (*addr, *value as u16, gameshark::ValueSize::Bits8)
+                }
+                Some(gameshark::CodeLine::Write16 { addr, value }) => {
+                    (*addr, *value, gameshark::ValueSize::Bits16)
+                }
+                _ => return Ok(None),
+            };
+            if write_addr + 0x80000000 != addr + bytes.len() as SizeInt {
+                return Ok(None);
+            }
+
+            bytes.extend(value.to_be_bytes()[2 - write_size.num_bytes() as usize..].iter());
+            num_code_lines += 1;
+        }
+
+        if num_code_lines < 2 {
+            return Ok(None);
+        }
+
+        let values = bytes[..array_size]
+            .chunks(element_size as usize)
+            .map(|element| {
+                element
+                    .iter()
+                    .fold(0, |value, byte| (value << 8) | *byte as u64)
+            })
+            .collect::<Vec<u64>>();
+        let is_same_value = values.iter().all(|value| *value == values[0]);
+
+        // Value written to an element, which may be a named constant
+        let mut format_value = |element: &str, value: u64| {
+            if options.named_constants {
+                constant_name(element, value, constants)
+            } else {
+                options.format_literal(value)
+            }
+        };
+
+        let fill = if options.array_fill_loops && is_same_value {
+            format!(
+                "for (int i = 0; i < {}; i++) {}[i] = {};",
+                num_elements,
+                array.kind,
+                format_value(&array.kind.to_string(), values[0]),
+            )
+        } else if options.array_initializers && num_elements <= MAX_INITIALIZER_ELEMENTS {
+            let c_type = match element_lvalue.c_int_type() {
+                Some(c_type) => c_type,
+                None => return Ok(None),
+            };
+            let values = values
+                .iter()
+                .enumerate()
+                .map(|(i, value)| format_value(&format!("{}[{}]", array.kind, i), *value))
+                .collect::<Vec<String>>();
+            format!(
+                "memcpy(&{}, ({}[]){{{}}}, sizeof({}));",
+                array.kind,
+                c_type,
+                values.join(", "),
+                array.kind,
+            )
+        } else {
+            return Ok(None);
+        };
+
+        let comments = code_lines[..num_code_lines]
+            .iter()
+            .map(|code_line| self.line_comment(*code_line, options))
+            .collect::<String>();
+
+        let mut line = format!("{}{}", comments, fill);
+
+        // Write the bytes past the end of the array
+        for (i, byte) in bytes.iter().enumerate().skip(array_size) {
+            line.push(' ');
+            line.push_str(&self.format_write(
+                gameshark::ValueSize::Bits8,
+                *byte as u64,
+                addr + i as SizeInt,
+                options,
+                constants,
+            )?);
+        }
+
+        Ok(Some((line, num_code_lines)))
+    }
+
+    /// Create a line of C source code that does a write to an address
+    ///
+    /// ## Parameters
+    ///   * `write_size` - Size of value to write
+    ///   * `value` - Value to write
+    ///   * `addr` - Address to write value
+    ///   * `options` - Conversion options
+    ///   * `constants` - Names and values of constants defined so far, which
+    ///     written values are added to if `options.named_constants` is set
+    fn format_write(
+        &self,
+        write_size: gameshark::ValueSize,
+        value: u64,
+        addr: SizeInt,
+        options: &PatchOptions,
+        constants: &mut Vec<(String, u64)>,
+    ) -> Result<String, ToPatchError> {
+        let lvalue = self.addr_to_written_lvalue(addr, options)?;
+
+        // Get bit shift amount
+        let shift = self.lvalue_get_shift(&lvalue, write_size, addr)?;
+
+        // Update variables if the write overlaps multiple lvalues.
         let (
             // Bit shift amount
             shift,
-            // Second write to append to output
-            next_write,
+            // Value of second write to append to output
+            next_value,
             // Updated size of value to write
             write_size,
             // Updated value to write
@@ -544,25 +1928,129 @@ impl DecompData {
             // Write overlaps multiple lvalues
             None => (
                 0,
-                Some(self.format_write(gameshark::ValueSize::Bits8, value & 0xff, addr + 1)?),
+                Some(value & 0xff),
                 gameshark::ValueSize::Bits8,
                 value >> 8,
             ),
         };
 
-        let next_write = match next_write {
-            Some(s) => format!(" {}", s),
+        // Value written to the lvalue, which may be a named constant
+        let shifted_value = if options.named_constants {
+            constant_name(&lvalue.kind.to_string(), value << shift, constants)
+        } else {
+            options.format_literal(value << shift)
+        };
+
+        // Do recursion for the second write
+        let next_write = match next_value {
+            Some(next_value) => format!(
+                " {}",
+                self.format_write(
+                    gameshark::ValueSize::Bits8,
+                    next_value,
+                    addr + 1,
+                    options,
+                    constants,
+                )?
+            ),
             None => String::new(),
         };
 
-        Ok(format!(
-            "{} = ({} & {:#x}) | {:#x};{}",
-            lvalue,
-            lvalue,
-            !(write_size.mask() << shift),
-            value << shift,
-            next_write
-        ))
+        let access = if options.volatile_access {
+            lvalue.volatile_access()
+        } else {
+            lvalue.to_string()
+        };
+
+        // A write of the whole lvalue doesn't need to keep any of its bits
+        if self.size_of_type(&lvalue.typ)? == write_size.num_bytes() {
+            return Ok(format!("{} = {};{}", access, shifted_value, next_write));
+        }
+
+        let masked_value = format!(
+            "({} & {}) | {}",
+            access,
+            options.format_mask(!(write_size.mask() << shift)),
+            shifted_value
+        );
+        let masked_value = match lvalue.c_int_type() {
+            Some(c_type) if options.narrowing_casts => format!("({}) ({})", c_type, masked_value),
+            _ => masked_value,
+        };
+
+        Ok(format!("{} = {};{}", access, masked_value, next_write))
+    }
+
+    /// Create a line of C source code that writes bytes into a symbol whose
+    /// type is ignored, like `*((char *) &symbol + 0x1) = 0xab;`
+    ///
+    /// The bytes are written in the N64's big-endian order, which may not be
+    /// the symbol's layout on the PC port.
+    ///
+    /// ## Parameters
+    ///   * `bytes` - Bytes to write
+    ///   * `addr` - Address to write the first byte
+    fn format_ignored_write(&self, bytes: &[u8], addr: SizeInt) -> Result<String, ToPatchError> {
+        let decl = self.decl_before(addr).context(NoDeclSnafu { addr })?;
+
+        let writes = bytes
+            .iter()
+            .enumerate()
+            .map(|(i, byte)| {
+                format!(
+                    "*((char *) &{} + {:#x}) = {:#x};",
+                    decl.name,
+                    addr - decl.addr + i as SizeInt,
+                    byte
+                )
+            })
+            .collect::<Vec<String>>();
+
+        Ok(writes.join(" "))
+    }
+
+    /// Create a C condition that checks the bytes of a function, like
+    /// `*(uint8_t *) ((char *) &func + 0x4) == 0x24`
+    ///
+    /// There's a comparison for each byte, in the N64's big-endian order.
+    ///
+    /// ## Parameters
+    ///   * `read_size` - Size of value to read
+    ///   * `value` - Value to compare with
+    ///   * `addr` - Address to read value from
+    ///   * `check_eq` - Whether the operation is `==` or `!=`
+    fn format_fn_condition(
+        &self,
+        read_size: gameshark::ValueSize,
+        value: u64,
+        addr: SizeInt,
+        check_eq: bool,
+    ) -> Result<String, ToPatchError> {
+        let decl = self.decl_before(addr).context(NoDeclSnafu { addr })?;
+
+        let num_bytes = read_size.num_bytes();
+        let operator = if check_eq { "==" } else { "!=" };
+
+        let comparisons = (0..num_bytes)
+            .map(|i| {
+                format!(
+                    "*(uint8_t *) ((char *) &{} + {:#x}) {} {:#x}",
+                    decl.name,
+                    addr - decl.addr + i,
+                    operator,
+                    (value >> ((num_bytes - 1 - i) * 8)) & 0xff,
+                )
+            })
+            .collect::<Vec<String>>();
+
+        // The value is different if any byte is different
+        Ok(if check_eq {
+            comparisons.join(" && ")
+        } else if comparisons.len() > 1 {
+            format!("({})", comparisons.join(" || "))
+        } else {
+            comparisons.join("")
+        })
     }
 
     /// Create a line of C source code that checks the value at an address
@@ -572,119 +2060,1082 @@ impl DecompData {
     ///   * `value` - Value to compare with
     ///   * `addr` - Address to read value from
     ///   * `check_eq` - Whether the operation is `==` or `!=`
+    ///   * `options` - Conversion options
     fn format_check(
         &self,
         read_size: gameshark::ValueSize,
         value: u64,
         addr: SizeInt,
         check_eq: bool,
+        options: &PatchOptions,
     ) -> Result<String, ToPatchError> {
-        let lvalue = self.addr_to_lvalue(addr)?;
+        Ok(self
+            .format_conditions(read_size, value, addr, check_eq, options)?
+            .iter()
+            .map(|condition| format!("if ({})", condition))
+            .collect::<Vec<String>>()
+            .join(" "))
+    }
+
+    /// Create the C conditions that check the value at an address
+    ///
+    /// There's one condition for each lvalue that the read overlaps. The
+    /// parameters are the same as `format_check`.
+    fn format_conditions(
+        &self,
+        read_size: gameshark::ValueSize,
+        value: u64,
+        addr: SizeInt,
+        check_eq: bool,
+        options: &PatchOptions,
+    ) -> Result<Vec<String>, ToPatchError> {
+        let lvalue = match self.addr_to_lvalue(addr) {
+            Ok(lvalue) => lvalue,
+            Err(ToPatchError::FnPatch { .. }) if options.fn_checks => {
+                return Ok(vec![
+                    self.format_fn_condition(read_size, value, addr, check_eq)?
+                ]);
+            }
+            Err(err) => return Err(err),
+        };
 
         // Get bit shift amount
         let shift = self.lvalue_get_shift(&lvalue, read_size, addr)?;
 
         // Update variables and do recursion if the read overlaps multiple
         // lvalues.
-        let (shift, next_read, read_size, value) = match shift {
+        let (shift, next_reads, read_size, value) = match shift {
             // Read is entirely within one lvalue; keep the same variables.
-            Some(shift) => (shift, None, read_size, value),
+            Some(shift) => (shift, Vec::new(), read_size, value),
 
             // Read overlaps multiple lvalues
             None => (
                 0,
-                Some(self.format_check(
+                self.format_conditions(
                     gameshark::ValueSize::Bits8,
                     value & 0xff,
                     addr + 1,
                     check_eq,
-                )?),
+                    options,
+                )?,
                 gameshark::ValueSize::Bits8,
                 value >> 8,
             ),
         };
 
-        let next_read = match next_read {
-            Some(s) => format!(" {}", s),
-            None => String::new(),
-        };
+        let access = if options.volatile_access {
+            lvalue.volatile_access()
+        } else {
+            lvalue.to_string()
+        };
+
+        let operator = if check_eq { "==" } else { "!=" };
+
+        let condition = if options.shift_down_checks {
+            // Shift the read value down to compare it with the unshifted value
+            let shifted_access = if shift == 0 {
+                access
+            } else {
+                format!("({} >> {})", access, shift)
+            };
+
+            format!(
+                "({} & {}) {} {}",
+                shifted_access,
+                options.format_mask(read_size.mask()),
+                operator,
+                options.format_literal(value),
+            )
+        } else {
+            format!(
+                "({} & {}) {} {}",
+                access,
+                options.format_mask(read_size.mask() << shift),
+                operator,
+                options.format_literal(value << shift),
+            )
+        };
+
+        Ok(once(condition).chain(next_reads).collect())
+    }
+
+    /// Get the left bit shift amount required to access a `value_size`d value
+    /// at `addr` in `lvalue`
+    ///
+    /// ## Return values
+    ///   * `Ok(Some(shift))` - Success
+    ///   * `Ok(None)` - No shift exists, because `value_size` at `addr`
+    ///     overlaps the edge of the lvalue.
+    ///   * `Err(err)` - Error getting size of lvalue
+    pub(crate) fn lvalue_get_shift(
+        &self,
+        lvalue: &LeftValue,
+        value_size: gameshark::ValueSize,
+        addr: SizeInt,
+    ) -> Result<Option<SizeInt>, ToPatchError> {
+        let lvalue_size = self.size_of_type(&lvalue.typ)?;
+
+        Ok(lvalue_size
+            .checked_sub(value_size.num_bytes())
+            .and_then(|size_diff| size_diff.checked_sub(addr - lvalue.addr))
+            .map(|diff_diff| diff_diff * 8))
+    }
+}
+
+/// Get the name of the symbol that a pointer initializer points to, if it's
+/// the address of the start of a symbol, like `&gMarioStates` or
+/// `&gMarioStates[0]`, or an array that decays to a pointer, like
+/// `gMarioStates`
+#[cfg(feature = "loader")]
+fn pointer_target(init: clang::Entity) -> Option<String> {
+    let children = init.get_children();
+    match init.get_kind() {
+        clang::EntityKind::DeclRefExpr => init.get_reference()?.get_name(),
+        // Only `&` keeps the symbol, other operators like `*` read through it
+        clang::EntityKind::UnaryOperator => {
+            let operator = init.get_range()?.tokenize().first()?.get_spelling();
+            if operator == "&" {
+                pointer_target(*children.first()?)
+            } else {
+                None
+            }
+        }
+        // Only indexing the first element keeps the start of the symbol
+        clang::EntityKind::ArraySubscriptExpr => match children.get(1)?.evaluate()? {
+            clang::EvaluationResult::SignedInteger(0)
+            | clang::EvaluationResult::UnsignedInteger(0) => pointer_target(*children.first()?),
+            _ => None,
+        },
+        // Casts keep the symbol that the pointer is into
+        clang::EntityKind::UnexposedExpr
+        | clang::EntityKind::ParenExpr
+        | clang::EntityKind::CStyleCastExpr => pointer_target(*children.first()?),
+        _ => None,
+    }
+}
+
+/// Check that codes converted into one patch don't expand to more than
+/// `options.max_expansion` memory operations in total
+fn check_expansion<'a>(
+    codes: impl Iterator<Item = &'a gameshark::Code>,
+    options: &PatchOptions,
+) -> Result<(), ToPatchError> {
+    let num_operations = codes
+        .map(gameshark::Code::estimated_operations)
+        .sum::<usize>();
+    ensure!(
+        num_operations <= options.max_expansion,
+        ExpansionTooLargeSnafu {
+            limit: options.max_expansion
+        }
+    );
+    Ok(())
+}
+
+/// Most elements of an array that `PatchOptions::array_initializers` sets with
+/// a compound literal, which keeps the line readable
+const MAX_INITIALIZER_ELEMENTS: SizeInt = 16;
+
+/// Headers included by a `gameshark.c` created by a self-contained patch, for
+/// the globals that cheats access
+const SELF_CONTAINED_INCLUDES: &[&str] = &[
+    "#include <PR/ultratypes.h>",
+    "",
+    "#include \"sm64.h\"",
+    "#include \"area.h\"",
+    "#include \"camera.h\"",
+    "#include \"game_init.h\"",
+    "#include \"gameshark.h\"",
+    "#include \"level_update.h\"",
+    "#include \"mario.h\"",
+    "#include \"object_list_processor.h\"",
+    "#include \"save_file.h\"",
+];
+
+/// Line numbers of `#include "thread6.h"` and of the default
+/// `PatchTarget::call_site` in sm64-port's `game_init.c`, where a
+/// self-contained patch includes `gameshark.h` and calls the hook
+///
+/// `patch` and `git apply` search near these lines for the hunks' context, so
+/// they don't have to be exact for other ports.
+const GAME_INIT_INCLUDE_LINE: u64 = 21;
+const GAME_INIT_CALL_SITE_LINE: u64 = 647;
+
+/// Get the lines that start and end the block where cheats are registered,
+/// for `PatchOptions::cheat_manager` or `PatchOptions::cheat_table`
+fn registration_block(options: &PatchOptions) -> Option<(&str, &str)> {
+    match (&options.cheat_manager, &options.cheat_table) {
+        (Some(cheat_manager), _) => Some((&cheat_manager.registration_hook, "}")),
+        (None, Some(cheat_table)) => Some((&cheat_table.table_start, "};")),
+        (None, None) => None,
+    }
+}
+
+/// Get the line that registers a cheat's function with
+/// `PatchOptions::cheat_manager`, or adds it as a row of
+/// `PatchOptions::cheat_table`
+fn registration(name: &str, options: &PatchOptions) -> Option<String> {
+    let fn_name = format!("cheat_{}", c_identifier(name));
+
+    match (&options.cheat_manager, &options.cheat_table) {
+        (Some(cheat_manager), _) => Some(format!(
+            "    {}(\"{}\", {});",
+            cheat_manager.register_fn,
+            name.escape_default(),
+            fn_name
+        )),
+        (None, Some(cheat_table)) => {
+            Some(format!("    {}", cheat_table.format_row(name, &fn_name)))
+        }
+        (None, None) => None,
+    }
+}
+
+/// Create a patch that creates `gameshark.c`, with the cheats in
+/// `run_gameshark_cheats`, and `gameshark.h`, which declares it, and calls
+/// the hook from `game_init.c`, like the base patches do
+///
+/// The call is inserted after `options.target.call_site`.
+///
+/// ## Parameters
+///   * `toggle_definitions` - Lines defining toggle variables before the hook
+///   * `added` - Lines for the cheats, inserted into the hook or defined as
+///     functions before it
+///   * `registrations` - Lines registering cheats with the cheat manager or
+///     the cheat table, which are put in a registration function or the
+///     table after the hook
+///   * `options` - Conversion options
+fn self_contained_patch(
+    toggle_definitions: &[String],
+    added: &[String],
+    registrations: &[String],
+    options: &PatchOptions,
+) -> String {
+    let hook = HOOK;
+
+    // Cheat functions are defined before the hook, and other cheats are
+    // inserted at the start of its body
+    let (before_hook, in_hook) = if options.wrap_in_function {
+        (added, &[][..])
+    } else {
+        (&[][..], added)
+    };
+
+    // Function that registers cheats with the cheat manager, or the cheat
+    // table
+    let registration_fn = match registration_block(options) {
+        Some((start, end)) => once(String::new())
+            .chain(once(start.to_owned()))
+            .chain(registrations.iter().cloned())
+            .chain(once(end.to_owned()))
+            .collect::<Vec<String>>(),
+        None => vec![],
+    };
+
+    // `assert` is only needed for bounds checks, `bool` for toggle variables
+    // and activation log flags, and `memcpy` for array initializers
+    let assert_include = if options.bounds_asserts {
+        &["#include <assert.h>"][..]
+    } else {
+        &[]
+    };
+    let bool_include = if options.toggle_variables || options.activation_log.is_some() {
+        &["#include <stdbool.h>"][..]
+    } else {
+        &[]
+    };
+    let string_include = if options.array_initializers {
+        &["#include <string.h>"][..]
+    } else {
+        &[]
+    };
+
+    let source_lines = assert_include
+        .iter()
+        .chain(bool_include)
+        .chain(string_include)
+        .chain(SELF_CONTAINED_INCLUDES)
+        .copied()
+        .chain(once(""))
+        .chain(toggle_definitions.iter().map(String::as_str))
+        .chain(before_hook.iter().map(String::as_str))
+        .chain(once(hook))
+        .chain(in_hook.iter().map(String::as_str))
+        .chain(["", "}"])
+        .chain(registration_fn.iter().map(String::as_str))
+        .collect::<Vec<&str>>();
+
+    // Declaration of the registration function, like
+    // `void register_gameshark_cheats(void);`, or of the cheat table, like
+    // `extern struct GameSharkCheat gGameSharkCheats[];`
+    let registration_declaration = match (&options.cheat_manager, &options.cheat_table) {
+        (Some(cheat_manager), _) => {
+            let hook = &cheat_manager.registration_hook;
+            Some(format!("{};", hook.strip_suffix(" {").unwrap_or(hook)))
+        }
+        (None, Some(cheat_table)) => {
+            let start = &cheat_table.table_start;
+            Some(format!(
+                "extern {};",
+                start.strip_suffix(" = {").unwrap_or(start)
+            ))
+        }
+        (None, None) => None,
+    };
+
+    let header_lines = [
+        "#ifndef GAMESHARK_H",
+        "#define GAMESHARK_H",
+        "",
+        "void run_gameshark_cheats(void);",
+    ]
+    .iter()
+    .copied()
+    .chain(registration_declaration.as_deref())
+    .chain(["", "#endif // GAMESHARK_H"])
+    .collect::<Vec<&str>>();
+
+    // Patch that creates a file
+    let new_file = |path: &'static str, lines: &[&str], end_newline: bool| {
+        let patch = patch::Patch {
+            old: patch::File {
+                path: Cow::from("/dev/null"),
+                meta: None,
+            },
+            new: patch::File {
+                path: Cow::from(path),
+                meta: None,
+            },
+            hunks: vec![patch::Hunk {
+                old_range: patch::Range { start: 0, count: 0 },
+                new_range: patch::Range {
+                    start: 1,
+                    count: lines.len() as u64,
+                },
+                lines: lines.iter().map(|line| patch::Line::Add(line)).collect(),
+            }],
+            end_newline,
+        };
+        options.patch_format.format(&patch)
+    };
+
+    // Include `gameshark.h` in `game_init.c` and call the hook once per frame,
+    // with the indentation of the call site
+    let call_site = &options.target.call_site;
+    let call = format!(
+        "{}run_gameshark_cheats();",
+        &call_site[..call_site.len() - call_site.trim_start().len()]
+    );
+    let game_init_patch = options.patch_format.format(&patch::Patch {
+        old: patch::File {
+            path: Cow::from("a/src/game/game_init.c"),
+            meta: None,
+        },
+        new: patch::File {
+            path: Cow::from("b/src/game/game_init.c"),
+            meta: None,
+        },
+        hunks: vec![
+            patch::Hunk {
+                old_range: patch::Range {
+                    start: GAME_INIT_INCLUDE_LINE,
+                    count: 2,
+                },
+                new_range: patch::Range {
+                    start: GAME_INIT_INCLUDE_LINE,
+                    count: 3,
+                },
+                lines: vec![
+                    patch::Line::Context("#include \"thread6.h\""),
+                    patch::Line::Add("#include \"gameshark.h\""),
+                    patch::Line::Context("#include <prevent_bss_reordering.h>"),
+                ],
+            },
+            patch::Hunk {
+                old_range: patch::Range {
+                    start: GAME_INIT_CALL_SITE_LINE,
+                    count: 2,
+                },
+                new_range: patch::Range {
+                    start: GAME_INIT_CALL_SITE_LINE + 1,
+                    count: 3,
+                },
+                lines: vec![
+                    patch::Line::Context(call_site),
+                    patch::Line::Add(&call),
+                    patch::Line::Context(""),
+                ],
+            },
+        ],
+        end_newline: true,
+    });
+
+    format!(
+        "{}\n{}\n{}",
+        game_init_patch,
+        new_file("b/src/game/gameshark.h", &header_lines, true),
+        new_file("b/src/game/gameshark.c", &source_lines, options.end_newline)
+    )
+}
+
+/// Create a commented-out raw write to memory without symbols, with a warning
+///
+/// The comment is an empty statement, so a check before it still only guards
+/// it, not the next line.
+///
+/// ## Parameters
+///   * `region` - Description of the memory region
+///   * `c_type` - C type of the written value
+///   * `value` - Value to write
+///   * `addr` - Address to write value
+fn format_unmapped_write(region: &str, c_type: &str, value: u64, addr: SizeInt) -> String {
+    format!(
+        "/* warning: no symbols in {}: *({} *) {:#x} = {:#x}; */;",
+        region, c_type, addr, value
+    )
+}
+
+/// Get the name of the constant for a value written to an lvalue, defining it
+/// in `constants` if needed
+///
+/// For example, a value written to `gMarioStates[0].flags` is named
+/// `MARIO_STATES_0_FLAGS_VALUE`. Different values written to the same lvalue
+/// get a numbered suffix, like `MARIO_STATES_0_FLAGS_VALUE_2`.
+fn constant_name(lvalue: &str, value: u64, constants: &mut Vec<(String, u64)>) -> String {
+    let mut words = Vec::<String>::new();
+    for token in lvalue.split(|c: char| !c.is_ascii_alphanumeric()) {
+        // Split `camelCase` into words
+        let mut word = String::new();
+        for c in token.chars() {
+            if c.is_ascii_uppercase() && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.push(c.to_ascii_uppercase());
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+    }
+
+    // Remove Hungarian notation prefix of globals and statics, like the `g` in
+    // `gMarioStates`
+    if words.len() > 1 && (words[0] == "G" || words[0] == "S") {
+        words.remove(0);
+    }
+
+    let base = format!("{}_VALUE", words.join("_"));
+
+    for i in 1.. {
+        let name = match i {
+            1 => base.clone(),
+            i => format!("{}_{}", base, i),
+        };
+
+        match constants.iter().find(|(other_name, _)| *other_name == name) {
+            // Reuse constant with same value
+            Some((_, other_value)) if *other_value == value => return name,
+            // Name is taken by a different value
+            Some(_) => continue,
+            None => {
+                constants.push((name.clone(), value));
+                return name;
+            }
+        }
+    }
+
+    unreachable!()
+}
+
+/// Tag at the start of the metadata comment before each cheat, if
+/// `PatchOptions::metadata_headers` is set
+pub(crate) const METADATA_TAG: &str = "@sm64gs2pc";
+
+/// Name of the variable that toggles a cheat, like `cheat_moon_jump_enabled`
+fn toggle_variable(name: &str) -> String {
+    format!("cheat_{}_enabled", c_identifier(name))
+}
+
+/// Definition of the variable that toggles a cheat, which enables it by
+/// default
+pub(crate) fn toggle_definition(name: &str) -> String {
+    format!("static bool {} = true;", toggle_variable(name))
+}
+
+/// Lines that log the first time that a cheat runs, with `log_fn`
+fn activation_log_lines(name: &str, log_fn: &str) -> Vec<String> {
+    let flag = format!("cheat_{}_logged", c_identifier(name));
+
+    vec![
+        format!("static bool {} = false;", flag),
+        format!("if (!{}) {{", flag),
+        format!("    {} = true;", flag),
+        format!(
+            "    {}(\"GameShark cheat activated: %s\\n\", \"{}\");",
+            log_fn,
+            name.escape_default()
+        ),
+        String::from("}"),
+    ]
+}
+
+/// Lay out a line of C source code like `clang-format` with the
+/// decompilation's style
+///
+/// The line is indented once for each check that guards it, and each
+/// statement after the first is moved to its own line. Only the first
+/// statement is guarded, so the others aren't indented.
+///
+/// ## Parameters
+///   * `line` - Line of C source code, which may start with comments
+///   * `num_guards` - Amount of checks that guard the line
+fn clang_format_line(line: &str, num_guards: usize) -> Vec<String> {
+    // Split after each `;` that isn't in parentheses, like in a `for` loop, or
+    // in a comment
+    let mut statements = Vec::new();
+    let mut depth = 0;
+    let mut in_comment = false;
+    let mut start = 0;
+    for (i, c) in line.char_indices() {
+        match c {
+            '*' if in_comment && line[i..].starts_with("*/") => in_comment = false,
+            _ if in_comment => {}
+            '/' if line[i..].starts_with("/*") => in_comment = true,
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ';' if depth == 0 => {
+                statements.push(line[start..=i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !line[start..].trim().is_empty() {
+        statements.push(line[start..].trim());
+    }
+
+    statements
+        .into_iter()
+        .enumerate()
+        .map(|(i, statement)| {
+            let indent = if i == 0 { num_guards } else { 0 };
+            format!("{}{}", "    ".repeat(indent), statement)
+        })
+        .collect()
+}
+
+/// Convert a cheat name to a `snake_case` C identifier
+///
+/// For example, `Moon Jump` becomes `moon_jump`.
+fn c_identifier(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<String>>()
+        .join("_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::options::MaskFormat;
+    use crate::typ::StructField;
+
+    fn add_int(decomp_data: &mut DecompData, addr: SizeInt, num_bytes: SizeInt, name: &str) {
+        decomp_data.decls.insert(
+            addr,
+            Decl {
+                addr,
+                kind: DeclKind::Var {
+                    typ: Type::Int {
+                        signed: false,
+                        num_bytes,
+                    },
+                },
+                name: name.to_owned(),
+            },
+        );
+    }
+
+    fn add_float(decomp_data: &mut DecompData, addr: SizeInt, name: &str) {
+        decomp_data.decls.insert(
+            addr,
+            Decl {
+                addr,
+                kind: DeclKind::Var { typ: Type::Float },
+                name: name.to_owned(),
+            },
+        );
+    }
+
+    fn decomp_data() -> DecompData {
+        let mut data = DecompData::default();
+        add_int(&mut data, 0x8000, 1, "A");
+        add_int(&mut data, 0x8001, 1, "B");
+        add_int(&mut data, 0x8002, 1, "C");
+        add_int(&mut data, 0x8003, 1, "D");
+        add_int(&mut data, 0x8004, 4, "E");
+        add_int(&mut data, 0x8008, 4, "F");
+        add_int(&mut data, 0x800c, 2, "G");
+        add_int(&mut data, 0x800e, 2, "H");
+        add_float(&mut data, 0x8010, "f0");
+        data
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let data = decomp_data();
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(serde_json::from_str::<DecompData>(&json).unwrap(), data);
+    }
+
+    #[test]
+    fn test_stable_serialization() {
+        let structs = ["A", "B", "C", "D", "E", "F", "G", "H"];
+
+        let mut data = decomp_data();
+        for name in structs {
+            data.structs
+                .insert(name.to_string(), Struct { fields: vec![] });
+        }
+        let mut reversed = decomp_data();
+        for name in structs.iter().rev() {
+            reversed
+                .structs
+                .insert(name.to_string(), Struct { fields: vec![] });
+        }
+
+        assert_eq!(
+            serde_json::to_string(&data).unwrap(),
+            serde_json::to_string(&reversed).unwrap()
+        );
+        assert_eq!(
+            bincode::serialize(&data).unwrap(),
+            bincode::serialize(&reversed).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_struct_assign() {
+        let mut data = decomp_data();
+        data.structs
+            .insert("Empty".to_owned(), Struct { fields: vec![] });
+        data.decls.insert(
+            0x9000,
+            Decl {
+                addr: 0x9000,
+                kind: DeclKind::Var {
+                    typ: Type::Struct {
+                        name: "Empty".to_owned(),
+                    },
+                },
+                name: "empty".to_owned(),
+            },
+        );
+        data.decls.insert(
+            0x9010,
+            Decl {
+                addr: 0x9010,
+                kind: DeclKind::Var {
+                    typ: Type::Array {
+                        element_type: Box::new(Type::AnonStruct(Struct { fields: vec![] })),
+                        num_elements: 4,
+                    },
+                },
+                name: "empties".to_owned(),
+            },
+        );
+
+        assert!(matches!(
+            data.addr_to_lvalue(0x9000),
+            Err(ToPatchError::StructAssign { addr: 0x9000 })
+        ));
+        assert!(matches!(
+            data.addr_to_lvalue(0x9010),
+            Err(ToPatchError::StructAssign { addr: 0x9010 })
+        ));
+    }
+
+    #[test]
+    fn test_pointer_size() {
+        let mut data = decomp_data();
+        let pointer = Type::Pointer {
+            inner_type: Box::new(Type::Ignored),
+        };
+        assert_eq!(data.size_of_type(&pointer).unwrap(), 4);
+
+        // An array of structs with a pointer before a field
+        data.decls.insert(
+            0x80301000,
+            Decl {
+                addr: 0x80301000,
+                kind: DeclKind::Var {
+                    typ: Type::Array {
+                        element_type: Box::new(Type::AnonStruct(Struct {
+                            fields: vec![
+                                StructField {
+                                    offset: 0,
+                                    name: "ptr".to_owned(),
+                                    typ: pointer,
+                                },
+                                StructField {
+                                    offset: 4,
+                                    name: "count".to_owned(),
+                                    typ: Type::Int {
+                                        signed: false,
+                                        num_bytes: 4,
+                                    },
+                                },
+                            ],
+                        })),
+                        num_elements: 2,
+                    },
+                },
+                name: "objs".to_owned(),
+            },
+        );
+
+        let code = gameshark::CodeLine::Write16 {
+            addr: 0x30100e,
+            value: 0x1234,
+        };
+        assert_eq!(
+            data.gs_line_to_c(code, &PatchOptions::default(), &mut Vec::new())
+                .unwrap(),
+            "/* 8130100E 1234 */ objs[1].count = (objs[1].count & 0xffffffffffff0000) | 0x1234;",
+        );
+        assert!(matches!(
+            data.addr_to_lvalue(0x80301008),
+            Err(ToPatchError::PointerAssign { addr: 0x80301008 })
+        ));
+    }
+
+    #[test]
+    fn test_aliases_at() {
+        let mut data = decomp_data();
+        assert_eq!(data.aliases_at(0x8004), vec!["E"]);
+        assert_eq!(data.aliases_at(0x8005), Vec::<&str>::new());
+
+        data.shadowed_names
+            .insert(0x8004, vec!["E_alias".to_owned()]);
+        assert_eq!(data.aliases_at(0x8004), vec!["E", "E_alias"]);
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let lvalue = LeftValue {
+            kind: LeftValueKind::Ident {
+                name: "A".to_owned(),
+            },
+            typ: Type::Float,
+            addr: 0,
+        };
+        let codes = [
+            ToPatchError::FnPatch { addr: 0 },
+            ToPatchError::IgnoredType,
+            ToPatchError::NoDecl { addr: 0 },
+            ToPatchError::NoStruct {
+                name: "A".to_owned(),
+            },
+            ToPatchError::NoField { addr: 0 },
+            ToPatchError::ArrayOutOfBounds { addr: 0, lvalue },
+            ToPatchError::PointerAssign { addr: 0 },
+            ToPatchError::UnmappedRegion {
+                addr: 0,
+                region: "A",
+            },
+            ToPatchError::StructAssign { addr: 0 },
+            ToPatchError::ExpansionTooLarge { limit: 0 },
+            ToPatchError::NotStaticallyLocated { addr: 0 },
+            ToPatchError::RomCheck { addr: 0 },
+            ToPatchError::SelfContainedUndefined {
+                name: "A".to_owned(),
+            },
+        ]
+        .iter()
+        .map(ToPatchError::code)
+        .collect::<Vec<&str>>();
+
+        assert_eq!(
+            codes,
+            vec![
+                "fn-patch",
+                "ignored-type",
+                "no-decl",
+                "no-struct",
+                "no-field",
+                "array-oob",
+                "pointer-assign",
+                "unmapped-region",
+                "struct-assign",
+                "expansion-too-large",
+                "not-statically-located",
+                "rom-check",
+                "self-contained-undefined",
+            ]
+        );
+        assert_eq!(codes.iter().collect::<BTreeSet<_>>().len(), codes.len());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut data = decomp_data();
+        data.decls.insert(
+            0x9000,
+            Decl {
+                addr: 0x9000,
+                kind: DeclKind::Fn,
+                name: "f".to_owned(),
+            },
+        );
+        data.structs
+            .insert("Empty".to_owned(), Struct { fields: vec![] });
+
+        assert_eq!(
+            data.stats(),
+            DecompStats {
+                num_fns: 1,
+                num_vars: 9,
+                num_structs: 1,
+            }
+        );
+        assert_eq!(DecompData::default().stats().num_vars, 0);
+    }
+
+    #[test]
+    fn test_largest_struct() {
+        let mut data = decomp_data();
+        assert_eq!(data.largest_struct(), None);
 
-        Ok(format!(
-            "if (({} & {:#x}) {} {:#x}){}",
-            lvalue,
-            read_size.mask() << shift,
-            if check_eq { "==" } else { "!=" },
-            value << shift,
-            next_read,
-        ))
-    }
+        let field = |offset, name: &str, typ| StructField {
+            offset,
+            name: name.to_owned(),
+            typ,
+        };
+        let int = |num_bytes| Type::Int {
+            signed: false,
+            num_bytes,
+        };
 
-    /// Get the left bit shift amount required to access a `value_size`d value
-    /// at `addr` in `lvalue`
-    ///
-    /// ## Return values
-    ///   * `Ok(Some(shift))` - Success
-    ///   * `Ok(None)` - No shift exists, because `value_size` at `addr`
-    ///                  overlaps the edge of the lvalue.
-    ///   * `Err(err)` - Error getting size of lvalue
-    fn lvalue_get_shift(
-        &self,
-        lvalue: &LeftValue,
-        value_size: gameshark::ValueSize,
-        addr: SizeInt,
-    ) -> Result<Option<SizeInt>, ToPatchError> {
-        let lvalue_size = self.size_of_type(&lvalue.typ)?;
+        data.structs.insert(
+            "Wide".to_owned(),
+            Struct {
+                fields: vec![
+                    field(0, "a", int(1)),
+                    field(1, "b", int(1)),
+                    field(2, "c", int(1)),
+                ],
+            },
+        );
+        data.structs.insert(
+            "Big".to_owned(),
+            Struct {
+                fields: vec![
+                    field(0, "flags", int(4)),
+                    field(
+                        4,
+                        "values",
+                        Type::Array {
+                            element_type: Box::new(int(2)),
+                            num_elements: 8,
+                        },
+                    ),
+                ],
+            },
+        );
+        // Unknown size, so it's skipped
+        data.structs.insert(
+            "Ignored".to_owned(),
+            Struct {
+                fields: vec![field(0, "ignored", Type::Ignored)],
+            },
+        );
 
-        Ok(lvalue_size
-            .checked_sub(value_size.num_bytes())
-            .and_then(|size_diff| size_diff.checked_sub(addr - lvalue.addr))
-            .map(|diff_diff| diff_diff * 8))
+        assert_eq!(data.largest_struct(), Some(("Big", 2)));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_validate_references() {
+        let mut data = decomp_data();
+        assert_eq!(data.validate_references(), Vec::<String>::new());
 
-    fn add_int(decomp_data: &mut DecompData, addr: SizeInt, num_bytes: SizeInt, name: &str) {
-        decomp_data.decls.insert(
-            addr,
+        data.decls.insert(
+            0x9000,
             Decl {
-                addr,
+                addr: 0x9000,
                 kind: DeclKind::Var {
-                    typ: Type::Int {
-                        signed: false,
-                        num_bytes,
+                    typ: Type::Array {
+                        element_type: Box::new(Type::Struct {
+                            name: "Missing".to_owned(),
+                        }),
+                        num_elements: 2,
                     },
                 },
-                name: name.to_owned(),
+                name: "arr".to_owned(),
             },
         );
+        assert_eq!(data.validate_references(), vec!["Missing"]);
     }
 
-    fn add_float(decomp_data: &mut DecompData, addr: SizeInt, name: &str) {
-        decomp_data.decls.insert(
-            addr,
+    #[test]
+    fn test_unmapped_region() {
+        let data = decomp_data();
+
+        assert_eq!(
+            data.gs_line_to_c(
+                gameshark::CodeLine::Write16 {
+                    addr: 0x0038F800,
+                    value: 0xabcd,
+                },
+                &PatchOptions::default(),
+                &mut Vec::new()
+            )
+            .unwrap(),
+            "/* 8138F800 ABCD */ /* warning: no symbols in framebuffers: \
+             *(uint16_t *) 0x8038f800 = 0xabcd; */;"
+        );
+
+        // Checks can't be skipped
+        assert!(matches!(
+            data.gs_line_to_c(
+                gameshark::CodeLine::IfEq8 {
+                    addr: 0x00000100,
+                    value: 0xaa,
+                },
+                &PatchOptions::default(),
+                &mut Vec::new()
+            ),
+            Err(ToPatchError::UnmappedRegion {
+                addr: 0x80000100,
+                region: "N64 OS low memory",
+            })
+        ));
+
+        // The write is dropped, so the line isn't valid
+        assert!(matches!(
+            data.validate_line(gameshark::CodeLine::Write16 {
+                addr: 0x0038F800,
+                value: 0xabcd,
+            }),
+            Err(ToPatchError::UnmappedRegion {
+                addr: 0x8038F800,
+                region: "framebuffers",
+            })
+        ));
+    }
+
+    #[test]
+    fn test_raw_ignored_writes() {
+        let mut data = decomp_data();
+        data.decls.insert(
+            0x80300000,
             Decl {
-                addr,
-                kind: DeclKind::Var { typ: Type::Float },
-                name: name.to_owned(),
+                addr: 0x80300000,
+                kind: DeclKind::Var { typ: Type::Ignored },
+                name: "d".to_owned(),
             },
         );
+        let code_line = gameshark::CodeLine::Write16 {
+            addr: 0x00300001,
+            value: 0xabcd,
+        };
+
+        assert!(matches!(
+            data.gs_line_to_c(code_line, &PatchOptions::default(), &mut Vec::new()),
+            Err(ToPatchError::IgnoredType)
+        ));
+
+        let options = PatchOptions {
+            raw_ignored_writes: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            data.gs_line_to_c(code_line, &options, &mut Vec::new())
+                .unwrap(),
+            "/* 81300001 ABCD */ *((char *) &d + 0x1) = 0xab; *((char *) &d + 0x2) = 0xcd;"
+        );
+
+        // Warnings skip the write instead of failing
+        assert!(data
+            .gs_code_to_patch_warn("", gameshark::Code(vec![code_line]), &options)
+            .unwrap()
+            .warnings
+            .is_empty());
+
+        // Checks still fail
+        assert!(matches!(
+            data.gs_line_to_c(
+                gameshark::CodeLine::IfEq8 {
+                    addr: 0x00300001,
+                    value: 0xab,
+                },
+                &options,
+                &mut Vec::new()
+            ),
+            Err(ToPatchError::IgnoredType)
+        ));
     }
 
-    fn decomp_data() -> DecompData {
-        let mut data = DecompData::default();
-        add_int(&mut data, 0x8000, 1, "A");
-        add_int(&mut data, 0x8001, 1, "B");
-        add_int(&mut data, 0x8002, 1, "C");
-        add_int(&mut data, 0x8003, 1, "D");
-        add_int(&mut data, 0x8004, 4, "E");
-        add_int(&mut data, 0x8008, 4, "F");
-        add_int(&mut data, 0x800c, 2, "G");
-        add_int(&mut data, 0x800e, 2, "H");
-        add_float(&mut data, 0x8010, "f0");
-        data
+    #[test]
+    fn test_c_identifier() {
+        assert_eq!(c_identifier("Moon Jump"), "moon_jump");
+        assert_eq!(c_identifier("  Have 180 Stars!"), "have_180_stars");
+        assert_eq!(c_identifier("Mario's hat-color"), "mario_s_hat_color");
+    }
+
+    #[test]
+    fn test_constant_name() {
+        let mut constants = Vec::new();
+        assert_eq!(
+            constant_name("gMarioStates[0].flags", 0x15, &mut constants),
+            "MARIO_STATES_0_FLAGS_VALUE"
+        );
+        assert_eq!(
+            constant_name("gMarioStates[0].flags", 0x15, &mut constants),
+            "MARIO_STATES_0_FLAGS_VALUE"
+        );
+        assert_eq!(
+            constant_name("gMarioStates[0].flags", 0x20, &mut constants),
+            "MARIO_STATES_0_FLAGS_VALUE_2"
+        );
+        assert_eq!(constant_name("A", 0xaa, &mut constants), "A_VALUE");
+        assert_eq!(
+            constants,
+            vec![
+                ("MARIO_STATES_0_FLAGS_VALUE".to_owned(), 0x15),
+                ("MARIO_STATES_0_FLAGS_VALUE_2".to_owned(), 0x20),
+                ("A_VALUE".to_owned(), 0xaa),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_symbol_address() {
+        let data = decomp_data();
+
+        assert_eq!(data.symbol_address("G"), Some(0x800c));
+        assert_eq!(data.symbol_address("G[0]"), None);
+        assert_eq!(data.symbol_address("missing"), None);
+        assert_eq!(
+            data.symbols().take(3).collect::<Vec<(&str, SizeInt)>>(),
+            vec![("A", 0x8000), ("B", 0x8001), ("C", 0x8002)]
+        );
+    }
+
+    #[test]
+    fn test_decl_before() {
+        let data = decomp_data();
+
+        // Same as a linear scan for the last declaration at or before the
+        // address
+        for addr in 0x7ff0..0x9010 {
+            assert_eq!(
+                data.decl_before(addr),
+                data.decls.values().rev().find(|decl| decl.addr <= addr)
+            );
+        }
+        assert_eq!(data.decl_before(0x800d).unwrap().name, "G");
+        assert_eq!(data.decl_before(0x7fff), None);
     }
 
     #[test]
@@ -692,101 +3143,544 @@ mod tests {
         let data = decomp_data();
 
         assert_eq!(
-            data.format_write(gameshark::ValueSize::Bits8, 0xaa, 0x8000)
-                .unwrap(),
-            "A = (A & 0xffffffffffffff00) | 0xaa;"
+            data.format_write(
+                gameshark::ValueSize::Bits8,
+                0xaa,
+                0x8000,
+                &PatchOptions::default(),
+                &mut Vec::new()
+            )
+            .unwrap(),
+            // Writes of a whole lvalue are plain assignments
+            "A = 0xaa;"
         );
         assert_eq!(
-            data.format_write(gameshark::ValueSize::Bits8, 0xaa, 0x800c)
-                .unwrap(),
+            data.format_write(
+                gameshark::ValueSize::Bits8,
+                0xaa,
+                0x800c,
+                &PatchOptions::default(),
+                &mut Vec::new()
+            )
+            .unwrap(),
             "G = (G & 0xffffffffffff00ff) | 0xaa00;"
         );
         assert_eq!(
-            data.format_write(gameshark::ValueSize::Bits8, 0xaa, 0x8004)
-                .unwrap(),
+            data.format_write(
+                gameshark::ValueSize::Bits8,
+                0xaa,
+                0x8004,
+                &PatchOptions::default(),
+                &mut Vec::new()
+            )
+            .unwrap(),
             "E = (E & 0xffffffff00ffffff) | 0xaa000000;"
         );
         assert_eq!(
-            data.format_write(gameshark::ValueSize::Bits8, 0xaa, 0x800d)
-                .unwrap(),
+            data.format_write(
+                gameshark::ValueSize::Bits8,
+                0xaa,
+                0x800d,
+                &PatchOptions::default(),
+                &mut Vec::new()
+            )
+            .unwrap(),
             "G = (G & 0xffffffffffffff00) | 0xaa;"
         );
         assert_eq!(
-            data.format_write(gameshark::ValueSize::Bits16, 0xabcd, 0x800e)
-                .unwrap(),
-            "H = (H & 0xffffffffffff0000) | 0xabcd;"
+            data.format_write(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x800e,
+                &PatchOptions::default(),
+                &mut Vec::new()
+            )
+            .unwrap(),
+            "H = 0xabcd;"
         );
 
         // Write spans multiple ints
         assert_eq!(
-            data.format_write(gameshark::ValueSize::Bits16, 0xabcd, 0x8000)
-                .unwrap(),
-            "A = (A & 0xffffffffffffff00) | 0xab; B = (B & 0xffffffffffffff00) | 0xcd;"
+            data.format_write(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x8000,
+                &PatchOptions::default(),
+                &mut Vec::new()
+            )
+            .unwrap(),
+            "A = 0xab; B = 0xcd;"
         );
         assert_eq!(
-            data.format_write(gameshark::ValueSize::Bits16, 0xabcd, 0x8003)
-                .unwrap(),
-            "D = (D & 0xffffffffffffff00) | 0xab; E = (E & 0xffffffff00ffffff) | 0xcd000000;"
+            data.format_write(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x8003,
+                &PatchOptions::default(),
+                &mut Vec::new()
+            )
+            .unwrap(),
+            "D = 0xab; E = (E & 0xffffffff00ffffff) | 0xcd000000;"
         );
         assert_eq!(
-            data.format_write(gameshark::ValueSize::Bits16, 0xabcd, 0x8007)
-                .unwrap(),
+            data.format_write(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x8007,
+                &PatchOptions::default(),
+                &mut Vec::new()
+            )
+            .unwrap(),
             "E = (E & 0xffffffffffffff00) | 0xab; F = (F & 0xffffffff00ffffff) | 0xcd000000;"
         );
 
         // Floats
         assert_eq!(
-            data.format_write(gameshark::ValueSize::Bits16, 0xabcd, 0x8010)
-                .unwrap(),
+            data.format_write(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x8010,
+                &PatchOptions::default(),
+                &mut Vec::new()
+            )
+            .unwrap(),
             "*(uint32_t *) &f0 = (*(uint32_t *) &f0 & 0xffffffff0000ffff) | 0xabcd0000;"
         );
     }
 
+    #[test]
+    fn test_format_write_8_byte_int() {
+        let mut data = decomp_data();
+        add_int(&mut data, 0x8018, 8, "I");
+
+        let writes = [
+            (0x8018, "I = (I & 0xffffffffffff) | 0xabcd000000000000;"),
+            (0x801a, "I = (I & 0xffff0000ffffffff) | 0xabcd00000000;"),
+            (0x801c, "I = (I & 0xffffffff0000ffff) | 0xabcd0000;"),
+            (0x801e, "I = (I & 0xffffffffffff0000) | 0xabcd;"),
+        ];
+        for (addr, c_source) in writes {
+            assert_eq!(
+                data.format_write(
+                    gameshark::ValueSize::Bits16,
+                    0xabcd,
+                    addr,
+                    &PatchOptions::default(),
+                    &mut Vec::new()
+                )
+                .unwrap(),
+                c_source
+            );
+        }
+
+        assert_eq!(
+            data.format_check(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x8018,
+                true,
+                &PatchOptions::default()
+            )
+            .unwrap(),
+            "if ((I & 0xffff000000000000) == 0xabcd000000000000)"
+        );
+    }
+
+    #[test]
+    fn test_format_write_double() {
+        let mut data = decomp_data();
+        data.decls.insert(
+            0x8018,
+            Decl {
+                addr: 0x8018,
+                kind: DeclKind::Var { typ: Type::Double },
+                name: "d0".to_owned(),
+            },
+        );
+
+        assert_eq!(
+            data.format_write(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x801a,
+                &PatchOptions::default(),
+                &mut Vec::new()
+            )
+            .unwrap(),
+            "*(uint64_t *) &d0 = (*(uint64_t *) &d0 & 0xffff0000ffffffff) | 0xabcd00000000;"
+        );
+        assert!(data.scalar_info_at(0x801a).unwrap().is_float);
+    }
+
     #[test]
     fn test_format_check() {
         let data = decomp_data();
 
         assert_eq!(
-            data.format_check(gameshark::ValueSize::Bits8, 0xaa, 0x8000, true)
-                .unwrap(),
+            data.format_check(
+                gameshark::ValueSize::Bits8,
+                0xaa,
+                0x8000,
+                true,
+                &PatchOptions::default()
+            )
+            .unwrap(),
             "if ((A & 0xff) == 0xaa)"
         );
         assert_eq!(
-            data.format_check(gameshark::ValueSize::Bits8, 0xaa, 0x800c, true)
-                .unwrap(),
+            data.format_check(
+                gameshark::ValueSize::Bits8,
+                0xaa,
+                0x800c,
+                true,
+                &PatchOptions::default()
+            )
+            .unwrap(),
             "if ((G & 0xff00) == 0xaa00)"
         );
         assert_eq!(
-            data.format_check(gameshark::ValueSize::Bits8, 0xaa, 0x8004, true)
-                .unwrap(),
+            data.format_check(
+                gameshark::ValueSize::Bits8,
+                0xaa,
+                0x8004,
+                true,
+                &PatchOptions::default()
+            )
+            .unwrap(),
             "if ((E & 0xff000000) == 0xaa000000)"
         );
         assert_eq!(
-            data.format_check(gameshark::ValueSize::Bits8, 0xaa, 0x800d, true)
-                .unwrap(),
+            data.format_check(
+                gameshark::ValueSize::Bits8,
+                0xaa,
+                0x800d,
+                true,
+                &PatchOptions::default()
+            )
+            .unwrap(),
             "if ((G & 0xff) == 0xaa)"
         );
         assert_eq!(
-            data.format_check(gameshark::ValueSize::Bits16, 0xabcd, 0x800e, true)
-                .unwrap(),
+            data.format_check(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x800e,
+                true,
+                &PatchOptions::default()
+            )
+            .unwrap(),
             "if ((H & 0xffff) == 0xabcd)"
         );
 
         // Check spans multiple ints
         assert_eq!(
-            data.format_check(gameshark::ValueSize::Bits16, 0xabcd, 0x8000, true)
-                .unwrap(),
+            data.format_check(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x8000,
+                true,
+                &PatchOptions::default()
+            )
+            .unwrap(),
             "if ((A & 0xff) == 0xab) if ((B & 0xff) == 0xcd)"
         );
         assert_eq!(
-            data.format_check(gameshark::ValueSize::Bits16, 0xabcd, 0x8003, true)
-                .unwrap(),
+            data.format_check(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x8003,
+                true,
+                &PatchOptions::default()
+            )
+            .unwrap(),
             "if ((D & 0xff) == 0xab) if ((E & 0xff000000) == 0xcd000000)"
         );
         assert_eq!(
-            data.format_check(gameshark::ValueSize::Bits16, 0xabcd, 0x8007, true)
-                .unwrap(),
+            data.format_check(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x8007,
+                true,
+                &PatchOptions::default()
+            )
+            .unwrap(),
             "if ((E & 0xff) == 0xab) if ((F & 0xff000000) == 0xcd000000)"
         );
     }
+
+    #[test]
+    fn test_format_binary_mask() {
+        let data = decomp_data();
+        let options = PatchOptions {
+            mask_format: MaskFormat::Binary,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            data.format_write(
+                gameshark::ValueSize::Bits8,
+                0xaa,
+                0x800c,
+                &options,
+                &mut Vec::new()
+            )
+            .unwrap(),
+            "G = (G & 0b1111111111111111111111111111111111111111111111110000000011111111) \
+             | 0xaa00;"
+        );
+        assert_eq!(
+            data.format_check(gameshark::ValueSize::Bits8, 0xaa, 0x800c, true, &options)
+                .unwrap(),
+            "if ((G & 0b1111111100000000) == 0xaa00)"
+        );
+    }
+    #[test]
+    fn test_format_shift_down_check() {
+        let data = decomp_data();
+        let options = PatchOptions {
+            shift_down_checks: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            data.format_check(gameshark::ValueSize::Bits8, 0xaa, 0x800c, true, &options)
+                .unwrap(),
+            "if (((G >> 8) & 0xff) == 0xaa)"
+        );
+        assert_eq!(
+            data.format_check(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x8006,
+                false,
+                &options
+            )
+            .unwrap(),
+            "if ((E & 0xffff) != 0xabcd)"
+        );
+    }
+
+    #[test]
+    fn test_format_volatile_access() {
+        let data = decomp_data();
+        let options = PatchOptions {
+            volatile_access: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            data.format_write(
+                gameshark::ValueSize::Bits8,
+                0xaa,
+                0x800c,
+                &options,
+                &mut Vec::new()
+            )
+            .unwrap(),
+            "*(volatile uint16_t *) &G = (*(volatile uint16_t *) &G & 0xffffffffffff00ff) \
+             | 0xaa00;"
+        );
+        assert_eq!(
+            data.format_check(gameshark::ValueSize::Bits16, 0x3f80, 0x8010, true, &options)
+                .unwrap(),
+            "if ((*(volatile uint32_t *) &f0 & 0xffff0000) == 0x3f800000)"
+        );
+    }
+
+    #[test]
+    fn test_format_narrowing_casts() {
+        let data = decomp_data();
+        let options = PatchOptions {
+            narrowing_casts: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            data.format_write(
+                gameshark::ValueSize::Bits8,
+                0xaa,
+                0x800c,
+                &options,
+                &mut Vec::new()
+            )
+            .unwrap(),
+            "G = (uint16_t) ((G & 0xffffffffffff00ff) | 0xaa00);"
+        );
+        assert_eq!(
+            data.format_write(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x8010,
+                &options,
+                &mut Vec::new()
+            )
+            .unwrap(),
+            "*(uint32_t *) &f0 = (uint32_t) ((*(uint32_t *) &f0 & 0xffffffff0000ffff) | 0xabcd0000);"
+        );
+
+        // Writes of a whole field aren't cast
+        assert_eq!(
+            data.format_write(
+                gameshark::ValueSize::Bits16,
+                0xabcd,
+                0x800e,
+                &options,
+                &mut Vec::new()
+            )
+            .unwrap(),
+            "H = 0xabcd;"
+        );
+    }
+
+    #[test]
+    fn test_format_unsigned_literals() {
+        let data = decomp_data();
+        let options = PatchOptions {
+            unsigned_literals: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            data.format_write(
+                gameshark::ValueSize::Bits8,
+                0xaa,
+                0x8004,
+                &options,
+                &mut Vec::new()
+            )
+            .unwrap(),
+            "E = (E & 0xffffffff00ffffffull) | 0xaa000000u;"
+        );
+        assert_eq!(
+            data.format_check(gameshark::ValueSize::Bits16, 0x3f80, 0x8010, true, &options)
+                .unwrap(),
+            "if ((*(uint32_t *) &f0 & 0xffff0000u) == 0x3f800000u)"
+        );
+    }
+
+    #[test]
+    fn test_format_fn_check() {
+        let mut data = decomp_data();
+        data.decls.insert(
+            0x9000,
+            Decl {
+                addr: 0x9000,
+                kind: DeclKind::Fn,
+                name: "func".to_owned(),
+            },
+        );
+        let options = PatchOptions {
+            fn_checks: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            data.format_check(gameshark::ValueSize::Bits16, 0x2402, 0x9004, true, &options)
+                .unwrap(),
+            "if (*(uint8_t *) ((char *) &func + 0x4) == 0x24 \
+             && *(uint8_t *) ((char *) &func + 0x5) == 0x2)"
+        );
+        assert_eq!(
+            data.format_check(gameshark::ValueSize::Bits8, 0x24, 0x9004, false, &options)
+                .unwrap(),
+            "if (*(uint8_t *) ((char *) &func + 0x4) != 0x24)"
+        );
+
+        // Writes to functions still fail
+        assert!(matches!(
+            data.format_write(
+                gameshark::ValueSize::Bits8,
+                0x24,
+                0x9004,
+                &options,
+                &mut Vec::new()
+            ),
+            Err(ToPatchError::FnPatch { addr: 0x9004 })
+        ));
+        assert!(matches!(
+            data.format_check(
+                gameshark::ValueSize::Bits8,
+                0x24,
+                0x9004,
+                true,
+                &Default::default()
+            ),
+            Err(ToPatchError::FnPatch { addr: 0x9004 })
+        ));
+    }
+
+    #[test]
+    fn test_const_pointer() {
+        let mut data = decomp_data();
+        add_int(&mut data, 0x80300000, 4, "gTarget");
+        data.decls.insert(
+            0x80300004,
+            Decl {
+                addr: 0x80300004,
+                kind: DeclKind::Var {
+                    typ: Type::Pointer {
+                        inner_type: Box::new(Type::Int {
+                            signed: false,
+                            num_bytes: 4,
+                        }),
+                    },
+                },
+                name: "gTargetPtr".to_owned(),
+            },
+        );
+        let code = gameshark::CodeLine::Write16 {
+            addr: 0x300006,
+            value: 0x1234,
+        };
+
+        // Without a known target, writing the pointer is an error
+        assert!(matches!(
+            data.gs_line_to_c(code, &PatchOptions::default(), &mut Vec::new()),
+            Err(ToPatchError::PointerAssign { addr: 0x80300006 })
+        ));
+
+        // Writes only resolve through the pointer to the symbol it points to
+        // with the option
+        data.pointer_targets
+            .insert("gTargetPtr".to_owned(), "gTarget".to_owned());
+        assert!(matches!(
+            data.gs_line_to_c(code, &PatchOptions::default(), &mut Vec::new()),
+            Err(ToPatchError::PointerAssign { addr: 0x80300006 })
+        ));
+        let options = PatchOptions {
+            resolve_const_pointers: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            data.gs_line_to_c(code, &options, &mut Vec::new()).unwrap(),
+            "/* 81300006 1234 */ gTarget = (gTarget & 0xffffffffffff0000) | 0x1234;"
+        );
+    }
+
+    #[test]
+    fn test_friendly_names() {
+        let mut data = decomp_data();
+        add_int(&mut data, 0x80300000, 2, "D_80300000");
+        data.set_friendly_name("D_80300000", "gCheatCoins");
+
+        let code = gameshark::CodeLine::Write16 {
+            addr: 0x300000,
+            value: 0x5,
+        };
+        let options = PatchOptions {
+            verbose_comments: true,
+            ..Default::default()
+        };
+
+        // Only the comment uses the readable name
+        assert_eq!(
+            data.gs_line_to_c(code, &options, &mut Vec::new()).unwrap(),
+            "/* 81300000 0005 -> 0x80300000 gCheatCoins */ \
+             D_80300000 = 0x5;"
+        );
+        assert_eq!(
+            data.explain(&gameshark::Code(vec![code])).unwrap(),
+            vec!["81300000 0005: write 0x0005 to gCheatCoins"]
+        );
+    }
 }
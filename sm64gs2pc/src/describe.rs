@@ -0,0 +1,172 @@
+//! Plain English descriptions of GameShark codes
+
+use crate::decomp_data::DecompData;
+use crate::decomp_data::ToPatchError;
+use crate::gameshark;
+use crate::gameshark::CodeLine;
+use crate::left_value::LeftValue;
+use crate::left_value::LeftValueKind;
+use crate::typ::SizeInt;
+
+/// Readable names for the starts of lvalues
+const PRETTY_PREFIXES: &[(&str, &str)] = &[
+    ("gMarioStates[0].", "Mario's "),
+    ("gControllers[0].", "controller 1's "),
+    ("gHudDisplay.", "HUD "),
+];
+
+/// Meanings of bits written to lvalues
+const BIT_NOTES: &[(&str, u64, &str)] = &[
+    ("gMarioStates[0].flags", 0x2, "vanish cap"),
+    ("gMarioStates[0].flags", 0x4, "metal cap"),
+    ("gMarioStates[0].flags", 0x8, "wing cap"),
+];
+
+impl DecompData {
+    /// Describe what each line of a GameShark code does in plain English
+    ///
+    /// This is like `DecompData::explain`, but uses readable names for
+    /// well-known SM64 variables, like `Mario's flags`, and notes the meaning
+    /// of well-known values. There is one description for each line, for use
+    /// as a bulleted list.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark;
+    ///
+    /// let code = "8133B176 0015".parse::<gameshark::Code>().unwrap();
+    ///
+    /// assert_eq!(
+    ///     sm64gs2pc::DECOMP_DATA_STATIC.describe(&code).unwrap(),
+    ///     vec!["Set Mario's flags to 0x15 (metal cap)"],
+    /// );
+    /// ```
+    pub fn describe(&self, code: &gameshark::Code) -> Result<Vec<String>, ToPatchError> {
+        code.expand_repeats()
+            .0
+            .iter()
+            .map(|line| self.describe_line(*line))
+            .collect()
+    }
+
+    /// Describe a single code line
+    fn describe_line(&self, line: CodeLine) -> Result<String, ToPatchError> {
+        let addr = line.addr() + 0x80000000;
+
+        let (write_size, value) = match line {
+            CodeLine::Write8 { value, .. } | CodeLine::Write8OnBtn { value, .. } => {
+                (gameshark::ValueSize::Bits8, value as u64)
+            }
+            CodeLine::Write16 { value, .. } | CodeLine::Write16OnBtn { value, .. } => {
+                (gameshark::ValueSize::Bits16, value as u64)
+            }
+            CodeLine::Repeat { .. } => unreachable!("repeater wasn't expanded"),
+            CodeLine::Master { .. } => return Ok(String::from("Ignore the master code")),
+            CodeLine::IfEq8 { value, .. } | CodeLine::IfNotEq8 { value, .. } => {
+                return self.describe_check(line, addr, value as u64)
+            }
+            CodeLine::IfEq16 { value, .. } | CodeLine::IfNotEq16 { value, .. } => {
+                return self.describe_check(line, addr, value as u64)
+            }
+        };
+
+        let mut values = Vec::new();
+        self.written_values_at(write_size, value, addr, &mut values)?;
+
+        Ok(values
+            .iter()
+            .map(|(lvalue, value)| describe_write(lvalue, *value))
+            .collect::<Vec<String>>()
+            .join(", and "))
+    }
+
+    /// Describe a check code line
+    fn describe_check(
+        &self,
+        line: CodeLine,
+        addr: SizeInt,
+        value: u64,
+    ) -> Result<String, ToPatchError> {
+        let lvalue = self.addr_to_lvalue(addr)?;
+
+        let target = match addr - lvalue.addr {
+            0 => pretty_name(&lvalue),
+            offset => format!("byte {} of {}", offset, pretty_name(&lvalue)),
+        };
+        let check = match line {
+            CodeLine::IfEq8 { .. } | CodeLine::IfEq16 { .. } => "is",
+            _ => "is not",
+        };
+
+        Ok(format!("If {} {} 0x{:X}", target, check, value))
+    }
+}
+
+/// Describe a write of `value` to `lvalue`
+fn describe_write(lvalue: &LeftValue, value: u64) -> String {
+    if let Some((file, field, course)) = save_course_access(lvalue) {
+        let field = match field {
+            "courseStars" => "stars",
+            _ => "coin score",
+        };
+        return format!(
+            "Write 0x{:X} to save file {} course {} {}",
+            value,
+            file + 1,
+            course,
+            field
+        );
+    }
+
+    let name = lvalue.kind.to_string();
+    let notes = BIT_NOTES
+        .iter()
+        .filter(|(lvalue, bit, _)| *lvalue == name && value & bit != 0)
+        .map(|(_, _, note)| *note)
+        .collect::<Vec<&str>>();
+    let notes = match notes.as_slice() {
+        [] => String::new(),
+        notes => format!(" ({})", notes.join(", ")),
+    };
+
+    format!("Set {} to 0x{:X}{}", pretty_name(lvalue), value, notes)
+}
+
+/// Get a readable name for an lvalue
+fn pretty_name(lvalue: &LeftValue) -> String {
+    let name = lvalue.kind.to_string();
+
+    PRETTY_PREFIXES
+        .iter()
+        .find_map(|(prefix, pretty)| {
+            name.strip_prefix(prefix)
+                .map(|rest| format!("{}{}", pretty, rest))
+        })
+        .unwrap_or(name)
+}
+
+/// Get the save file, field, and course of an lvalue like
+/// `gSaveBuffer.files[0][0].courseStars[3]`
+fn save_course_access(lvalue: &LeftValue) -> Option<(SizeInt, &str, SizeInt)> {
+    let (course_array, course) = match &lvalue.kind {
+        LeftValueKind::ArrayIndex { array, index } => (array, *index),
+        _ => return None,
+    };
+    let (save_file, field) = match &course_array.kind {
+        LeftValueKind::StructField {
+            struct_,
+            field_name,
+        } if field_name == "courseStars" || field_name == "courseCoinScores" => {
+            (struct_, field_name.as_str())
+        }
+        _ => return None,
+    };
+    // Each save file has a backup copy, which is the inner index
+    let files = match &save_file.kind {
+        LeftValueKind::ArrayIndex { array, .. } => array,
+        _ => return None,
+    };
+    match &files.kind {
+        LeftValueKind::ArrayIndex { index: file, .. } => Some((*file, field, course)),
+        _ => None,
+    }
+}
@@ -0,0 +1,435 @@
+//! Human-readable explanations of GameShark codes
+
+use crate::decomp_data::DecompData;
+use crate::decomp_data::ToPatchError;
+use crate::gameshark;
+use crate::gameshark::CodeLine;
+use crate::left_value::LeftValue;
+use crate::left_value::LeftValueKind;
+use crate::typ::SizeInt;
+use crate::typ::Type;
+
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+/// One step of resolving an address, from `DecompData::resolution_path`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolutionStep {
+    /// C expression of the step, like `gSaveBuffer.files[0]`
+    pub expr: String,
+    /// C type of the step, like `struct SaveFile[2]`
+    pub type_name: String,
+    /// Address of the start of the step
+    pub addr: SizeInt,
+}
+
+/// A value that a check compares a field with, from
+/// `DecompData::compared_values`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComparedValue {
+    /// C expression of the field, like `gMarioStates[0].vel[1]`
+    pub field: String,
+    /// Value from the code, before it's shifted to its position in the field
+    pub value: u64,
+    /// Amount of bits that the value is shifted left by to compare it with
+    /// the field
+    pub shift: SizeInt,
+    /// Whether the check passes if the field is equal to the value, rather
+    /// than not equal
+    pub check_eq: bool,
+}
+
+impl DecompData {
+    /// Get each enclosing scope of an address, from its declaration down to
+    /// the scalar at the address
+    ///
+    /// Each step is a declaration, struct field, or array element that
+    /// contains the next step.
+    ///
+    /// ```
+    /// let path = sm64gs2pc::DECOMP_DATA_STATIC
+    ///     .resolution_path(0x8033B176)
+    ///     .unwrap();
+    ///
+    /// assert_eq!(path[0].expr, "gMarioStates");
+    /// assert_eq!(path.last().unwrap().expr, "gMarioStates[0].flags");
+    /// ```
+    ///
+    /// ## Errors
+    /// This function fails if the address doesn't resolve to a scalar.
+    pub fn resolution_path(&self, addr: SizeInt) -> Result<Vec<ResolutionStep>, ToPatchError> {
+        let mut steps = Vec::new();
+        push_resolution_steps(&self.addr_to_lvalue(addr)?, &mut steps);
+        Ok(steps)
+    }
+
+    /// Explain what each line of a GameShark code does
+    ///
+    /// Each explanation starts with the code lines it covers, and uses the
+    /// readable names of symbols from `DecompData::set_friendly_name`. A pair
+    /// of 16-bit writes that sets both halves of a `float` is explained as one
+    /// write of the reconstructed `float` value.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark;
+    ///
+    /// let code = "8133B1BC 4220\n8133B1BE 0000"
+    ///     .parse::<gameshark::Code>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     sm64gs2pc::DECOMP_DATA_STATIC.explain(&code).unwrap(),
+    ///     vec!["8133B1BC 4220, 8133B1BE 0000: set gMarioStates[0].vel[1] to 40.0"],
+    /// );
+    /// ```
+    pub fn explain(&self, code: &gameshark::Code) -> Result<Vec<String>, ToPatchError> {
+        let mut explanations = Vec::new();
+        let mut lines = code.0.iter().copied().peekable();
+
+        while let Some(line) = lines.next() {
+            // Try to explain this line and the next as one `float` write
+            if let Some(&next) = lines.peek() {
+                if let Some(explanation) = self.explain_float_write(line, next)? {
+                    explanations.push(explanation);
+                    lines.next();
+                    continue;
+                }
+            }
+
+            explanations.push(self.explain_line(line)?);
+        }
+
+        Ok(explanations)
+    }
+
+    /// Get the names of the structs whose fields a GameShark code reads or
+    /// writes, sorted by name
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark;
+    ///
+    /// let code = "D033AFA1 0020\n8133B1BC 4220"
+    ///     .parse::<gameshark::Code>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     sm64gs2pc::DECOMP_DATA_STATIC.touched_structs(&code).unwrap(),
+    ///     vec!["Controller", "MarioState"],
+    /// );
+    /// ```
+    ///
+    /// ## Errors
+    /// This function fails if the address of a code line can't be resolved.
+    /// Addresses in memory regions without symbols touch no structs, so they
+    /// are skipped.
+    pub fn touched_structs(&self, code: &gameshark::Code) -> Result<Vec<String>, ToPatchError> {
+        let mut names = BTreeSet::new();
+
+        for line in &code.expand_repeats().0 {
+            if let CodeLine::Master { .. } = line {
+                continue;
+            }
+
+            match self.addr_to_lvalue(line.addr() + 0x80000000) {
+                Ok(lvalue) => lvalue.collect_struct_names(&mut names),
+                Err(ToPatchError::UnmappedRegion { .. }) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(names.into_iter().collect())
+    }
+
+    /// Get the field that each write of a GameShark code stores to, and the
+    /// value stored in it
+    ///
+    /// The value is shifted to its position within the field, so a write to
+    /// the low byte of a field gives the field's value, not a masked
+    /// expression. Writes that span two fields give a value for each field.
+    /// Checks don't store anything, so they are skipped.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark;
+    ///
+    /// let code = "8133B176 0015".parse::<gameshark::Code>().unwrap();
+    ///
+    /// assert_eq!(
+    ///     sm64gs2pc::DECOMP_DATA_STATIC.written_values(&code).unwrap(),
+    ///     vec![(String::from("gMarioStates[0].flags"), 0x15)],
+    /// );
+    /// ```
+    pub fn written_values(
+        &self,
+        code: &gameshark::Code,
+    ) -> Result<Vec<(String, u64)>, ToPatchError> {
+        let mut values = Vec::new();
+
+        for line in &code.expand_repeats().0 {
+            let (write_size, value) = match *line {
+                CodeLine::Write8 { value, .. } | CodeLine::Write8OnBtn { value, .. } => {
+                    (gameshark::ValueSize::Bits8, value as u64)
+                }
+                CodeLine::Write16 { value, .. } | CodeLine::Write16OnBtn { value, .. } => {
+                    (gameshark::ValueSize::Bits16, value as u64)
+                }
+                _ => continue,
+            };
+
+            self.written_values_at(write_size, value, line.addr() + 0x80000000, &mut values)?;
+        }
+
+        Ok(values
+            .into_iter()
+            .map(|(lvalue, value)| (lvalue.kind.to_string(), value))
+            .collect())
+    }
+
+    /// Get the field that a check reads and the value that it compares the
+    /// field with, as written in the code
+    ///
+    /// The generated C source compares the field with the value shifted to
+    /// its position, like `0x200000`, so this gives the value from the code,
+    /// like `0x20`, and the shift. A check that spans two fields gives a value
+    /// for each field. Writes aren't checks, so they give no values.
+    ///
+    /// ```
+    /// use sm64gs2pc::ComparedValue;
+    ///
+    /// let check = "D033B1BD 0020".parse().unwrap();
+    ///
+    /// assert_eq!(
+    ///     sm64gs2pc::DECOMP_DATA_STATIC.compared_values(check).unwrap(),
+    ///     vec![ComparedValue {
+    ///         field: String::from("gMarioStates[0].vel[1]"),
+    ///         value: 0x20,
+    ///         shift: 16,
+    ///         check_eq: true,
+    ///     }],
+    /// );
+    /// ```
+    ///
+    /// ## Errors
+    /// This function fails if the address of the check can't be resolved.
+    pub fn compared_values(&self, check: CodeLine) -> Result<Vec<ComparedValue>, ToPatchError> {
+        let (read_size, value, check_eq) = match check {
+            CodeLine::IfEq8 { value, .. } => (gameshark::ValueSize::Bits8, value as u64, true),
+            CodeLine::IfEq16 { value, .. } => (gameshark::ValueSize::Bits16, value as u64, true),
+            CodeLine::IfNotEq8 { value, .. } => (gameshark::ValueSize::Bits8, value as u64, false),
+            CodeLine::IfNotEq16 { value, .. } => {
+                (gameshark::ValueSize::Bits16, value as u64, false)
+            }
+            _ => return Ok(vec![]),
+        };
+
+        let mut values = Vec::new();
+        let mut addr = check.addr() + 0x80000000;
+        let mut read_size = read_size;
+        let mut value = value;
+        loop {
+            let lvalue = self.addr_to_lvalue(addr)?;
+            match self.lvalue_get_shift(&lvalue, read_size, addr)? {
+                // Read is entirely within one lvalue
+                Some(shift) => {
+                    values.push(ComparedValue {
+                        field: lvalue.kind.to_string(),
+                        value,
+                        shift,
+                        check_eq,
+                    });
+                    return Ok(values);
+                }
+
+                // Read overlaps multiple lvalues, like in
+                // `DecompData::format_conditions`
+                None => {
+                    values.push(ComparedValue {
+                        field: lvalue.kind.to_string(),
+                        value: value >> 8,
+                        shift: 0,
+                        check_eq,
+                    });
+                    addr += 1;
+                    read_size = gameshark::ValueSize::Bits8;
+                    value &= 0xff;
+                }
+            }
+        }
+    }
+
+    /// Add the lvalues and values stored by a write to `values`
+    ///
+    /// This splits the write like `DecompData::format_write` does.
+    pub(crate) fn written_values_at(
+        &self,
+        write_size: gameshark::ValueSize,
+        value: u64,
+        addr: SizeInt,
+        values: &mut Vec<(LeftValue, u64)>,
+    ) -> Result<(), ToPatchError> {
+        let lvalue = self.addr_to_lvalue(addr)?;
+
+        match self.lvalue_get_shift(&lvalue, write_size, addr)? {
+            // Write is entirely within one lvalue
+            Some(shift) => values.push((lvalue, value << shift)),
+
+            // Write overlaps multiple lvalues
+            None => {
+                values.push((lvalue, value >> 8));
+                self.written_values_at(
+                    gameshark::ValueSize::Bits8,
+                    value & 0xff,
+                    addr + 1,
+                    values,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Explain two code lines as a write of a whole `float`, if they are
+    /// 16-bit writes to both of its halves
+    fn explain_float_write(
+        &self,
+        first: CodeLine,
+        second: CodeLine,
+    ) -> Result<Option<String>, ToPatchError> {
+        let (first_half, second_half) = match (first, second) {
+            (
+                CodeLine::Write16 {
+                    addr: first_addr,
+                    value: first_value,
+                },
+                CodeLine::Write16 {
+                    addr: second_addr,
+                    value: second_value,
+                },
+            ) => ((first_addr, first_value), (second_addr, second_value)),
+            _ => return Ok(None),
+        };
+
+        // The N64 is big-endian, so the high half has the lower address
+        let ((high_addr, high_value), (low_addr, low_value)) = if first_half.0 < second_half.0 {
+            (first_half, second_half)
+        } else {
+            (second_half, first_half)
+        };
+        if low_addr != high_addr + 2 {
+            return Ok(None);
+        }
+
+        let high_addr = high_addr + 0x80000000;
+        let lvalue = self.addr_to_lvalue(high_addr)?;
+        if lvalue.typ != Type::Float || lvalue.addr != high_addr {
+            return Ok(None);
+        }
+
+        let value = f32::from_bits(((high_value as u32) << 16) | low_value as u32);
+        Ok(Some(format!(
+            "{}, {}: set {} to {:?}",
+            first,
+            second,
+            self.friendly_lvalue(&lvalue),
+            value
+        )))
+    }
+
+    /// Explain a single code line
+    fn explain_line(&self, line: CodeLine) -> Result<String, ToPatchError> {
+        // Repeaters don't access memory themselves
+        if let CodeLine::Repeat {
+            count,
+            addr_step,
+            value_step,
+        } = line
+        {
+            return Ok(format!(
+                "{}: repeat the next write {} times, adding {:#x} to its address and {:#x} to \
+                 its value each time",
+                line, count, addr_step, value_step
+            ));
+        }
+        if let CodeLine::Master { .. } = line {
+            return Ok(format!("{}: ignored master code", line));
+        }
+
+        let addr = line.addr() + 0x80000000;
+        let lvalue = self.addr_to_lvalue(addr)?;
+
+        // Describe where in the lvalue the line accesses
+        let name = self.friendly_lvalue(&lvalue);
+        let target = match addr - lvalue.addr {
+            0 => name,
+            offset => format!("{} at byte offset {}", name, offset),
+        };
+
+        let explanation = match line {
+            CodeLine::Write8 { value, .. } => format!("write {:#04x} to {}", value, target),
+            CodeLine::Write16 { value, .. } => format!("write {:#06x} to {}", value, target),
+            CodeLine::Write8OnBtn { value, .. } => format!(
+                "write {:#04x} to {} while the GameShark button is held",
+                value, target
+            ),
+            CodeLine::Write16OnBtn { value, .. } => format!(
+                "write {:#06x} to {} while the GameShark button is held",
+                value, target
+            ),
+            CodeLine::Repeat { .. } | CodeLine::Master { .. } => {
+                unreachable!("line without an address was already explained")
+            }
+            CodeLine::IfEq8 { value, .. } => format!("if {} is {:#04x}", target, value),
+            CodeLine::IfEq16 { value, .. } => format!("if {} is {:#06x}", target, value),
+            CodeLine::IfNotEq8 { value, .. } => format!("if {} is not {:#04x}", target, value),
+            CodeLine::IfNotEq16 { value, .. } => format!("if {} is not {:#06x}", target, value),
+        };
+
+        Ok(format!("{}: {}", line, explanation))
+    }
+}
+
+/// Push the steps of an lvalue's enclosing lvalues, then the lvalue itself
+fn push_resolution_steps(lvalue: &LeftValue, steps: &mut Vec<ResolutionStep>) {
+    match &lvalue.kind {
+        LeftValueKind::Ident { .. } => {}
+        LeftValueKind::ArrayIndex { array, .. } => push_resolution_steps(array, steps),
+        LeftValueKind::StructField { struct_, .. } => push_resolution_steps(struct_, steps),
+    }
+
+    steps.push(ResolutionStep {
+        expr: lvalue.kind.to_string(),
+        type_name: type_name(&lvalue.typ),
+        addr: lvalue.addr,
+    });
+}
+
+/// Get the C name of a type, like `struct SaveFile[2]` or `u8`
+fn type_name(typ: &Type) -> String {
+    match typ {
+        Type::AnonStruct(_) => String::from("struct { ... }"),
+        Type::Struct { name } => format!("struct {}", name),
+        Type::Array {
+            element_type,
+            num_elements,
+        } => {
+            // Inner dimensions come after outer dimensions, like `u8[4][2]`
+            let element_name = type_name(element_type);
+            match element_name.find('[') {
+                Some(i) => format!(
+                    "{}[{}]{}",
+                    &element_name[..i],
+                    num_elements,
+                    &element_name[i..]
+                ),
+                None => format!("{}[{}]", element_name, num_elements),
+            }
+        }
+        Type::Int { signed, num_bytes } => {
+            format!("{}{}", if *signed { "s" } else { "u" }, num_bytes * 8)
+        }
+        Type::Pointer { inner_type } => format!("{} *", type_name(inner_type)),
+        Type::Float => String::from("f32"),
+        Type::Double => String::from("f64"),
+        Type::Ignored => String::from("(unsupported type)"),
+    }
+}
@@ -0,0 +1,151 @@
+//! Recovering GameShark codes from patches created by this tool
+
+use crate::decomp_data::DecompData;
+use crate::decomp_data::METADATA_TAG;
+use crate::gameshark;
+use crate::gameshark::CodeLine;
+
+use snafu::ResultExt;
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+pub enum FromPatchError {
+    #[snafu(display("Patch parse error: {}", message))]
+    PatchParse { message: String },
+
+    #[snafu(display("{}: Code line in patch comment is invalid: {}", line, source))]
+    CodeLineParse {
+        line: String,
+        source: gameshark::ParseError,
+    },
+
+    #[snafu(display("{}: Code line in patch comes before any cheat name", line))]
+    NoCheatName { line: String },
+}
+
+impl DecompData {
+    /// Recover the cheat names and GameShark codes from a patch created by
+    /// this tool
+    ///
+    /// Each code line is recovered from the `/* TTXXXXXX YYYY */` comment at
+    /// the start of its line of C source code. A line for several code lines,
+    /// like folded checks or an array fill, starts with a comment for each.
+    /// Each cheat name is recovered from the comment before the cheat's lines.
+    /// Lines of the patch that aren't added lines are ignored.
+    ///
+    /// ## Parameters
+    ///   * `patch` - Patch in the unified diff format
+    ///
+    /// ## Errors
+    /// This function fails if
+    ///   * The patch can't be parsed
+    ///   * A comment shaped like a code line isn't a valid code line
+    ///   * A code line comes before any cheat name
+    pub fn patch_to_code(
+        &self,
+        patch: &str,
+    ) -> Result<Vec<(String, gameshark::Code)>, FromPatchError> {
+        // The parser expects the patch to end with a newline
+        let patch = if patch.ends_with('\n') {
+            patch.to_owned()
+        } else {
+            format!("{}\n", patch)
+        };
+
+        let patches =
+            patch::Patch::from_multiple(&patch).map_err(|err| FromPatchError::PatchParse {
+                message: err.to_string(),
+            })?;
+
+        let mut cheats = Vec::<(String, gameshark::Code)>::new();
+
+        let added_lines = patches
+            .iter()
+            .flat_map(|patch| patch.hunks.iter())
+            .flat_map(|hunk| hunk.lines.iter())
+            .filter_map(|line| match line {
+                patch::Line::Add(line) => Some(line.trim()),
+                _ => None,
+            });
+
+        for line in added_lines {
+            // Get the text of the comment at the start of the line
+            let (comment, mut rest) = match line
+                .strip_prefix("/* ")
+                .and_then(|line| line.split_once(" */"))
+            {
+                Some(comment_rest) => comment_rest,
+                None => continue,
+            };
+
+            // Lines for several code lines have a comment for each
+            let mut folded_code_lines = Vec::new();
+            while let Some((next_comment, next_rest)) = rest
+                .strip_prefix(" /* ")
+                .and_then(|rest| rest.split_once(" */"))
+            {
+                let next_code_line = next_comment.split(" -> ").next().unwrap_or(next_comment);
+                match next_code_line.parse::<CodeLine>() {
+                    Ok(code_line) => folded_code_lines.push(code_line),
+                    Err(_) => break,
+                }
+                rest = next_rest;
+            }
+
+            // Verbose comments have the resolved address after the code line
+            let code_line = comment.split(" -> ").next().unwrap_or(comment);
+
+            match code_line.parse::<CodeLine>() {
+                // Code line of the current cheat
+                Ok(code_line) => {
+                    let (_, code) = cheats.last_mut().ok_or(FromPatchError::NoCheatName {
+                        line: line.to_owned(),
+                    })?;
+                    code.0.push(code_line);
+                    code.0.extend(folded_code_lines);
+                }
+
+                // Section comments group cheats, so they aren't cheat names
+                Err(_) if comment.starts_with("===== ") && comment.ends_with(" =====") => {}
+
+                // Metadata comments describe the next cheat
+                Err(_) if comment.starts_with(METADATA_TAG) => {}
+
+                // A comment on its own line is the name of a new cheat. Cheats
+                // wrapped in functions also say how to register the function.
+                Err(_) if rest.is_empty() => {
+                    let name = match comment.split_once(": register cheat_") {
+                        Some((name, _)) => name,
+                        None => comment,
+                    };
+                    cheats.push((name.to_owned(), gameshark::Code(Vec::new())));
+                }
+
+                // A code line that isn't valid can't have been created by this
+                // tool
+                Err(err) if looks_like_code_line(code_line) => {
+                    return Err(err).context(CodeLineParseSnafu {
+                        line: line.to_owned(),
+                    });
+                }
+
+                // Some other comment
+                Err(_) => {}
+            }
+        }
+
+        Ok(cheats)
+    }
+}
+
+/// Check if text has the shape of a GameShark code line, `TTXXXXXX YYYY`
+fn looks_like_code_line(text: &str) -> bool {
+    match text.split_once(' ') {
+        Some((type_addr, value)) => {
+            type_addr.len() == 8
+                && value.len() == 4
+                && text.chars().all(|c| c == ' ' || c.is_ascii_alphanumeric())
+        }
+        None => false,
+    }
+}
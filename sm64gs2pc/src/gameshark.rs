@@ -22,11 +22,20 @@
 //! ```
 
 use crate::typ::SizeInt;
+use crate::warning::Warning;
+use crate::warning::WarningKind;
 
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::fmt;
+use std::io::BufRead;
+use std::iter::FromIterator;
 use std::str::FromStr;
 
+use serde::Deserialize;
+use serde::Serialize;
 use snafu::ensure;
+use snafu::OptionExt;
 use snafu::ResultExt;
 use snafu::Snafu;
 
@@ -57,10 +66,89 @@ pub enum ParseError {
         /// Code type that isn't known
         code_type: u8,
     },
+
+    /// Error parsing a line of streamed input
+    #[snafu(display("Line {}: {}", line_number, source))]
+    LineError {
+        /// Number of the line, starting at 1
+        line_number: usize,
+        /// Error parsing the line
+        #[snafu(source(from(ParseError, Box::new)))]
+        source: Box<ParseError>,
+    },
+
+    /// Error reading a line of streamed input
+    #[snafu(display("Line {}: Read error: {}", line_number, source))]
+    ReadError {
+        /// Number of the line, starting at 1
+        line_number: usize,
+        /// Error reading the line
+        source: std::io::Error,
+    },
+
+    /// Code has more lines than allowed
+    #[snafu(display("Code has more than {} lines", max_lines))]
+    TooManyLinesError {
+        /// Maximum amount of lines
+        max_lines: usize,
+    },
+
+    /// Line of a cheat set that is neither a cheat name nor a code line
+    #[snafu(display("Line {}: Expected a cheat name followed by ':'", line_number))]
+    CheatNameError {
+        /// Number of the line, starting at 1
+        line_number: usize,
+    },
+
+    /// Code line of a cheat set that comes before any cheat name
+    #[snafu(display("Line {}: Code line comes before any cheat name", line_number))]
+    NoCheatNameError {
+        /// Number of the line, starting at 1
+        line_number: usize,
+    },
+}
+
+impl ParseError {
+    /// Get a stable, machine-readable code for the kind of error, like
+    /// `"code-type"`
+    ///
+    /// Unlike the display text, this doesn't change between versions, so
+    /// programs can handle kinds of errors without matching text. Errors for a
+    /// line of streamed input have the code of the error parsing the line.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::ParseIntError { .. } => "parse-int",
+            ParseError::FormatError { .. } => "format",
+            ParseError::CodeTypeError { .. } => "code-type",
+            ParseError::LineError { source, .. } => source.code(),
+            ParseError::ReadError { .. } => "read",
+            ParseError::TooManyLinesError { .. } => "too-many-lines",
+            ParseError::CheatNameError { .. } => "cheat-name",
+            ParseError::NoCheatNameError { .. } => "no-cheat-name",
+        }
+    }
+}
+
+/// Structural error in a GameShark code
+#[derive(Debug, Snafu)]
+pub enum StructureError {
+    /// Check at the end of a code, with no line to guard
+    #[snafu(display("{}: Check is at the end of the code, so it guards nothing", code_line))]
+    DanglingCheck {
+        /// Line of the check
+        code_line: String,
+    },
+
+    /// Repeater that isn't followed by a write to repeat
+    #[snafu(display("{}: Repeater isn't followed by an 80 or 81 write", code_line))]
+    DanglingRepeat {
+        /// Line of the repeater
+        code_line: String,
+    },
 }
 
 /// A parsed line of a Nintendo 64 GameShark code
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum CodeLine {
     /// 8-bit Write
     ///
@@ -68,7 +156,9 @@ pub enum CodeLine {
     /// 80XXXXXX 00YY
     /// ```
     ///
-    /// Writes `YY` to address `XXXXXX`.
+    /// Writes `YY` to address `XXXXXX`. Writes to the uncached mirror of
+    /// memory, `A0XXXXXX 00YY`, are parsed as this, since they write the same
+    /// memory.
     Write8 {
         /// Address of write `XXXXXX`
         addr: SizeInt,
@@ -82,7 +172,9 @@ pub enum CodeLine {
     /// 81XXXXXX YYYY
     /// ```
     ///
-    /// Writes `YYYY` to address `XXXXXX`.
+    /// Writes `YYYY` to address `XXXXXX`. Writes to the uncached mirror of
+    /// memory, `A1XXXXXX YYYY`, are parsed as this, since they write the same
+    /// memory.
     Write16 {
         /// Address of write `XXXXXX`
         addr: SizeInt,
@@ -90,6 +182,72 @@ pub enum CodeLine {
         value: u16,
     },
 
+    /// 8-bit Write while the GameShark button is held
+    ///
+    /// ```text
+    /// 88XXXXXX 00YY
+    /// ```
+    ///
+    /// Writes `YY` to address `XXXXXX` while the GameShark button is held.
+    Write8OnBtn {
+        /// Address of write `XXXXXX`
+        addr: SizeInt,
+        /// Written value `YY`
+        value: u8,
+    },
+
+    /// 16-bit Write while the GameShark button is held
+    ///
+    /// ```text
+    /// 89XXXXXX YYYY
+    /// ```
+    ///
+    /// Writes `YYYY` to address `XXXXXX` while the GameShark button is held.
+    Write16OnBtn {
+        /// Address of write `XXXXXX`
+        addr: SizeInt,
+        /// Written value `YYYY`
+        value: u16,
+    },
+
+    /// Repeater
+    ///
+    /// ```text
+    /// 50XXXXYY ZZZZ
+    /// TTAAAAAA VVVV
+    /// ```
+    ///
+    /// Repeats the write `TTAAAAAA VVVV` `XXXX` times, adding `YY` to the
+    /// address and `ZZZZ` to the value after each time. The value wraps around
+    /// at the size of the write.
+    Repeat {
+        /// Amount of writes `XXXX`
+        count: u16,
+        /// Address increment `YY`
+        addr_step: u8,
+        /// Value increment `ZZZZ`
+        value_step: u16,
+    },
+
+    /// Master code
+    ///
+    /// ```text
+    /// FFXXXXXX YYYY
+    /// EEXXXXXX YYYY
+    /// ```
+    ///
+    /// Enables the GameShark (`FF`), or disables the Expansion Pak (`EE`).
+    /// These configure the GameShark itself and don't access memory, so
+    /// they're ignored when converting.
+    Master {
+        /// Code type `FF` or `EE`
+        code_type: u8,
+        /// Address `XXXXXX`
+        addr: SizeInt,
+        /// Value `YYYY`
+        value: u16,
+    },
+
     /// 8-bit check equal
     ///
     /// ```text
@@ -157,16 +315,72 @@ pub enum CodeLine {
 
 impl CodeLine {
     /// Get the address that this code writes to or reads from
+    ///
+    /// Repeaters and master codes don't access memory themselves, so their
+    /// address is 0.
     pub fn addr(self) -> SizeInt {
         match self {
+            CodeLine::Repeat { .. } | CodeLine::Master { .. } => 0,
             CodeLine::Write8 { addr, .. } => addr,
             CodeLine::Write16 { addr, .. } => addr,
+            CodeLine::Write8OnBtn { addr, .. } => addr,
+            CodeLine::Write16OnBtn { addr, .. } => addr,
             CodeLine::IfEq8 { addr, .. } => addr,
             CodeLine::IfEq16 { addr, .. } => addr,
             CodeLine::IfNotEq8 { addr, .. } => addr,
             CodeLine::IfNotEq16 { addr, .. } => addr,
         }
     }
+
+    /// Check if this code line is a check, which guards the next line, rather
+    /// than a write
+    pub fn is_check(self) -> bool {
+        match self {
+            CodeLine::Write8 { .. }
+            | CodeLine::Write16 { .. }
+            | CodeLine::Write8OnBtn { .. }
+            | CodeLine::Write16OnBtn { .. }
+            | CodeLine::Repeat { .. }
+            | CodeLine::Master { .. } => false,
+            CodeLine::IfEq8 { .. }
+            | CodeLine::IfEq16 { .. }
+            | CodeLine::IfNotEq8 { .. }
+            | CodeLine::IfNotEq16 { .. } => true,
+        }
+    }
+
+    /// Check if this code line writes to memory, including button-activated
+    /// writes
+    pub fn is_write(self) -> bool {
+        matches!(
+            self,
+            CodeLine::Write8 { .. }
+                | CodeLine::Write16 { .. }
+                | CodeLine::Write8OnBtn { .. }
+                | CodeLine::Write16OnBtn { .. }
+        )
+    }
+}
+
+impl CodeLine {
+    /// Get the address and value of each byte that this code writes or reads
+    ///
+    /// The N64 is big-endian, so the high byte of a 16-bit value is first.
+    pub(crate) fn bytes(self) -> Vec<(SizeInt, u8)> {
+        match self {
+            CodeLine::Write8 { addr, value }
+            | CodeLine::Write8OnBtn { addr, value }
+            | CodeLine::IfEq8 { addr, value }
+            | CodeLine::IfNotEq8 { addr, value } => vec![(addr, value)],
+            CodeLine::Write16 { addr, value }
+            | CodeLine::Write16OnBtn { addr, value }
+            | CodeLine::IfEq16 { addr, value }
+            | CodeLine::IfNotEq16 { addr, value } => {
+                vec![(addr, (value >> 8) as u8), (addr + 1, value as u8)]
+            }
+            CodeLine::Repeat { .. } | CodeLine::Master { .. } => vec![],
+        }
+    }
 }
 
 impl FromStr for CodeLine {
@@ -175,12 +389,15 @@ impl FromStr for CodeLine {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // Split `TTXXXXXX YYYY` into `TTXXXXXX` and `YYYY`
         let tokens = s.split_whitespace().collect::<Vec<&str>>();
-        let (type_addr, value) = if let [type_addr, value] = *tokens.as_slice() {
-            (type_addr, value)
-        } else {
-            return Err(ParseError::FormatError {
-                code_line: s.to_owned(),
-            });
+        let (type_addr, value) = match *tokens.as_slice() {
+            [type_addr, value] => (type_addr, value),
+            // Some dumps join the parts, like `TTXXXXXXYYYY`
+            [joined] if joined.len() == 12 && joined.is_char_boundary(8) => joined.split_at(8),
+            _ => {
+                return Err(ParseError::FormatError {
+                    code_line: s.to_owned(),
+                })
+            }
         };
 
         ensure!(type_addr.len() == 8, FormatSnafu { code_line: s });
@@ -200,14 +417,28 @@ impl FromStr for CodeLine {
         let addr = type_addr & 0x00FFFFFF;
 
         match code_type {
-            0x80 => Ok(CodeLine::Write8 {
+            // `A0` and `A1` write to the uncached mirror of the same memory
+            0x80 | 0xA0 => Ok(CodeLine::Write8 {
+                addr,
+                value: value8,
+            }),
+            0x81 | 0xA1 => Ok(CodeLine::Write16 {
+                addr,
+                value: value16,
+            }),
+            0x88 => Ok(CodeLine::Write8OnBtn {
                 addr,
                 value: value8,
             }),
-            0x81 => Ok(CodeLine::Write16 {
+            0x89 => Ok(CodeLine::Write16OnBtn {
                 addr,
                 value: value16,
             }),
+            0x50 => Ok(CodeLine::Repeat {
+                count: (addr >> 8) as u16,
+                addr_step: addr as u8,
+                value_step: value16,
+            }),
             0xD0 => Ok(CodeLine::IfEq8 {
                 addr,
                 value: value8,
@@ -224,6 +455,11 @@ impl FromStr for CodeLine {
                 addr,
                 value: value16,
             }),
+            0xFF | 0xEE => Ok(CodeLine::Master {
+                code_type,
+                addr,
+                value: value16,
+            }),
             code_type => Err(ParseError::CodeTypeError {
                 code_line: s.to_owned(),
                 code_type,
@@ -237,6 +473,18 @@ impl fmt::Display for CodeLine {
         match self {
             CodeLine::Write8 { addr, value } => write!(f, "80{:06X} {:04X}", addr, value),
             CodeLine::Write16 { addr, value } => write!(f, "81{:06X} {:04X}", addr, value),
+            CodeLine::Write8OnBtn { addr, value } => write!(f, "88{:06X} {:04X}", addr, value),
+            CodeLine::Write16OnBtn { addr, value } => write!(f, "89{:06X} {:04X}", addr, value),
+            CodeLine::Repeat {
+                count,
+                addr_step,
+                value_step,
+            } => write!(f, "50{:04X}{:02X} {:04X}", count, addr_step, value_step),
+            CodeLine::Master {
+                code_type,
+                addr,
+                value,
+            } => write!(f, "{:02X}{:06X} {:04X}", code_type, addr, value),
             CodeLine::IfEq8 { addr, value } => write!(f, "D0{:06X} {:04X}", addr, value),
             CodeLine::IfEq16 { addr, value } => write!(f, "D1{:06X} {:04X}", addr, value),
             CodeLine::IfNotEq8 { addr, value } => write!(f, "D2{:06X} {:04X}", addr, value),
@@ -245,118 +493,1163 @@ impl fmt::Display for CodeLine {
     }
 }
 
-/// A parsed Nintendo 64 GameShark code
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Code(pub Vec<CodeLine>);
+/// A run of code lines, from `Code::guarded_runs`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuardedRun {
+    /// The check that guards each line of the run, if any
+    pub guard: Option<CodeLine>,
+    /// Lines of the run
+    ///
+    /// If `guard` is `None`, this is one line that isn't part of a guarded
+    /// run.
+    pub lines: Vec<CodeLine>,
+}
 
-impl FromStr for Code {
-    type Err = ParseError;
+/// A line of a code and the line that it applies to, from
+/// `Code::decision_tree`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    /// Code line of the node
+    pub line: CodeLine,
+    /// Node of the line that this line applies to
+    ///
+    /// Checks branch to the line that they guard, and repeaters to the write
+    /// that they repeat. Writes are leaves, and so is a check or repeater at
+    /// the end of the code.
+    pub child: Option<Box<TreeNode>>,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let code = s
-            .lines()
-            // Ignore leading and trailing whitespace
-            .map(|line| line.trim())
-            // Ignore empty lines
-            .filter(|line| !line.is_empty())
-            // Parse line
-            .map(|line| line.parse::<CodeLine>())
-            .collect::<Result<Vec<CodeLine>, Self::Err>>()?;
+/// Control flow of a code, from `Code::decision_tree`
+///
+/// This displays as an indented outline, with each line indented under the
+/// line that guards it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tree {
+    /// Nodes that run every frame, in order
+    pub roots: Vec<TreeNode>,
+}
 
-        Ok(Code(code))
+impl fmt::Display for Tree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for root in &self.roots {
+            let mut node = Some(root);
+            let mut depth = 0;
+            while let Some(current) = node {
+                writeln!(f, "{:indent$}{}", "", current.line, indent = depth * 2)?;
+                node = current.child.as_deref();
+                depth += 1;
+            }
+        }
+
+        Ok(())
     }
 }
 
-/// Size of a value written or read from a GameShark code
+/// How a code is activated, from `Code::activation`
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum ValueSize {
-    /// 8-Bit value
-    Bits8,
-    /// 16-Bit value
-    Bits16,
+pub enum Activation {
+    /// The code only writes, so it's always on
+    AlwaysOn,
+    /// The code writes while a button is held, with `88`/`89` writes or a
+    /// check of the controller's buttons
+    ButtonActivated,
+    /// The code has checks of other memory
+    Conditional,
 }
 
-impl ValueSize {
-    /// Amount of bytes of the value
+/// A memory write of a code line, from `Code::memory_writes`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct MemoryWrite {
+    /// Address of the write in the N64's RAM, like `0x8033B176`
+    pub addr: SizeInt,
+    /// Amount of bytes written
+    pub size: SizeInt,
+    /// Value written
+    pub value: u16,
+}
+
+/// A memory read of a check, from `Code::read_effects`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct MemoryRead {
+    /// Address of the read in the N64's RAM, like `0x8033AFA1`
+    pub addr: SizeInt,
+    /// Amount of bytes read
+    pub size: SizeInt,
+    /// Value that the read is compared with
+    pub expected: u16,
+    /// Whether the check passes if the value is not `expected`
+    pub negate: bool,
+}
+
+/// A parsed Nintendo 64 GameShark code
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Code(pub Vec<CodeLine>);
+
+impl Code {
+    /// Get the amount of lines in the code
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Check if the code has no lines
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterate over the lines of the code
     ///
     /// ```
-    /// use sm64gs2pc::gameshark::ValueSize;
+    /// use sm64gs2pc::gameshark::Code;
     ///
-    /// assert_eq!(ValueSize::Bits8.num_bytes(), 1);
-    /// assert_eq!(ValueSize::Bits16.num_bytes(), 2);
+    /// let code = "D033AFA1 0020\n8133B1BC 4220".parse::<Code>().unwrap();
+    /// let writes = code
+    ///     .iter()
+    ///     .filter(|line| line.is_write())
+    ///     .collect::<Code>();
+    ///
+    /// assert_eq!(writes, "8133B1BC 4220".parse::<Code>().unwrap());
     /// ```
-    pub fn num_bytes(self) -> SizeInt {
-        match self {
-            ValueSize::Bits8 => 1,
-            ValueSize::Bits16 => 2,
+    pub fn iter(&self) -> std::slice::Iter<'_, CodeLine> {
+        self.0.iter()
+    }
+
+    /// Estimate how many memory operations the code does every frame
+    ///
+    /// Cheats run every frame, so this can be used to warn about expensive
+    /// cheats. Each write and each check is one operation, counted as if
+    /// repeaters were expanded with `Code::expand_repeats`.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::Code;
+    ///
+    /// let code = "D033AFA1 0020\n8133B1BC 4220".parse::<Code>().unwrap();
+    /// assert_eq!(code.estimated_operations(), 2);
+    /// ```
+    pub fn estimated_operations(&self) -> usize {
+        let mut num_operations = 0;
+        // Amount of checks right before the current line
+        let mut num_guards = 0;
+        let mut lines = self.0.iter().peekable();
+
+        while let Some(line) = lines.next() {
+            match (line, lines.peek()) {
+                // Each repeated write is guarded by the repeater's checks,
+                // which were already counted once
+                (
+                    CodeLine::Repeat { count, .. },
+                    Some(CodeLine::Write8 { .. } | CodeLine::Write16 { .. }),
+                ) => {
+                    num_operations += *count as usize * (1 + num_guards);
+                    num_operations -= num_guards;
+                    lines.next();
+                }
+                // A repeater without a write repeats nothing, and master codes
+                // don't run every frame
+                (CodeLine::Repeat { .. }, _) | (CodeLine::Master { .. }, _) => {}
+                _ => num_operations += 1,
+            }
+
+            num_guards = if line.is_check() { num_guards + 1 } else { 0 };
         }
+
+        num_operations
     }
 
-    /// Get mask that can be bitwise AND'ed with an integer to isolate the value
-    /// size.
+    /// Replace each repeater and the write after it with the repeated writes
+    ///
+    /// Checks that guard the repeater guard each repeated write, so they're
+    /// repeated before each write. A repeater that isn't followed by an
+    /// `80`/`81` write repeats nothing, so it's removed.
     ///
     /// ```
-    /// use sm64gs2pc::gameshark::ValueSize;
+    /// use sm64gs2pc::gameshark::Code;
     ///
-    /// assert_eq!(ValueSize::Bits8.mask(), 0xff);
-    /// assert_eq!(ValueSize::Bits16.mask(), 0xffff);
+    /// let code = "50000302 0001\n8033B21E 0008".parse::<Code>().unwrap();
+    /// assert_eq!(
+    ///     code.expand_repeats(),
+    ///     "8033B21E 0008\n8033B220 0009\n8033B222 000A"
+    ///         .parse::<Code>()
+    ///         .unwrap(),
+    /// );
+    /// ```
+    pub fn expand_repeats(&self) -> Code {
+        let mut lines = Vec::<CodeLine>::new();
+        let mut i = 0;
+
+        while i < self.0.len() {
+            match (self.0[i], self.0.get(i + 1)) {
+                (
+                    CodeLine::Repeat {
+                        count,
+                        addr_step,
+                        value_step,
+                    },
+                    Some(&write @ (CodeLine::Write8 { .. } | CodeLine::Write16 { .. })),
+                ) => {
+                    let num_guards = lines
+                        .iter()
+                        .rev()
+                        .take_while(|line| line.is_check())
+                        .count();
+                    let guards = lines.split_off(lines.len() - num_guards);
+
+                    for n in 0..count {
+                        let addr = (write.addr() + n as SizeInt * addr_step as SizeInt) & 0xFFFFFF;
+                        let value_offset = n.wrapping_mul(value_step);
+                        lines.extend(&guards);
+                        lines.push(match write {
+                            CodeLine::Write8 { value, .. } => CodeLine::Write8 {
+                                addr,
+                                value: value.wrapping_add(value_offset as u8),
+                            },
+                            CodeLine::Write16 { value, .. } => CodeLine::Write16 {
+                                addr,
+                                value: value.wrapping_add(value_offset),
+                            },
+                            _ => unreachable!("repeated line isn't a write"),
+                        });
+                    }
+                    i += 2;
+                }
+                (CodeLine::Repeat { .. }, _) => i += 1,
+                (line, _) => {
+                    lines.push(line);
+                    i += 1;
+                }
+            }
+        }
+
+        Code(lines)
+    }
+
+    /// Get the memory writes of the code's write lines, without checks
+    ///
+    /// This is only the code's effect on memory, so it can be serialized for
+    /// tools like server-side anticheats that check which cheats were applied.
+    /// Guarded writes and button-activated writes are included, whether or not
+    /// the guard passes or the button is held.
     ///
-    /// assert_eq!(ValueSize::Bits8.mask() & 0xaabbccdd, 0xdd);
     /// ```
-    pub fn mask(self) -> u64 {
-        match self {
-            ValueSize::Bits8 => 0xff,
-            ValueSize::Bits16 => 0xffff,
+    /// use sm64gs2pc::gameshark::Code;
+    /// use sm64gs2pc::gameshark::MemoryWrite;
+    ///
+    /// let code = "D033AFA1 0020\n8133B1BC 4220".parse::<Code>().unwrap();
+    /// assert_eq!(
+    ///     code.memory_writes(),
+    ///     vec![MemoryWrite {
+    ///         addr: 0x8033B1BC,
+    ///         size: 2,
+    ///         value: 0x4220,
+    ///     }]
+    /// );
+    /// ```
+    pub fn memory_writes(&self) -> Vec<MemoryWrite> {
+        self.expand_repeats()
+            .0
+            .iter()
+            .filter_map(|code_line| match *code_line {
+                CodeLine::Write8 { addr, value } | CodeLine::Write8OnBtn { addr, value } => {
+                    Some((addr, 1, value as u16))
+                }
+                CodeLine::Write16 { addr, value } | CodeLine::Write16OnBtn { addr, value } => {
+                    Some((addr, 2, value))
+                }
+                _ => None,
+            })
+            .map(|(addr, size, value)| MemoryWrite {
+                addr: addr + 0x80000000,
+                size,
+                value,
+            })
+            .collect()
+    }
+
+    /// Get the memory reads of the code's checks, without writes
+    ///
+    /// This is like `Code::memory_writes`, for tools that reconstruct the
+    /// conditions of a code.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::Code;
+    /// use sm64gs2pc::gameshark::MemoryRead;
+    ///
+    /// let code = "D233AFA1 0020\n8133B1BC 4220".parse::<Code>().unwrap();
+    /// assert_eq!(
+    ///     code.read_effects(),
+    ///     vec![MemoryRead {
+    ///         addr: 0x8033AFA1,
+    ///         size: 1,
+    ///         expected: 0x20,
+    ///         negate: true,
+    ///     }]
+    /// );
+    /// ```
+    pub fn read_effects(&self) -> Vec<MemoryRead> {
+        self.0
+            .iter()
+            .filter_map(|code_line| match *code_line {
+                CodeLine::IfEq8 { addr, value } => Some((addr, 1, value as u16, false)),
+                CodeLine::IfEq16 { addr, value } => Some((addr, 2, value, false)),
+                CodeLine::IfNotEq8 { addr, value } => Some((addr, 1, value as u16, true)),
+                CodeLine::IfNotEq16 { addr, value } => Some((addr, 2, value, true)),
+                _ => None,
+            })
+            .map(|(addr, size, expected, negate)| MemoryRead {
+                addr: addr + 0x80000000,
+                size,
+                expected,
+                negate,
+            })
+            .collect()
+    }
+
+    /// Get a fingerprint that identifies the code
+    ///
+    /// This is a 64-bit FNV-1a hash of the code's lines in their canonical
+    /// `TTXXXXXX YYYY` form, so it's the same across runs, platforms, and
+    /// versions of this crate. Whitespace and letter case in the original text
+    /// don't affect it.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::Code;
+    ///
+    /// let code = "8133b176 0015".parse::<Code>().unwrap();
+    /// let same_code = "  8133B176 0015\n".parse::<Code>().unwrap();
+    /// assert_eq!(code.fingerprint(), same_code.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+
+        // Each line is followed by a newline
+        self.0
+            .iter()
+            .flat_map(|line| format!("{}\n", line).into_bytes())
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+            })
+    }
+
+    /// Group writes that are each guarded by the same check
+    ///
+    /// Codes often repeat a check before each write, like an activator that
+    /// means "while the button is held, do all of these". This groups a run of
+    /// check and write pairs with the same check, so it can be checked once.
+    ///
+    /// A run ends early if one of its writes changes memory that the check
+    /// reads, since the repeated check could then have a different result. A
+    /// check that is itself guarded isn't grouped, since the outer check only
+    /// guards the first pair.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::Code;
+    ///
+    /// let code = "D033AFA1 0020\n8133B1BC 4220\nD033AFA1 0020\n8133B17C 0300"
+    ///     .parse::<Code>()
+    ///     .unwrap();
+    /// let runs = code.guarded_runs();
+    ///
+    /// assert_eq!(runs.len(), 1);
+    /// assert_eq!(runs[0].guard, Some(code.0[0]));
+    /// assert_eq!(runs[0].lines, vec![code.0[1], code.0[3]]);
+    /// ```
+    pub fn guarded_runs(&self) -> Vec<GuardedRun> {
+        let lines = &self.0;
+        let mut runs = Vec::new();
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = lines[i];
+            let is_guarded = i > 0 && lines[i - 1].is_check();
+            let guards_write = matches!(lines.get(i + 1), Some(next) if !next.is_check());
+
+            if !line.is_check() || is_guarded || !guards_write {
+                runs.push(GuardedRun {
+                    guard: None,
+                    lines: vec![line],
+                });
+                i += 1;
+                continue;
+            }
+
+            // Addresses that the check reads
+            let read_addrs = line
+                .bytes()
+                .into_iter()
+                .map(|(addr, _)| addr)
+                .collect::<Vec<SizeInt>>();
+            let writes_read_addr = |write: CodeLine| {
+                write
+                    .bytes()
+                    .iter()
+                    .any(|(addr, _)| read_addrs.contains(addr))
+            };
+
+            let mut writes = vec![lines[i + 1]];
+            i += 2;
+            while lines.get(i) == Some(&line)
+                && matches!(lines.get(i + 1), Some(next) if !next.is_check())
+                && !writes.iter().any(|write| writes_read_addr(*write))
+            {
+                writes.push(lines[i + 1]);
+                i += 2;
+            }
+
+            runs.push(GuardedRun {
+                guard: Some(line),
+                lines: writes,
+            });
         }
+
+        runs
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Find pairs of writes that write to the same memory, where the later
+    /// write wins
+    ///
+    /// Each pair is the indices of the lines in the code, with the earlier
+    /// line first. Repeaters aren't expanded, so only the first write of a
+    /// repeat is compared.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::Code;
+    ///
+    /// let code = "8033B176 0015\n8133B176 0015".parse::<Code>().unwrap();
+    /// assert_eq!(code.internal_overlaps(), vec![(0, 1)]);
+    /// ```
+    pub fn internal_overlaps(&self) -> Vec<(usize, usize)> {
+        let written_addrs = self
+            .0
+            .iter()
+            .map(|line| {
+                if line.is_write() {
+                    line.bytes().into_iter().map(|(addr, _)| addr).collect()
+                } else {
+                    vec![]
+                }
+            })
+            .collect::<Vec<Vec<SizeInt>>>();
 
-    #[test]
-    fn test_parse_code() {
-        // Code from:
-        // https://sites.google.com/site/sm64gameshark/codes/level-reset-star-select
-        let code = "8129CE9C 2400\n\
-                    8129CEC0 2400\n\
-                    D033AFA1 0020\n\
-                    8033B21E 0008\n\
-                    \n\
-                    D033AFA1  0020  \n\
-                    8133B262 0000 \n \
-                    D033AFA1 0020\n \
-                    8133B218   0000\n\
-                    D033AFA1 0020 \n\
-                    8033B248  0002\n\
-                    D033AFA1 0020 \n\
-                    81361414 0005 ";
-        assert_eq!(
-            code.parse::<Code>().unwrap(),
-            Code(vec![
-                CodeLine::Write16 {
-                    addr: 0x0029CE9C,
-                    value: 0x2400,
-                },
-                CodeLine::Write16 {
-                    addr: 0x0029CEC0,
-                    value: 0x2400,
-                },
-                CodeLine::IfEq8 {
-                    addr: 0x0033AFA1,
-                    value: 0x20,
-                },
-                CodeLine::Write8 {
-                    addr: 0x0033B21E,
-                    value: 0x08,
-                },
-                CodeLine::IfEq8 {
-                    addr: 0x0033AFA1,
-                    value: 0x20,
-                },
-                CodeLine::Write16 {
-                    addr: 0x0033B262,
+        let mut overlaps = Vec::new();
+        for (i, earlier) in written_addrs.iter().enumerate() {
+            for (j, later) in written_addrs.iter().enumerate().skip(i + 1) {
+                if earlier.iter().any(|addr| later.contains(addr)) {
+                    overlaps.push((i, j));
+                }
+            }
+        }
+
+        overlaps
+    }
+
+    /// Get every distinct address that the code reads from or writes to
+    ///
+    /// Repeaters are expanded, so each repeated write's address is included.
+    /// Repeaters and master codes don't access memory themselves, so they add
+    /// no address.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::Code;
+    ///
+    /// let code = "D033AFA1 0020\n8133B1BC 4220\nD033AFA1 0020\n8133B17C 0300"
+    ///     .parse::<Code>()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     code.distinct_addresses().into_iter().collect::<Vec<_>>(),
+    ///     vec![0x33AFA1, 0x33B17C, 0x33B1BC],
+    /// );
+    /// ```
+    pub fn distinct_addresses(&self) -> BTreeSet<SizeInt> {
+        self.expand_repeats()
+            .0
+            .into_iter()
+            .filter(|line| !matches!(line, CodeLine::Repeat { .. } | CodeLine::Master { .. }))
+            .map(CodeLine::addr)
+            .collect()
+    }
+
+    /// Classify how the code is activated
+    ///
+    /// A code with both a button and other checks is button-activated. Only
+    /// the first controller's buttons, at `8033AFA0`, are recognized.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::Activation;
+    /// use sm64gs2pc::gameshark::Code;
+    ///
+    /// let code = "8133B176 0015".parse::<Code>().unwrap();
+    /// assert_eq!(code.activation(), Activation::AlwaysOn);
+    /// ```
+    pub fn activation(&self) -> Activation {
+        let is_button_line = |line: &CodeLine| match line {
+            CodeLine::Write8OnBtn { .. } | CodeLine::Write16OnBtn { .. } => true,
+            _ if line.is_check() => line
+                .bytes()
+                .iter()
+                .any(|(addr, _)| CONTROLLER_BUTTONS.contains(addr)),
+            _ => false,
+        };
+
+        if self.0.iter().any(is_button_line) {
+            Activation::ButtonActivated
+        } else if self.0.iter().any(|line| line.is_check()) {
+            Activation::Conditional
+        } else {
+            Activation::AlwaysOn
+        }
+    }
+
+    /// Build a tree of the code's control flow, where checks branch to the line
+    /// that they guard and writes are leaves
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::Code;
+    ///
+    /// let code = "D033AFA1 0020\n8133B1BC 4220\n8133B176 0015"
+    ///     .parse::<Code>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     code.decision_tree().to_string(),
+    ///     "D033AFA1 0020\n  8133B1BC 4220\n8133B176 0015\n",
+    /// );
+    /// ```
+    pub fn decision_tree(&self) -> Tree {
+        let mut roots = Vec::new();
+        let mut lines = self.0.iter().copied();
+
+        while let Some(node) = tree_node(&mut lines) {
+            roots.push(node);
+        }
+
+        Tree { roots }
+    }
+
+    /// Check that the code is structurally valid, without resolving any
+    /// addresses
+    ///
+    /// ## Errors
+    /// This function fails if
+    ///   * A repeater isn't followed by an `80`/`81` write to repeat
+    ///   * The code ends with a check, which has no line to guard
+    pub fn validate_structure(&self) -> Result<(), StructureError> {
+        for (i, code_line) in self.0.iter().enumerate() {
+            if let CodeLine::Repeat { .. } = code_line {
+                ensure!(
+                    matches!(
+                        self.0.get(i + 1),
+                        Some(CodeLine::Write8 { .. } | CodeLine::Write16 { .. })
+                    ),
+                    DanglingRepeatSnafu {
+                        code_line: code_line.to_string()
+                    }
+                );
+            }
+        }
+
+        match self.0.last() {
+            Some(
+                code_line @ CodeLine::IfEq8 { .. }
+                | code_line @ CodeLine::IfEq16 { .. }
+                | code_line @ CodeLine::IfNotEq8 { .. }
+                | code_line @ CodeLine::IfNotEq16 { .. },
+            ) => Err(StructureError::DanglingCheck {
+                code_line: code_line.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Find lines that trivially do nothing
+    ///
+    /// Cheats run from start to end without the game running in between, so
+    /// an unconditional write's value is known to later lines. This finds:
+    ///   * Checks that compare memory against a value that the code already
+    ///     wrote differently, so they are always false
+    ///   * Writes of the value that the code already wrote, so they have no
+    ///     effect
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::Code;
+    ///
+    /// let code = "8133B176 0000\nD133B176 0015\n8133B1BC 4220"
+    ///     .parse::<Code>()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     code.dead_lines(),
+    ///     vec!["D133B176 0015: check is always false, since the code already wrote 0x0000"],
+    /// );
+    /// ```
+    pub fn dead_lines(&self) -> Vec<String> {
+        self.dead_line_warnings()
+            .into_iter()
+            .map(|warning| warning.message)
+            .collect()
+    }
+
+    /// Find lines that trivially do nothing, like `dead_lines`, as warnings
+    pub(crate) fn dead_line_warnings(&self) -> Vec<Warning> {
+        let code = self.expand_repeats();
+        let mut warnings = Vec::new();
+
+        // Values of bytes written by unconditional writes
+        let mut known = BTreeMap::<SizeInt, u8>::new();
+        // Whether the current line is guarded by a check
+        let mut conditional = false;
+
+        for line in &code.0 {
+            let bytes = line.bytes();
+
+            // Value already in memory, if all of its bytes are known
+            let known_value = bytes
+                .iter()
+                .map(|(addr, _)| known.get(addr).map(|byte| *byte as u16))
+                .collect::<Option<Vec<u16>>>()
+                .map(|known_bytes| {
+                    known_bytes
+                        .into_iter()
+                        .fold(0, |value, byte| value << 8 | byte)
+                });
+            let width = bytes.len() * 2 + 2;
+
+            let is_check = match line {
+                CodeLine::Write8 { .. }
+                | CodeLine::Write16 { .. }
+                | CodeLine::Write8OnBtn { .. }
+                | CodeLine::Write16OnBtn { .. } => {
+                    let already_written = bytes
+                        .iter()
+                        .all(|(addr, value)| known.get(addr) == Some(value));
+                    // A write of the value already in memory does nothing, even
+                    // if it's guarded by a check
+                    if already_written {
+                        warnings.push(Warning {
+                            kind: WarningKind::DeadLine,
+                            message: format!(
+                                "{}: write has no effect, since the code already wrote this value",
+                                line
+                            ),
+                            line: *line,
+                        });
+                    }
+
+                    // Button-activated writes depend on the button, like a
+                    // check
+                    let conditional = conditional
+                        || matches!(
+                            line,
+                            CodeLine::Write8OnBtn { .. } | CodeLine::Write16OnBtn { .. }
+                        );
+                    for (addr, value) in bytes {
+                        if !conditional {
+                            known.insert(addr, value);
+                        } else if known.get(&addr) != Some(&value) {
+                            // Value depends on the check
+                            known.remove(&addr);
+                        }
+                    }
+
+                    false
+                }
+                CodeLine::Repeat { .. } => unreachable!("repeater wasn't expanded"),
+                CodeLine::Master { .. } => false,
+                CodeLine::IfEq8 { .. }
+                | CodeLine::IfEq16 { .. }
+                | CodeLine::IfNotEq8 { .. }
+                | CodeLine::IfNotEq16 { .. } => {
+                    let check_eq = matches!(line, CodeLine::IfEq8 { .. } | CodeLine::IfEq16 { .. });
+                    let value = bytes
+                        .iter()
+                        .fold(0, |value, (_, byte)| value << 8 | *byte as u16);
+
+                    if let Some(known_value) = known_value {
+                        if (known_value == value) != check_eq {
+                            warnings.push(Warning {
+                                kind: WarningKind::DeadLine,
+                                message: format!(
+                                    "{}: check is always false, since the code already wrote \
+                                     {:#0width$x}",
+                                    line,
+                                    known_value,
+                                    width = width
+                                ),
+                                line: *line,
+                            });
+                        }
+                    }
+
+                    true
+                }
+            };
+
+            conditional = is_check;
+        }
+
+        warnings
+    }
+}
+
+/// Build the tree node of the next line of `lines`, taking the lines that it
+/// applies to
+fn tree_node(lines: &mut impl Iterator<Item = CodeLine>) -> Option<TreeNode> {
+    let line = lines.next()?;
+    let child = match line {
+        CodeLine::Repeat { .. } => tree_node(lines),
+        _ if line.is_check() => tree_node(lines),
+        _ => None,
+    };
+
+    Some(TreeNode {
+        line,
+        child: child.map(Box::new),
+    })
+}
+
+impl Code {
+    /// Parse a code with at most `max_lines` code lines
+    ///
+    /// This is like parsing with `str::parse`, which allows any amount of
+    /// lines, but stops as soon as the limit is passed. Blank lines aren't
+    /// counted. This guards against huge pasted input.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::Code;
+    ///
+    /// assert!(Code::parse_with_max_lines("8133B176 0015", 1).is_ok());
+    /// assert!(Code::parse_with_max_lines("8133B176 0015\n8133B176 0015", 1).is_err());
+    /// ```
+    ///
+    /// ## Errors
+    /// This function fails if
+    ///   * The code has more than `max_lines` lines
+    ///   * A line can't be parsed
+    pub fn parse_with_max_lines(s: &str, max_lines: usize) -> Result<Self, ParseError> {
+        let lines = s
+            .lines()
+            // Ignore leading and trailing whitespace
+            .map(|line| line.trim())
+            // Ignore empty lines
+            .filter(|line| !line.is_empty());
+
+        let mut code = Vec::new();
+        for line in lines {
+            ensure!(code.len() < max_lines, TooManyLinesSnafu { max_lines });
+            code.push(line.parse::<CodeLine>()?);
+        }
+
+        Ok(Code(code))
+    }
+
+    /// Parse a code, stripping a leading header line and trailing control
+    /// lines that the PC port doesn't need
+    ///
+    /// Full dumps of codes sometimes end with lines that control the
+    /// GameShark itself, rather than reading or writing game memory, like an
+    /// entry point line `DE000400 0000` or a master code line
+    /// `FF000220 0000`. Elsewhere in the code, entry point lines are still
+    /// errors, and master code lines are parsed as `CodeLine::Master`.
+    ///
+    /// Archived cheat files sometimes start with a header line, like a
+    /// version `N64 GameShark v3.3` or a count of code lines `12`.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::Code;
+    ///
+    /// let (code, stripped) = Code::parse_lenient("8133B176 0015\nDE000400 0000").unwrap();
+    /// assert_eq!(code, "8133B176 0015".parse::<Code>().unwrap());
+    /// assert_eq!(stripped, vec!["DE000400 0000"]);
+    /// ```
+    ///
+    /// ## Return values
+    ///   * `Ok((code, stripped))` - The parsed code, and the stripped header
+    ///     and control lines in order
+    ///   * `Err(err)` - A line that isn't stripped can't be parsed
+    pub fn parse_lenient(s: &str) -> Result<(Self, Vec<String>), ParseError> {
+        let mut lines = s
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<&str>>();
+
+        let header = match lines.first() {
+            Some(line) if is_header_line(line) => Some(lines.remove(0)),
+            _ => None,
+        };
+
+        let num_control_lines = lines
+            .iter()
+            .rev()
+            .take_while(|line| is_control_line(line))
+            .count();
+        let stripped = header
+            .into_iter()
+            .chain(lines.split_off(lines.len() - num_control_lines))
+            .map(String::from)
+            .collect();
+
+        let code = lines
+            .into_iter()
+            .map(|line| line.parse::<CodeLine>())
+            .collect::<Result<Vec<CodeLine>, ParseError>>()?;
+
+        Ok((Code(code), stripped))
+    }
+}
+
+/// Check if a line is a header at the start of a cheat file, either a
+/// GameShark version, like `N64 GameShark v3.3`, or a count of code lines
+///
+/// A count is at most 4 digits and doesn't parse as a code line, so a code
+/// line without a space between the address and value, like `813614140005`,
+/// isn't mistaken for one.
+fn is_header_line(line: &str) -> bool {
+    let lowercase = line.to_ascii_lowercase();
+    let is_count = line.len() <= 4
+        && line.chars().all(|c| c.is_ascii_digit())
+        && line.parse::<CodeLine>().is_err();
+    is_count || lowercase.contains("gameshark") || lowercase.contains("game shark")
+}
+
+/// Code types of lines that control the GameShark, rather than reading or
+/// writing game memory
+///
+///   * `CC` - Disable the exception handler
+///   * `DE` - Entry point of the game
+///   * `EE` - Disable the Expansion Pak
+///   * `FF` - Master code, the address to hook the GameShark into
+const CONTROL_CODE_TYPES: [u8; 4] = [0xCC, 0xDE, 0xEE, 0xFF];
+
+/// GameShark addresses of the first controller's `buttonDown` and
+/// `buttonPressed`, in `gControllers[0]`
+const CONTROLLER_BUTTONS: std::ops::Range<SizeInt> = 0x33AFA0..0x33AFA4;
+
+/// Check if a line of a code is a control line, with the `TTXXXXXX YYYY`
+/// shape and a code type in `CONTROL_CODE_TYPES`
+fn is_control_line(line: &str) -> bool {
+    match *line.split_whitespace().collect::<Vec<&str>>().as_slice() {
+        [type_addr, value] => {
+            type_addr.len() == 8
+                && value.len() == 4
+                && value.chars().all(|c| c.is_ascii_hexdigit())
+                && type_addr.chars().all(|c| c.is_ascii_hexdigit())
+                && u8::from_str_radix(&type_addr[..2], 0x10)
+                    .is_ok_and(|code_type| CONTROL_CODE_TYPES.contains(&code_type))
+        }
+        _ => false,
+    }
+}
+
+impl FromStr for Code {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Code::parse_with_max_lines(s, usize::MAX)
+    }
+}
+
+impl IntoIterator for Code {
+    type Item = CodeLine;
+    type IntoIter = std::vec::IntoIter<CodeLine>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Code {
+    type Item = &'a CodeLine;
+    type IntoIter = std::slice::Iter<'a, CodeLine>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<CodeLine> for Code {
+    fn from_iter<I: IntoIterator<Item = CodeLine>>(iter: I) -> Self {
+        Code(iter.into_iter().collect())
+    }
+}
+
+impl<'a> FromIterator<&'a CodeLine> for Code {
+    fn from_iter<I: IntoIterator<Item = &'a CodeLine>>(iter: I) -> Self {
+        Code(iter.into_iter().copied().collect())
+    }
+}
+
+/// Each line of the code, separated by newlines, which parses back to the same
+/// code
+///
+/// ```
+/// use sm64gs2pc::gameshark::Code;
+///
+/// let code = "D033AFA1 0020\n8133B1BC 4220".parse::<Code>().unwrap();
+/// assert_eq!(code.to_string(), "D033AFA1 0020\n8133B1BC 4220");
+/// ```
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, line) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse code lines one at a time from a reader
+///
+/// Unlike parsing a `Code`, this doesn't read the whole input first, so it can
+/// parse very large or live input. Blank lines are skipped, and errors contain
+/// the number of the line.
+///
+/// ```
+/// use sm64gs2pc::gameshark;
+/// use sm64gs2pc::gameshark::CodeLine;
+///
+/// let input = std::io::Cursor::new("8133B176 0015\n\nD033AFA1 0020\n");
+/// let lines = gameshark::parse_lines(input)
+///     .collect::<Result<Vec<CodeLine>, gameshark::ParseError>>()
+///     .unwrap();
+///
+/// assert_eq!(lines.len(), 2);
+/// ```
+pub fn parse_lines<R: BufRead>(reader: R) -> impl Iterator<Item = Result<CodeLine, ParseError>> {
+    reader
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        // Ignore empty lines
+        .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|(line_number, line)| {
+            line.context(ReadSnafu { line_number })?
+                .trim()
+                .parse::<CodeLine>()
+                .context(LineSnafu { line_number })
+        })
+}
+
+/// Parse a set of named cheats
+///
+/// Each cheat is a line with its name followed by `:`, then its code lines,
+/// indented. The name is everything before the `:` that ends the line, so names
+/// can contain colons. Blank lines are skipped.
+///
+/// ```
+/// use sm64gs2pc::gameshark;
+///
+/// let cheats = gameshark::parse_cheat_set(
+///     "Always have Metal Cap:
+///         8133B176 0015
+///     ",
+/// )
+/// .unwrap();
+///
+/// assert_eq!(cheats[0].0, "Always have Metal Cap");
+/// assert_eq!(cheats[0].1.0.len(), 1);
+/// ```
+///
+/// ## Errors
+/// This function fails if
+///   * A line that isn't indented doesn't end with `:`
+///   * A code line comes before any cheat name
+///   * A code line can't be parsed
+pub fn parse_cheat_set(s: &str) -> Result<Vec<(String, Code)>, ParseError> {
+    let mut cheats = Vec::<(String, Code)>::new();
+
+    for (i, line) in s.lines().enumerate() {
+        let line_number = i + 1;
+
+        // Ignore empty lines
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // Code line of the current cheat
+        if line.starts_with(char::is_whitespace) {
+            let code_line = line
+                .trim()
+                .parse::<CodeLine>()
+                .context(LineSnafu { line_number })?;
+            let (_, code) = cheats
+                .last_mut()
+                .context(NoCheatNameSnafu { line_number })?;
+            code.0.push(code_line);
+            continue;
+        }
+
+        // Name of a new cheat
+        let name = line
+            .trim_end()
+            .strip_suffix(':')
+            .context(CheatNameSnafu { line_number })?;
+        cheats.push((name.trim_end().to_owned(), Code(Vec::new())));
+    }
+
+    Ok(cheats)
+}
+
+/// Parse several named cheats separated by blank lines or a separator line
+///
+/// Each block of lines is a cheat. Its first line that starts with `;` is its
+/// name, like `; Moon Jump`, and other lines that start with `;` are ignored.
+/// If `separator` is `None`, blocks are separated by blank lines. Otherwise,
+/// they're separated by lines that are `separator`, like `----`, and blank
+/// lines are skipped.
+///
+/// ```
+/// use sm64gs2pc::gameshark;
+///
+/// let cheats = gameshark::parse_multi(
+///     "; Always have Metal Cap\n8133B176 0015\n\n; Moon Jump\nD033AFA1 0020\n8133B1BC 4220\n",
+///     None,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(cheats.len(), 2);
+/// assert_eq!(cheats[1].0, "Moon Jump");
+/// ```
+///
+/// ## Errors
+/// This function fails if
+///   * A block has no name
+///   * A code line can't be parsed
+pub fn parse_multi(s: &str, separator: Option<&str>) -> Result<Vec<(String, Code)>, ParseError> {
+    // Blocks of numbered lines
+    let mut blocks = vec![Vec::<(usize, &str)>::new()];
+    for (i, line) in s.lines().enumerate() {
+        let line = line.trim();
+        let is_separator = match separator {
+            Some(separator) => line == separator.trim(),
+            None => line.is_empty(),
+        };
+
+        if is_separator {
+            blocks.push(Vec::new());
+        } else if !line.is_empty() {
+            blocks.last_mut().unwrap().push((i + 1, line));
+        }
+    }
+
+    blocks
+        .into_iter()
+        .filter(|block| !block.is_empty())
+        .map(|block| {
+            let name = block
+                .iter()
+                .find_map(|(_, line)| line.strip_prefix(';'))
+                .context(NoCheatNameSnafu {
+                    line_number: block[0].0,
+                })?;
+
+            let code = block
+                .iter()
+                .filter(|(_, line)| !line.starts_with(';'))
+                .map(|(line_number, line)| {
+                    line.parse::<CodeLine>().context(LineSnafu {
+                        line_number: *line_number,
+                    })
+                })
+                .collect::<Result<Vec<CodeLine>, ParseError>>()?;
+
+            Ok((name.trim().to_owned(), Code(code)))
+        })
+        .collect()
+}
+
+/// Size of a value written or read from a GameShark code
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ValueSize {
+    /// 8-Bit value
+    Bits8,
+    /// 16-Bit value
+    Bits16,
+}
+
+impl ValueSize {
+    /// Amount of bytes of the value
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::ValueSize;
+    ///
+    /// assert_eq!(ValueSize::Bits8.num_bytes(), 1);
+    /// assert_eq!(ValueSize::Bits16.num_bytes(), 2);
+    /// ```
+    pub fn num_bytes(self) -> SizeInt {
+        match self {
+            ValueSize::Bits8 => 1,
+            ValueSize::Bits16 => 2,
+        }
+    }
+
+    /// Get mask that can be bitwise AND'ed with an integer to isolate the value
+    /// size.
+    ///
+    /// ```
+    /// use sm64gs2pc::gameshark::ValueSize;
+    ///
+    /// assert_eq!(ValueSize::Bits8.mask(), 0xff);
+    /// assert_eq!(ValueSize::Bits16.mask(), 0xffff);
+    ///
+    /// assert_eq!(ValueSize::Bits8.mask() & 0xaabbccdd, 0xdd);
+    /// ```
+    pub fn mask(self) -> u64 {
+        match self {
+            ValueSize::Bits8 => 0xff,
+            ValueSize::Bits16 => 0xffff,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_code() {
+        // Code from:
+        // https://sites.google.com/site/sm64gameshark/codes/level-reset-star-select
+        let code = "8129CE9C 2400\n\
+                    8129CEC0 2400\n\
+                    D033AFA1 0020\n\
+                    8033B21E 0008\n\
+                    \n\
+                    D033AFA1  0020  \n\
+                    8133B262 0000 \n \
+                    D033AFA1 0020\n \
+                    8133B218   0000\n\
+                    D033AFA1 0020 \n\
+                    8033B248  0002\n\
+                    D033AFA1 0020 \n\
+                    81361414 0005 ";
+        assert_eq!(
+            code.parse::<Code>().unwrap(),
+            Code(vec![
+                CodeLine::Write16 {
+                    addr: 0x0029CE9C,
+                    value: 0x2400,
+                },
+                CodeLine::Write16 {
+                    addr: 0x0029CEC0,
+                    value: 0x2400,
+                },
+                CodeLine::IfEq8 {
+                    addr: 0x0033AFA1,
+                    value: 0x20,
+                },
+                CodeLine::Write8 {
+                    addr: 0x0033B21E,
+                    value: 0x08,
+                },
+                CodeLine::IfEq8 {
+                    addr: 0x0033AFA1,
+                    value: 0x20,
+                },
+                CodeLine::Write16 {
+                    addr: 0x0033B262,
                     value: 0x00,
                 },
                 CodeLine::IfEq8 {
@@ -386,4 +1679,676 @@ mod tests {
             ])
         );
     }
+    #[test]
+    fn test_parse_button_code() {
+        let code = "88341234 0001\n\
+                    8933B1BC 4220\n\
+                    D033AFA1 0020\n\
+                    8833B21E 0008";
+        assert_eq!(
+            code.parse::<Code>().unwrap(),
+            Code(vec![
+                CodeLine::Write8OnBtn {
+                    addr: 0x00341234,
+                    value: 0x01,
+                },
+                CodeLine::Write16OnBtn {
+                    addr: 0x0033B1BC,
+                    value: 0x4220,
+                },
+                CodeLine::IfEq8 {
+                    addr: 0x0033AFA1,
+                    value: 0x20,
+                },
+                CodeLine::Write8OnBtn {
+                    addr: 0x0033B21E,
+                    value: 0x08,
+                },
+            ])
+        );
+    }
+    #[test]
+    fn test_format_button_code() {
+        for line in &["88341234 0001", "8933B1BC 4220"] {
+            assert_eq!(line.parse::<CodeLine>().unwrap().to_string(), *line);
+        }
+    }
+    #[test]
+    fn test_parse_uncached_write() {
+        assert_eq!(
+            "A033B176 0015".parse::<CodeLine>().unwrap(),
+            "8033B176 0015".parse::<CodeLine>().unwrap()
+        );
+        assert_eq!(
+            "A133B1BC 4220".parse::<CodeLine>().unwrap(),
+            "8133B1BC 4220".parse::<CodeLine>().unwrap()
+        );
+    }
+    #[test]
+    fn test_parse_master_code() {
+        let line = "FF000220 0000".parse::<CodeLine>().unwrap();
+        assert_eq!(
+            line,
+            CodeLine::Master {
+                code_type: 0xFF,
+                addr: 0x000220,
+                value: 0x0000,
+            }
+        );
+        assert_eq!(line.to_string(), "FF000220 0000");
+        assert!(!line.is_check());
+        assert!(!line.is_write());
+
+        let code = "EE000000 0000\n8133B176 0015".parse::<Code>().unwrap();
+        assert_eq!(code.0[0].to_string(), "EE000000 0000");
+        assert_eq!(code.estimated_operations(), 1);
+        assert!(code.memory_writes().len() == 1);
+    }
+
+    #[test]
+    fn test_parse_repeat() {
+        let line = "50000A02 0001".parse::<CodeLine>().unwrap();
+        assert_eq!(
+            line,
+            CodeLine::Repeat {
+                count: 0x0A,
+                addr_step: 0x02,
+                value_step: 0x0001,
+            }
+        );
+        assert_eq!(line.to_string(), "50000A02 0001");
+        assert!(!line.is_check());
+    }
+    #[test]
+    fn test_expand_repeats() {
+        // Values wrap around at the size of the write, and checks guard each
+        // repeated write
+        let code = "D033AFA1 0020\n\
+                    50000302 8000\n\
+                    8133B21E FFFF\n\
+                    50000201 0080\n\
+                    8033B248 00C0"
+            .parse::<Code>()
+            .unwrap();
+        let expanded = "D033AFA1 0020\n\
+                        8133B21E FFFF\n\
+                        D033AFA1 0020\n\
+                        8133B220 7FFF\n\
+                        D033AFA1 0020\n\
+                        8133B222 FFFF\n\
+                        8033B248 00C0\n\
+                        8033B249 0040"
+            .parse::<Code>()
+            .unwrap();
+        assert_eq!(code.expand_repeats(), expanded);
+        assert_eq!(code.estimated_operations(), expanded.estimated_operations());
+
+        // A repeater without a write, or that repeats nothing, is removed
+        // with its checks
+        let code = "D033AFA1 0020\n50000002 0001\n8133B21E 0000\n50000102 0000"
+            .parse::<Code>()
+            .unwrap();
+        assert_eq!(code.expand_repeats(), Code(vec![]));
+        assert_eq!(code.estimated_operations(), 0);
+    }
+    #[test]
+    fn test_estimated_operations() {
+        assert_eq!(Code(vec![]).estimated_operations(), 0);
+
+        let code = "D033AFA1 0020\n\
+                    8133B1BC 4220\n\
+                    8133B1BE 0000"
+            .parse::<Code>()
+            .unwrap();
+        assert_eq!(code.estimated_operations(), 3);
+    }
+    #[test]
+    fn test_parse_joined_code_line() {
+        assert_eq!(
+            "8133B1760015".parse::<CodeLine>().unwrap(),
+            "8133B176 0015".parse::<CodeLine>().unwrap()
+        );
+        assert_eq!(
+            " D033AFA10020 ".parse::<CodeLine>().unwrap(),
+            CodeLine::IfEq8 {
+                addr: 0x0033AFA1,
+                value: 0x20,
+            }
+        );
+
+        // Joined tokens must be exactly 12 characters
+        assert!(matches!(
+            "8133B176001".parse::<CodeLine>(),
+            Err(ParseError::FormatError { .. })
+        ));
+        assert!(matches!(
+            "8133B17600155".parse::<CodeLine>(),
+            Err(ParseError::FormatError { .. })
+        ));
+    }
+    #[test]
+    fn test_dead_lines() {
+        // Check against a value the code just wrote differently
+        let code = "8133B176 0000\n\
+                    D133B176 0015\n\
+                    8133B1BC 4220"
+            .parse::<Code>()
+            .unwrap();
+        assert_eq!(
+            code.dead_lines(),
+            vec!["D133B176 0015: check is always false, since the code already wrote 0x0000"]
+        );
+
+        // Checks of bytes written by a 16-bit write, and repeated writes
+        let code = "8133B176 0015\n\
+                    D233B177 0015\n\
+                    D033B177 0015\n\
+                    8033B177 0015"
+            .parse::<Code>()
+            .unwrap();
+        assert_eq!(
+            code.dead_lines(),
+            vec![
+                "D233B177 0015: check is always false, since the code already wrote 0x15",
+                "8033B177 0015: write has no effect, since the code already wrote this value",
+            ]
+        );
+
+        // Conditional writes make values unknown
+        let code = "D033AFA1 0020\n\
+                    8133B176 0000\n\
+                    D133B176 0015"
+            .parse::<Code>()
+            .unwrap();
+        assert_eq!(code.dead_lines(), Vec::<String>::new());
+    }
+    #[test]
+    fn test_parse_lines() {
+        let input = std::io::Cursor::new(
+            "8133B176 0015\n\
+             \n\
+             \x20 D033AFA1 0020 \n\
+             8133B1BC",
+        );
+        let lines = parse_lines(input).collect::<Vec<Result<CodeLine, ParseError>>>();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0].as_ref().unwrap(),
+            &CodeLine::Write16 {
+                addr: 0x0033B176,
+                value: 0x15,
+            }
+        );
+        assert_eq!(
+            lines[1].as_ref().unwrap(),
+            &CodeLine::IfEq8 {
+                addr: 0x0033AFA1,
+                value: 0x20,
+            }
+        );
+        assert!(matches!(
+            lines[2],
+            Err(ParseError::LineError { line_number: 4, .. })
+        ));
+    }
+    #[test]
+    fn test_memory_writes_json() {
+        let code = "8133B176 0015".parse::<Code>().unwrap();
+        assert_eq!(
+            serde_json::to_string(&code.memory_writes()).unwrap(),
+            r#"[{"addr":2150871414,"size":2,"value":21}]"#
+        );
+    }
+
+    #[test]
+    fn test_read_effects() {
+        // Moon Jump, with a check that Mario isn't already moving up
+        let code = "D033AFA1 0020\n\
+                    D333B1BC 4220\n\
+                    8133B1BC 4220"
+            .parse::<Code>()
+            .unwrap();
+        assert_eq!(
+            code.read_effects(),
+            vec![
+                MemoryRead {
+                    addr: 0x8033AFA1,
+                    size: 1,
+                    expected: 0x20,
+                    negate: false,
+                },
+                MemoryRead {
+                    addr: 0x8033B1BC,
+                    size: 2,
+                    expected: 0x4220,
+                    negate: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        // FNV-1a of "8133B176 0015\n"
+        let code = "8133B176 0015".parse::<Code>().unwrap();
+        assert_eq!(code.fingerprint(), 0x9e6e_dac6_26c6_fb58);
+
+        assert_eq!(Code(Vec::new()).fingerprint(), 0xcbf29ce484222325);
+        assert_ne!(
+            code.fingerprint(),
+            "8133B176 0016".parse::<Code>().unwrap().fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let codes = [
+            "8133B176 00zz".parse::<CodeLine>().unwrap_err(),
+            "8133B176".parse::<CodeLine>().unwrap_err(),
+            "7733B176 0015".parse::<CodeLine>().unwrap_err(),
+            ParseError::ReadError {
+                line_number: 1,
+                source: std::io::Error::other("read"),
+            },
+            ParseError::TooManyLinesError { max_lines: 1 },
+            ParseError::CheatNameError { line_number: 1 },
+            ParseError::NoCheatNameError { line_number: 1 },
+        ]
+        .iter()
+        .map(ParseError::code)
+        .collect::<Vec<&str>>();
+
+        assert_eq!(
+            codes,
+            vec![
+                "parse-int",
+                "format",
+                "code-type",
+                "read",
+                "too-many-lines",
+                "cheat-name",
+                "no-cheat-name",
+            ]
+        );
+        assert_eq!(
+            codes
+                .iter()
+                .collect::<std::collections::BTreeSet<_>>()
+                .len(),
+            codes.len()
+        );
+
+        // Line errors have the code of the inner error
+        let mut lines = parse_lines(std::io::Cursor::new("7733B176 0015"));
+        assert_eq!(lines.next().unwrap().unwrap_err().code(), "code-type");
+    }
+
+    #[test]
+    fn test_guarded_runs() {
+        let code = "D033AFA1 0020
+                    8133B1BC 4220
+                    D033AFA1 0020
+                    8133B17C 0300
+                    D033AFA1 0020
+                    8133B17E 0880
+                    D033B1BD 0020
+                    8133B1BC 0000
+                    D033B1BD 0020
+                    8133B1BC 0000
+                    8133B176 0015
+                    D033AFA1 0020
+                    D033AFA1 0020
+                    8133B176 0015
+                    D033AFA1 0020
+                    8133B176 0015"
+            .parse::<Code>()
+            .unwrap();
+        let lines = &code.0;
+
+        assert_eq!(
+            code.guarded_runs(),
+            vec![
+                GuardedRun {
+                    guard: Some(lines[0]),
+                    lines: vec![lines[1], lines[3], lines[5]],
+                },
+                // The write changes the checked byte, so the check is repeated
+                GuardedRun {
+                    guard: Some(lines[6]),
+                    lines: vec![lines[7]],
+                },
+                GuardedRun {
+                    guard: Some(lines[8]),
+                    lines: vec![lines[9]],
+                },
+                GuardedRun {
+                    guard: None,
+                    lines: vec![lines[10]],
+                },
+                // A guarded check only guards one pair
+                GuardedRun {
+                    guard: None,
+                    lines: vec![lines[11]],
+                },
+                GuardedRun {
+                    guard: None,
+                    lines: vec![lines[12]],
+                },
+                GuardedRun {
+                    guard: None,
+                    lines: vec![lines[13]],
+                },
+                GuardedRun {
+                    guard: Some(lines[14]),
+                    lines: vec![lines[15]],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_internal_overlaps() {
+        // The 16-bit write covers the byte of the 8-bit write, and the next
+        let code = "8033B176 0015
+                    D033AFA1 0020
+                    8133B175 1234
+                    8133B178 0000
+                    8033B176 0001"
+            .parse::<Code>()
+            .unwrap();
+        assert_eq!(code.internal_overlaps(), vec![(0, 2), (0, 4), (2, 4)]);
+
+        // Checks read, but don't write
+        let code = "D033AFA1 0020\n8033AFA1 0000".parse::<Code>().unwrap();
+        assert_eq!(code.internal_overlaps(), vec![]);
+    }
+
+    #[test]
+    fn test_distinct_addresses() {
+        // Level Reset, whose button check is repeated before each write
+        let code = "8129CE9C 2400
+                    8129CEC0 2400
+                    D033AFA1 0020
+                    8033B21E 0008
+                    D033AFA1 0020
+                    8133B262 0000
+                    D033AFA1 0020
+                    8133B218 0000
+                    D033AFA1 0020
+                    8033B248 0002
+                    D033AFA1 0020
+                    81361414 0005"
+            .parse::<Code>()
+            .unwrap();
+        assert_eq!(code.distinct_addresses().len(), 8);
+
+        // Each repeated write has its own address
+        let code = "EE000000 0000\n50000302 0001\n8033B21E 0008"
+            .parse::<Code>()
+            .unwrap();
+        assert_eq!(
+            code.distinct_addresses(),
+            vec![0x33B21E, 0x33B220, 0x33B222].into_iter().collect(),
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        // Level Reset, from `test_parse_code`, with irregular whitespace that
+        // isn't kept
+        let code = "8129CE9C 2400\n\
+                    8129CEC0 2400\n\
+                    D033AFA1 0020\n\
+                    8033B21E 0008\n\
+                    \n\
+                    D033AFA1  0020  \n\
+                    8133B262 0000 \n \
+                    D033AFA1 0020\n \
+                    8133B218   0000\n\
+                    D033AFA1 0020 \n\
+                    8033B248  0002\n\
+                    D033AFA1 0020 \n\
+                    81361414 0005 "
+            .parse::<Code>()
+            .unwrap();
+        let formatted = code.to_string();
+
+        assert_eq!(formatted.lines().count(), 12);
+        assert!(formatted.starts_with("8129CE9C 2400\n8129CEC0 2400\nD033AFA1 0020\n"));
+        assert_eq!(formatted.parse::<Code>().unwrap(), code);
+    }
+
+    #[test]
+    fn test_iter() {
+        let code = "D033AFA1 0020\n8133B1BC 4220\n8133B176 0015"
+            .parse::<Code>()
+            .unwrap();
+        assert_eq!(code.len(), 3);
+        assert!(!code.is_empty());
+        assert!(Code(vec![]).is_empty());
+
+        let checks = (&code)
+            .into_iter()
+            .filter(|line| line.is_check())
+            .collect::<Code>();
+        assert_eq!(checks, "D033AFA1 0020".parse::<Code>().unwrap());
+
+        let addrs = code
+            .clone()
+            .into_iter()
+            .map(CodeLine::addr)
+            .collect::<Vec<_>>();
+        assert_eq!(addrs, vec![0x33AFA1, 0x33B1BC, 0x33B176]);
+
+        assert_eq!(code.iter().copied().collect::<Code>(), code);
+    }
+
+    #[test]
+    fn test_serde_json() {
+        let code = "D033AFA1 0020\n50000302 0001\n8133B1BC 4220"
+            .parse::<Code>()
+            .unwrap();
+        let json = serde_json::to_string(&code).unwrap();
+
+        // The format is stable, so cached codes keep loading
+        assert_eq!(
+            json,
+            "[\
+             {\"IfEq8\":{\"addr\":3387297,\"value\":32}},\
+             {\"Repeat\":{\"count\":3,\"addr_step\":2,\"value_step\":1}},\
+             {\"Write16\":{\"addr\":3387836,\"value\":16928}}\
+             ]"
+        );
+        assert_eq!(serde_json::from_str::<Code>(&json).unwrap(), code);
+
+        assert_eq!(
+            serde_json::to_string(&ValueSize::Bits8).unwrap(),
+            "\"Bits8\""
+        );
+    }
+
+    #[test]
+    fn test_activation() {
+        let metal_cap = "8133B176 0015".parse::<Code>().unwrap();
+        assert_eq!(metal_cap.activation(), Activation::AlwaysOn);
+
+        let moon_jump = "D033AFA1 0020\n8133B1BC 4220".parse::<Code>().unwrap();
+        assert_eq!(moon_jump.activation(), Activation::ButtonActivated);
+
+        let button_write = "8933B176 0015".parse::<Code>().unwrap();
+        assert_eq!(button_write.activation(), Activation::ButtonActivated);
+
+        // Checks Mario's action, not the buttons
+        let conditional = "D133B17C 0C40\n8133B176 0015".parse::<Code>().unwrap();
+        assert_eq!(conditional.activation(), Activation::Conditional);
+    }
+
+    #[test]
+    fn test_decision_tree() {
+        let moon_jump = "D033AFA1 0020\n8133B1BC 4220".parse::<Code>().unwrap();
+        assert_eq!(
+            moon_jump.decision_tree(),
+            Tree {
+                roots: vec![TreeNode {
+                    line: moon_jump.0[0],
+                    child: Some(Box::new(TreeNode {
+                        line: moon_jump.0[1],
+                        child: None,
+                    })),
+                }],
+            }
+        );
+
+        // Nested checks, a repeater, and a dangling check
+        let code = "D033AFA1 0020
+                    D133B176 0015
+                    8133B1BC 4220
+                    50000202 0001
+                    8033B400 0000
+                    D033AFA1 0020"
+            .parse::<Code>()
+            .unwrap();
+        assert_eq!(
+            code.decision_tree().to_string(),
+            "D033AFA1 0020
+  D133B176 0015
+    8133B1BC 4220
+50000202 0001
+  8033B400 0000
+D033AFA1 0020
+"
+        );
+    }
+
+    #[test]
+    fn test_parse_with_max_lines() {
+        let input = "8133B176 0015\n\nD033AFA1 0020\n8133B1BC 4220\n";
+
+        assert_eq!(
+            Code::parse_with_max_lines(input, 3).unwrap(),
+            input.parse::<Code>().unwrap()
+        );
+        assert!(matches!(
+            Code::parse_with_max_lines(input, 2),
+            Err(ParseError::TooManyLinesError { max_lines: 2 })
+        ));
+
+        // The limit is hit before invalid lines past it are parsed
+        assert!(matches!(
+            Code::parse_with_max_lines("8133B176 0015\ninvalid", 1),
+            Err(ParseError::TooManyLinesError { max_lines: 1 })
+        ));
+    }
+    #[test]
+    fn test_parse_lenient() {
+        let (code, stripped) =
+            Code::parse_lenient("D033AFA1 0020\n8133B1BC 4220\n\nEE000000 0000\nff000220 0000\n")
+                .unwrap();
+        assert_eq!(
+            code,
+            "D033AFA1 0020\n8133B1BC 4220".parse::<Code>().unwrap()
+        );
+        assert_eq!(stripped, vec!["EE000000 0000", "ff000220 0000"]);
+
+        // A version or count header is stripped
+        let (code, stripped) =
+            Code::parse_lenient("N64 GameShark v3.3\nD033AFA1 0020\n8133B1BC 4220").unwrap();
+        assert_eq!(
+            code,
+            "D033AFA1 0020\n8133B1BC 4220".parse::<Code>().unwrap()
+        );
+        assert_eq!(stripped, vec!["N64 GameShark v3.3"]);
+        let (code, stripped) = Code::parse_lenient("1\n8133B176 0015").unwrap();
+        assert_eq!(code, "8133B176 0015".parse::<Code>().unwrap());
+        assert_eq!(stripped, vec!["1"]);
+
+        // A code line without a space isn't a count, even if it's all digits
+        let (code, stripped) = Code::parse_lenient("813614140005\n8133B176 0015").unwrap();
+        assert_eq!(
+            code,
+            "81361414 0005\n8133B176 0015".parse::<Code>().unwrap()
+        );
+        assert!(stripped.is_empty());
+
+        // Only trailing control lines are stripped
+        assert!(matches!(
+            Code::parse_lenient("DE000400 0000\n8133B176 0015"),
+            Err(ParseError::CodeTypeError {
+                code_type: 0xDE,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_cheat_set() {
+        let cheats = parse_cheat_set(
+            "Always have Metal Cap:\n\
+             \x20   8133B176 0015\n\
+             \n\
+             Mario: Moon Jump:\n\
+             \x20   D033AFA1 0020\n\
+             \t8133B1BC 4220\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            cheats,
+            vec![
+                (
+                    String::from("Always have Metal Cap"),
+                    "8133B176 0015".parse::<Code>().unwrap()
+                ),
+                (
+                    String::from("Mario: Moon Jump"),
+                    "D033AFA1 0020\n8133B1BC 4220".parse::<Code>().unwrap()
+                ),
+            ]
+        );
+
+        assert!(matches!(
+            parse_cheat_set("  8133B176 0015"),
+            Err(ParseError::NoCheatNameError { line_number: 1 })
+        ));
+        assert!(matches!(
+            parse_cheat_set("Metal Cap:\nMoon Jump"),
+            Err(ParseError::CheatNameError { line_number: 2 })
+        ));
+        assert!(matches!(
+            parse_cheat_set("Metal Cap:\n  8133B176"),
+            Err(ParseError::LineError { line_number: 2, .. })
+        ));
+    }
+    #[test]
+    fn test_validate_structure() {
+        // Chained checks guarding a write
+        let code = "D033AFA1 0020\n\
+                    D033AFA0 0000\n\
+                    8133B1BC 4220"
+            .parse::<Code>()
+            .unwrap();
+        assert!(code.validate_structure().is_ok());
+
+        assert!(Code(vec![]).validate_structure().is_ok());
+
+        let code = "8133B1BC 4220\n\
+                    D033AFA1 0020"
+            .parse::<Code>()
+            .unwrap();
+        assert!(matches!(
+            code.validate_structure(),
+            Err(StructureError::DanglingCheck { code_line }) if code_line == "D033AFA1 0020"
+        ));
+
+        let code = "50000302 0001\n\
+                    D033AFA1 0020\n\
+                    8133B1BC 4220"
+            .parse::<Code>()
+            .unwrap();
+        assert!(matches!(
+            code.validate_structure(),
+            Err(StructureError::DanglingRepeat { code_line }) if code_line == "50000302 0001"
+        ));
+    }
 }
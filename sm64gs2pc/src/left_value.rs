@@ -7,6 +7,8 @@
 use crate::typ::SizeInt;
 use crate::typ::Type;
 
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::fmt;
 
 /// A C lvalue
@@ -48,12 +50,116 @@ pub enum LeftValueKind {
     },
 }
 
+impl LeftValue {
+    /// Add the names of the structs whose fields are accessed in this lvalue
+    /// to `names`
+    ///
+    /// Anonymous structs have no name, so they are skipped.
+    pub(crate) fn collect_struct_names(&self, names: &mut BTreeSet<String>) {
+        match &self.kind {
+            LeftValueKind::Ident { .. } => {}
+            LeftValueKind::ArrayIndex { array, .. } => array.collect_struct_names(names),
+            LeftValueKind::StructField { struct_, .. } => {
+                if let Type::Struct { name } = &struct_.typ {
+                    names.insert(name.clone());
+                }
+                struct_.collect_struct_names(names);
+            }
+        }
+    }
+
+    /// Copy this lvalue, with the identifier at its root renamed if it's in
+    /// `names`
+    pub(crate) fn renamed(&self, names: &BTreeMap<String, String>) -> LeftValue {
+        let kind = match &self.kind {
+            LeftValueKind::Ident { name } => LeftValueKind::Ident {
+                name: names.get(name).unwrap_or(name).clone(),
+            },
+            LeftValueKind::ArrayIndex { array, index } => LeftValueKind::ArrayIndex {
+                array: Box::new(array.renamed(names)),
+                index: *index,
+            },
+            LeftValueKind::StructField {
+                struct_,
+                field_name,
+            } => LeftValueKind::StructField {
+                struct_: Box::new(struct_.renamed(names)),
+                field_name: field_name.clone(),
+            },
+        };
+
+        LeftValue {
+            kind,
+            typ: self.typ.clone(),
+            addr: self.addr,
+        }
+    }
+
+    /// Copy this lvalue, with its address and the addresses of its parts moved
+    /// from `from` to `to`
+    ///
+    /// This keeps offsets the same, so an lvalue resolved at another address
+    /// can be used for the original address.
+    pub(crate) fn rebased(&self, from: SizeInt, to: SizeInt) -> LeftValue {
+        let kind = match &self.kind {
+            LeftValueKind::Ident { name } => LeftValueKind::Ident { name: name.clone() },
+            LeftValueKind::ArrayIndex { array, index } => LeftValueKind::ArrayIndex {
+                array: Box::new(array.rebased(from, to)),
+                index: *index,
+            },
+            LeftValueKind::StructField {
+                struct_,
+                field_name,
+            } => LeftValueKind::StructField {
+                struct_: Box::new(struct_.rebased(from, to)),
+                field_name: field_name.clone(),
+            },
+        };
+
+        LeftValue {
+            kind,
+            typ: self.typ.clone(),
+            addr: self.addr - from + to,
+        }
+    }
+
+    /// Format this lvalue as an access through a `volatile` pointer, like
+    /// `*(volatile uint16_t *) &gMarioStates[0].action`
+    ///
+    /// This stops the compiler from optimizing away repeated accesses.
+    pub(crate) fn volatile_access(&self) -> String {
+        match self.c_int_type() {
+            Some(c_type) => format!("*(volatile {} *) &{}", c_type, self.kind),
+            None => self.to_string(),
+        }
+    }
+
+    /// Get the fixed-width C integer type that this lvalue is accessed as,
+    /// like `uint16_t`
+    ///
+    /// Floats are accessed as their bits, so their type is `uint32_t`, or
+    /// `uint64_t` for doubles. Other types aren't accessed as integers, so
+    /// they have no type.
+    pub(crate) fn c_int_type(&self) -> Option<String> {
+        match self.typ {
+            Type::Int { signed, num_bytes } => Some(format!(
+                "{}int{}_t",
+                if signed { "" } else { "u" },
+                num_bytes * 8
+            )),
+            Type::Float => Some(String::from("uint32_t")),
+            Type::Double => Some(String::from("uint64_t")),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for LeftValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.typ == Type::Float {
-            write!(f, "*(uint32_t *) &{}", self.kind)
-        } else {
-            write!(f, "{}", self.kind)
+        match self.typ {
+            Type::Float => write!(f, "*(uint32_t *) &{}", self.kind),
+            Type::Double => write!(f, "*(uint64_t *) &{}", self.kind),
+            _ => write!(f, "{}", self.kind),
         }
     }
 }
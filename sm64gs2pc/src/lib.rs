@@ -14,13 +14,52 @@
 //! println!("{}", patch);
 //! ```
 
+mod action;
+mod cheat_toml;
+mod context_diff;
 mod decl;
 mod decomp_data;
+mod describe;
+mod explain;
+mod from_patch;
 pub mod gameshark;
 mod left_value;
+mod options;
+mod rom;
+mod save_layout;
+mod split;
+mod target;
 mod typ;
+mod undo;
+mod version;
+mod warning;
 
+pub use cheat_toml::CheatDescription;
+pub use cheat_toml::FieldWrite;
 pub use decomp_data::DecompData;
+pub use decomp_data::DecompStats;
+pub use explain::ComparedValue;
+pub use explain::ResolutionStep;
+pub use left_value::LeftValue;
+pub use left_value::LeftValueKind;
+pub use options::MaskFormat;
+pub use options::PatchFormat;
+pub use options::PatchOptions;
+pub use target::compatible_base_patches;
+pub use target::BasePatch;
+pub use target::CheatManager;
+pub use target::CheatTable;
+pub use target::PatchTarget;
+pub use typ::ScalarInfo;
+pub use typ::Struct;
+pub use typ::StructField;
+pub use typ::Type;
+pub use version::compatible_versions;
+pub use version::detect_version;
+pub use version::Version;
+pub use warning::ConversionResult;
+pub use warning::Warning;
+pub use warning::WarningKind;
 
 use lazy_static::lazy_static;
 
@@ -2,30 +2,214 @@ use sm64gs2pc::gameshark;
 
 use std::io::Write;
 use std::path::PathBuf;
+use std::str::FromStr;
 
+use structopt::clap;
 use structopt::StructOpt;
 
 /// Parsed command-line arguments
 #[derive(StructOpt)]
 #[structopt(about)]
 struct Opts {
-    /// Name of GameShark cheat
+    /// Name of GameShark cheat, required without a subcommand or `--set`
     #[structopt(long)]
-    name: String,
+    name: Option<String>,
 
-    /// Path to file with GameShark code to convert
+    /// Path to file with GameShark code to convert, required without a
+    /// subcommand or `--set`
     #[structopt(long)]
-    code: PathBuf,
+    code: Option<PathBuf>,
+
+    /// Convert several cheats in the `--code` file into one patch
+    ///
+    /// Cheats are separated by blank lines, or by `--separator` lines, and
+    /// each is named by its first line starting with `;`.
+    #[structopt(long, conflicts_with = "name")]
+    multi: bool,
+
+    /// Line that separates cheats for `--multi`, like `----`, instead of blank
+    /// lines
+    #[structopt(long, requires = "multi")]
+    separator: Option<String>,
+
+    /// Path to file with a set of cheats to convert into one patch, each a
+    /// line with its name followed by `:`, then its indented code lines
+    #[structopt(long, conflicts_with_all = &["name", "code"])]
+    set: Option<PathBuf>,
+
+    /// Omit the comment with the GameShark code line before each line of C
+    /// source code
+    #[structopt(long)]
+    no_comments: bool,
+
+    /// Print the memory writes of the `--code` file as JSON, with the address,
+    /// size, and value of each write, instead of converting it
+    #[structopt(long, requires = "code", conflicts_with_all = &["multi", "set"])]
+    memory_writes: bool,
+
+    /// Only check that the cheats convert, printing nothing if they do, or
+    /// else the errors, and exiting with a failure status
+    #[structopt(long)]
+    check_only: bool,
+
+    /// Print the version and counts of the bundled decompilation data
+    #[structopt(long)]
+    version_info: bool,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+/// Subcommands other than converting a code
+#[derive(StructOpt)]
+enum Command {
+    /// Write the bundled decompilation data for use by other tools
+    DumpData {
+        /// Format of the written data
+        #[structopt(long, default_value = "json", possible_values = &["json"])]
+        format: DataFormat,
+    },
+}
+
+/// Format of dumped decompilation data
+enum DataFormat {
+    /// JSON, with the same structure as the Rust `DecompData` type
+    Json,
+}
+
+impl FromStr for DataFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(DataFormat::Json),
+            _ => Err(format!("Unknown data format '{}'", s)),
+        }
+    }
 }
 
 fn try_main() -> Result<(), Box<dyn std::error::Error>> {
     let opts = Opts::from_args();
 
+    // Print version info
+    if opts.version_info {
+        let stats = sm64gs2pc::DECOMP_DATA_STATIC.stats();
+        println!("sm64gs2pc {}", env!("CARGO_PKG_VERSION"));
+        println!("Functions: {}", stats.num_fns);
+        println!("Variables: {}", stats.num_vars);
+        println!("Structs: {}", stats.num_structs);
+        if let Some((name, num_fields)) = sm64gs2pc::DECOMP_DATA_STATIC.largest_struct() {
+            println!("Largest struct: {} ({} fields)", name, num_fields);
+        }
+        return Ok(());
+    }
+
+    // Dump decomp data
+    if let Some(Command::DumpData { format }) = opts.command {
+        match format {
+            DataFormat::Json => {
+                serde_json::to_writer(std::io::stdout(), &*sm64gs2pc::DECOMP_DATA_STATIC)?
+            }
+        }
+        return Ok(());
+    }
+
+    // Print memory writes
+    if let (true, Some(code)) = (opts.memory_writes, &opts.code) {
+        let code = std::fs::read_to_string(code)?.parse::<gameshark::Code>()?;
+        serde_json::to_writer(std::io::stdout(), &code.memory_writes())?;
+        return Ok(());
+    }
+
+    // Check that cheats convert
+    if opts.check_only {
+        let cheats = match (&opts.set, opts.multi, &opts.code) {
+            (Some(set), _, _) => gameshark::parse_cheat_set(&std::fs::read_to_string(set)?)?,
+            (None, true, Some(code)) => {
+                gameshark::parse_multi(&std::fs::read_to_string(code)?, opts.separator.as_deref())?
+            }
+            (None, false, Some(code)) => vec![(
+                opts.name.clone().unwrap_or_default(),
+                std::fs::read_to_string(code)?.parse::<gameshark::Code>()?,
+            )],
+            _ => clap::Error::with_description(
+                "--code or --set is required to check cheats",
+                clap::ErrorKind::MissingRequiredArgument,
+            )
+            .exit(),
+        };
+
+        let mut is_valid = true;
+        for (name, code) in &cheats {
+            let structure_error = code.validate_structure().err().map(|err| err.to_string());
+            let errors = structure_error
+                .into_iter()
+                .chain(
+                    sm64gs2pc::DECOMP_DATA_STATIC
+                        .validate(code)
+                        .iter()
+                        .map(ToString::to_string),
+                )
+                .collect::<Vec<String>>();
+
+            for error in &errors {
+                match name.as_str() {
+                    "" => eprintln!("{}", error),
+                    name => eprintln!("{}: {}", name, error),
+                }
+            }
+            is_valid &= errors.is_empty();
+        }
+
+        if !is_valid {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let options = sm64gs2pc::PatchOptions {
+        line_comments: !opts.no_comments,
+        ..Default::default()
+    };
+
+    // Convert a set of cheats
+    if let Some(set) = opts.set {
+        let cheats = gameshark::parse_cheat_set(&std::fs::read_to_string(set)?)?
+            .into_iter()
+            .map(|(name, code)| (None, name, code))
+            .collect::<Vec<(Option<String>, String, gameshark::Code)>>();
+        let patch = sm64gs2pc::DECOMP_DATA_STATIC.gs_codes_to_patch_multi(&cheats, &options)?;
+        std::io::stdout().write_all(patch.as_bytes())?;
+        return Ok(());
+    }
+
+    // Convert several cheats from one file
+    if let (true, Some(code)) = (opts.multi, &opts.code) {
+        let cheats =
+            gameshark::parse_multi(&std::fs::read_to_string(code)?, opts.separator.as_deref())?
+                .into_iter()
+                .map(|(name, code)| (None, name, code))
+                .collect::<Vec<(Option<String>, String, gameshark::Code)>>();
+        let patch = sm64gs2pc::DECOMP_DATA_STATIC.gs_codes_to_patch_multi(&cheats, &options)?;
+        std::io::stdout().write_all(patch.as_bytes())?;
+        return Ok(());
+    }
+
+    let (name, code) = match (opts.name, opts.code) {
+        (Some(name), Some(code)) => (name, code),
+        _ => clap::Error::with_description(
+            "--name and --code, or --set, are required to convert a code",
+            clap::ErrorKind::MissingRequiredArgument,
+        )
+        .exit(),
+    };
+
     // Parse GameShark code
-    let code = std::fs::read_to_string(opts.code)?.parse::<gameshark::Code>()?;
+    let code = std::fs::read_to_string(code)?.parse::<gameshark::Code>()?;
 
     // Convert code to patch
-    let patch = sm64gs2pc::DECOMP_DATA_STATIC.gs_code_to_patch(&opts.name, code)?;
+    let patch =
+        sm64gs2pc::DECOMP_DATA_STATIC.gs_code_to_patch_with_options(&name, code, &options)?;
 
     // Print patch
     std::io::stdout().write_all(patch.as_bytes())?;
@@ -36,5 +220,6 @@ fn try_main() -> Result<(), Box<dyn std::error::Error>> {
 fn main() {
     if let Err(err) = try_main() {
         eprintln!("sm64gs2pc: error: {}", err);
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,359 @@
+//! Options for patch conversion
+
+use crate::context_diff::format_context_diff;
+use crate::target::CheatManager;
+use crate::target::CheatTable;
+use crate::target::PatchTarget;
+
+/// Options for converting GameShark codes to patches
+///
+/// The default options produce the same patches as
+/// `DecompData::gs_code_to_patch`.
+///
+/// Conversion is deterministic, so the same cheats and options always produce
+/// byte-identical patches. Options that merge or group lines, like
+/// `merge_guards`, `fold_checks`, and `array_fill_loops`, keep the lines in
+/// the order of the code. Cheats are grouped into sections and files in the
+/// order that the groups first appear, and keep their order within a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchOptions {
+    /// The PC port source that the patch is applied to
+    pub target: PatchTarget,
+
+    /// Use the hook's first statement as trailing context instead of a blank
+    /// line
+    ///
+    /// A blank line is ambiguous if the target file has many blank lines near
+    /// the hook. This has no effect if `target.first_statement` is `None`.
+    pub compact: bool,
+
+    /// Define each cheat in its own function instead of inserting it into the
+    /// hook
+    ///
+    /// The function is named `cheat_<name>`, like `cheat_moon_jump`, and is
+    /// meant to be registered with a port's cheat list.
+    pub wrap_in_function: bool,
+
+    /// Register each cheat with a runtime cheat manager, instead of inserting
+    /// it into the hook
+    ///
+    /// Each cheat is defined in its own function, as if `wrap_in_function`
+    /// was set. The patch also inserts a call that registers each function
+    /// into `CheatManager::registration_hook`. With `self_contained`, the
+    /// patch defines the registration function.
+    pub cheat_manager: Option<CheatManager>,
+
+    /// Add each cheat as a row of a data-driven cheat table, instead of
+    /// inserting it into the hook
+    ///
+    /// Each cheat is defined in its own function, as if `wrap_in_function`
+    /// was set. The patch also inserts a row for each function at the start
+    /// of the table. With `self_contained`, the patch defines the table. This
+    /// has no effect with `cheat_manager`.
+    pub cheat_table: Option<CheatTable>,
+
+    /// Only run the cheat during gameplay, by wrapping it in an `if` that
+    /// checks `target.in_game_check`
+    ///
+    /// Writes to `gMarioStates` during the title screen can crash the port.
+    pub in_game_guard: bool,
+
+    /// Define a variable for each cheat, like
+    /// `static bool cheat_moon_jump_enabled = true;`, and only run the cheat
+    /// if it's set
+    ///
+    /// This lets the port toggle cheats at runtime, like from a debug menu.
+    /// The variables are defined before the hook or the cheat's function.
+    pub toggle_variables: bool,
+
+    /// Log when each cheat first runs, with a call to this `printf`-style
+    /// function, like `"printf"` or `"osSyncPrintf"`
+    ///
+    /// The call is guarded by a flag, like
+    /// `static bool cheat_moon_jump_logged = false;`, so each cheat only logs
+    /// once. This helps check that a converted cheat runs at all.
+    pub activation_log: Option<String>,
+
+    /// Group cheats by category under `/* ===== Category ===== */` comments
+    ///
+    /// This only affects `DecompData::gs_codes_to_patch_multi`.
+    pub section_comments: bool,
+
+    /// Define each written value as a named constant, like
+    /// `#define MARIO_STATES_0_FLAGS_VALUE 0x15`, and write the constant
+    /// instead
+    ///
+    /// The names are derived from the written field.
+    pub named_constants: bool,
+
+    /// Include the resolved address and lvalue in the comment of each code
+    /// line, like `/* 8133B176 0015 -> 0x8033B176 gMarioStates[0].flags */`
+    ///
+    /// This helps debugging with a memory viewer.
+    pub verbose_comments: bool,
+
+    /// Start each line of C source code with a comment with its code line,
+    /// like `/* 8133B176 0015 */`
+    ///
+    /// If `false`, only the cheat name comment is kept, which makes the patch
+    /// smaller. Codes can't be recovered from such a patch with
+    /// `DecompData::patch_to_code`, and `verbose_comments` has no effect.
+    /// Defaults to `true`.
+    pub line_comments: bool,
+
+    /// Put a machine-readable comment before each cheat, like
+    /// `/* @sm64gs2pc name="Moon Jump" lines=2 fingerprint=9e6edac626c6fb58 */`
+    ///
+    /// `lines` is the amount of GameShark code lines and `fingerprint` is
+    /// `gameshark::Code::fingerprint` in hex, so tools can index the cheats in
+    /// a patch.
+    pub metadata_headers: bool,
+
+    /// Write the raw bytes of symbols whose types aren't supported, like
+    /// `double`s, instead of failing with `ToPatchError::IgnoredType`
+    ///
+    /// The write is relative to the symbol, like
+    /// `*((char *) &symbol + 0x1) = 0xab;`. The bytes are in the N64's
+    /// big-endian order, so this is only correct for data whose layout is the
+    /// same on the PC port. Checks still fail.
+    pub raw_ignored_writes: bool,
+
+    /// Create `gameshark.c` and `gameshark.h` with the cheats, instead of
+    /// patching the files added by a base patch
+    ///
+    /// This is for sources without a base patch applied. Like the base
+    /// patches, the patch also calls `run_gameshark_cheats()` once per frame
+    /// in `game_init.c`, after `target.call_site`. `compact` has no effect.
+    /// The created files don't define `target.in_game_check` or
+    /// `target.button_check`, so `in_game_guard` and button-activated writes
+    /// fail with `ToPatchError::SelfContainedUndefined`.
+    pub self_contained: bool,
+
+    /// Whether the patched file ends with a newline
+    ///
+    /// If `false`, the patch ends with `\ No newline at end of file`, for
+    /// target files without a trailing newline. Defaults to `true`.
+    pub end_newline: bool,
+
+    /// How bit masks are written in the generated C source
+    pub mask_format: MaskFormat,
+
+    /// Format of the patch
+    pub patch_format: PatchFormat,
+
+    /// Check a repeated guard once, for a run of writes that are each guarded
+    /// by the same check
+    ///
+    /// The writes are put in a block, like `if (...) { ... }`. See
+    /// `gameshark::Code::guarded_runs`. Since the repeated checks are left out,
+    /// `DecompData::patch_to_code` recovers each guard only once.
+    pub merge_guards: bool,
+
+    /// Combine a chain of checks, each guarding the next, into one `if` with
+    /// all of their conditions, like `if (... && ...)`
+    ///
+    /// The line starts with the comment of each check.
+    pub fold_checks: bool,
+
+    /// Write a loop that fills an array, for a run of writes that sets each
+    /// element of an integer array to the same value, like
+    /// `for (int i = 0; i < 25; i++) gSaveBuffer.files[0][0].courseStars[i] = 0xff;`
+    ///
+    /// The line starts with the comment of each code line of the run.
+    pub array_fill_loops: bool,
+
+    /// Copy a compound literal into a small integer array, for a run of writes
+    /// that sets each of its elements, like
+    /// `memcpy(&x, (uint8_t[]){0x1, 0x2, 0x3}, sizeof(x));`
+    ///
+    /// Only arrays of up to 16 elements are set this way. Runs that set each
+    /// element to the same value are loops instead, if `array_fill_loops` is
+    /// set. The target file has to include `<string.h>`, which
+    /// `self_contained` does.
+    pub array_initializers: bool,
+
+    /// Lay out the generated C source like `clang-format` does with the
+    /// decompilation's `.clang-format`, so formatting the patched file doesn't
+    /// change it
+    ///
+    /// Lines guarded by a check are indented under it, and each statement is
+    /// on its own line. Long lines aren't wrapped.
+    pub clang_format: bool,
+
+    /// Compare the bytes of a function in checks that read from it, like
+    /// `if (*(uint8_t *) ((char *) &func + 0x4) == 0x24)`
+    ///
+    /// This can check which code is loaded. Writes to functions still fail
+    /// with `ToPatchError::FnPatch`.
+    pub fn_checks: bool,
+
+    /// Shift the read value down in checks, and compare it with the code's
+    /// value, like `if (((gMarioStates[0].action >> 16) & 0xffff) == 0x1234)`
+    ///
+    /// By default, the read value is masked in place and compared with the
+    /// shifted value, like
+    /// `if ((gMarioStates[0].action & 0xffff0000) == 0x12340000)`.
+    pub shift_down_checks: bool,
+
+    /// Access memory through `volatile` pointers, like
+    /// `*(volatile uint16_t *) &gMarioStates[0].action`
+    ///
+    /// This stops the port's compiler from optimizing away repeated cheat
+    /// reads and writes, at the cost of changing the generated code's
+    /// semantics.
+    pub volatile_access: bool,
+
+    /// Give integer literals of values and masks an unsigned suffix, like
+    /// `0xaa000000u` or `0xffffffff00ffffffull`
+    ///
+    /// Literals that fit in 32 bits get `u`, and others get `ull`. This stops
+    /// compilers from warning about the signedness of bare literals.
+    pub unsigned_literals: bool,
+
+    /// Check that each write's lvalue has the offset and size that the code
+    /// expects, with an `assert` before the write's line
+    ///
+    /// For example,
+    /// `assert((char *) &gMarioStates[0].flags - (char *) &gMarioStates == 0x4 && sizeof(gMarioStates[0].flags) == 4);`.
+    /// This catches codes for another version at runtime, while debugging
+    /// the port. Release builds define `NDEBUG`, which drops the checks. The
+    /// target file has to include `<assert.h>`, which `self_contained` does.
+    pub bounds_asserts: bool,
+
+    /// Cast the masked value of each write to the exact width of its field,
+    /// like `x = (uint16_t) ((x & 0xffffffffffff00ff) | 0xaa00);`
+    ///
+    /// The mask is 64 bits wide, so assigning the masked value to a narrower
+    /// field makes strict builds warn with `-Wconversion`. This works with
+    /// any `mask_format`. Writes of a whole field are plain assignments, so
+    /// they aren't cast.
+    pub narrowing_casts: bool,
+
+    /// Resolve writes to a `const` pointer that points to a known symbol,
+    /// like `gMarioState`, as writes at the same offset in the symbol it
+    /// points to
+    ///
+    /// Such a code writes the pointer's bytes on the N64, but the pointer
+    /// never changes, so this guesses that the code meant the symbol.
+    /// Otherwise, these writes fail with `ToPatchError::PointerAssign`. Only
+    /// data created by the loader knows the pointer targets.
+    pub resolve_const_pointers: bool,
+
+    /// Maximum amount of memory operations a code can expand to, as counted
+    /// by `gameshark::Code::estimated_operations`
+    ///
+    /// This stops a huge code from creating a huge patch. Defaults to 10000.
+    pub max_expansion: usize,
+}
+
+impl Default for PatchOptions {
+    fn default() -> Self {
+        PatchOptions {
+            target: PatchTarget::default(),
+            compact: false,
+            wrap_in_function: false,
+            cheat_manager: None,
+            cheat_table: None,
+            in_game_guard: false,
+            toggle_variables: false,
+            activation_log: None,
+            section_comments: false,
+            named_constants: false,
+            verbose_comments: false,
+            line_comments: true,
+            metadata_headers: false,
+            raw_ignored_writes: false,
+            self_contained: false,
+            end_newline: true,
+            mask_format: MaskFormat::default(),
+            patch_format: PatchFormat::default(),
+            merge_guards: false,
+            fold_checks: false,
+            array_fill_loops: false,
+            array_initializers: false,
+            clang_format: false,
+            fn_checks: false,
+            shift_down_checks: false,
+            volatile_access: false,
+            unsigned_literals: false,
+            bounds_asserts: false,
+            narrowing_casts: false,
+            resolve_const_pointers: false,
+            max_expansion: 10000,
+        }
+    }
+}
+
+impl PatchOptions {
+    /// Get the suffix of the integer literal for `value`, which is empty
+    /// unless `unsigned_literals` is set
+    pub(crate) fn literal_suffix(&self, value: u64) -> &'static str {
+        if !self.unsigned_literals {
+            ""
+        } else if value <= u32::MAX as u64 {
+            "u"
+        } else {
+            "ull"
+        }
+    }
+
+    /// Format `value` as a hexadecimal C integer literal
+    pub(crate) fn format_literal(&self, value: u64) -> String {
+        format!("{:#x}{}", value, self.literal_suffix(value))
+    }
+
+    /// Format `mask` as a C integer literal in the `mask_format`
+    pub(crate) fn format_mask(&self, mask: u64) -> String {
+        format!(
+            "{}{}",
+            self.mask_format.format(mask),
+            self.literal_suffix(mask)
+        )
+    }
+}
+
+/// How bit masks are written in the generated C source
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum MaskFormat {
+    /// Hexadecimal, like `0xffffffffffff00ff`
+    #[default]
+    Hex,
+
+    /// Binary, like `0b1111111111111111111111111111111111111111111111110000000011111111`
+    ///
+    /// This makes it clearer which bits of a bitfield are touched. Binary
+    /// literals are a GCC extension, which the PC port's compilers support.
+    Binary,
+}
+
+impl MaskFormat {
+    /// Format `mask` as a C integer literal
+    pub(crate) fn format(self, mask: u64) -> String {
+        match self {
+            MaskFormat::Hex => format!("{:#x}", mask),
+            MaskFormat::Binary => format!("{:#b}", mask),
+        }
+    }
+}
+
+/// Format of a created patch
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum PatchFormat {
+    /// Unified diff, with `@@` hunk headers and `+`/`-` line markers
+    #[default]
+    Unified,
+
+    /// Context diff, with `***`/`---` hunk headers and `+`/`-`/`!` line
+    /// markers, for older tools
+    Context,
+}
+
+impl PatchFormat {
+    /// Format `patch` as text
+    pub(crate) fn format(self, patch: &patch::Patch) -> String {
+        match self {
+            PatchFormat::Unified => patch.to_string(),
+            PatchFormat::Context => format_context_diff(patch),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Mapping GameShark codes to ROM writes
+
+use crate::decomp_data::DecompData;
+use crate::decomp_data::ToPatchError;
+use crate::gameshark;
+use crate::gameshark::CodeLine;
+use crate::typ::SizeInt;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+/// A segment of the ROM that is loaded to a fixed RAM address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RomSegment {
+    /// Name of the segment's section in the linker map, like `.main`
+    pub name: String,
+    /// RAM address that the segment is loaded to
+    pub ram_start: SizeInt,
+    /// RAM address after the end of the segment's loaded data
+    pub ram_end: SizeInt,
+    /// ROM offset of the segment
+    pub rom_start: SizeInt,
+}
+
+impl DecompData {
+    /// Map the writes of a GameShark code to writes to ROM offsets
+    ///
+    /// This is experimental. It's only possible for addresses in segments that
+    /// are loaded from ROM to a fixed RAM address. Data that is zeroed at boot,
+    /// allocated at runtime, or loaded to a dynamic address has no ROM offset.
+    ///
+    /// ## Return values
+    ///   * `Ok(writes)` - The ROM offset and bytes of each write
+    ///   * `Err(err)` - A line can't be mapped to a ROM write
+    ///
+    /// ## Errors
+    /// This function fails if
+    ///   * A line is a check, which a ROM patch can't do
+    ///   * A write is outside of the statically-located segments
+    pub fn code_to_rom_writes(
+        &self,
+        code: &gameshark::Code,
+    ) -> Result<Vec<(SizeInt, Vec<u8>)>, ToPatchError> {
+        code.expand_repeats()
+            .0
+            .iter()
+            // Master codes configure the GameShark, not memory
+            .filter(|line| !matches!(line, CodeLine::Master { .. }))
+            .map(|line| {
+                let addr = line.addr() + 0x80000000;
+
+                let bytes = match *line {
+                    CodeLine::Write8 { value, .. } => vec![value],
+                    CodeLine::Write16 { value, .. } => value.to_be_bytes().to_vec(),
+                    _ => return Err(ToPatchError::RomCheck { addr }),
+                };
+
+                let segment = self
+                    .rom_segments
+                    .iter()
+                    .find(|segment| {
+                        segment.ram_start <= addr
+                            && addr + bytes.len() as SizeInt <= segment.ram_end
+                    })
+                    .ok_or(ToPatchError::NotStaticallyLocated { addr })?;
+
+                Ok((addr - segment.ram_start + segment.rom_start, bytes))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_to_rom_writes() {
+        let mut data = DecompData::default();
+        data.rom_segments.push(RomSegment {
+            name: ".main".to_owned(),
+            ram_start: 0x80246000,
+            ram_end: 0x80330000,
+            rom_start: 0x1000,
+        });
+
+        let code = "81246010 2400\n80246012 0001"
+            .parse::<gameshark::Code>()
+            .unwrap();
+        assert_eq!(
+            data.code_to_rom_writes(&code).unwrap(),
+            vec![(0x1010, vec![0x24, 0x00]), (0x1012, vec![0x01])]
+        );
+
+        // Zeroed or dynamically loaded memory has no ROM offset
+        let code = "8133B176 0015".parse::<gameshark::Code>().unwrap();
+        assert!(matches!(
+            data.code_to_rom_writes(&code),
+            Err(ToPatchError::NotStaticallyLocated { addr: 0x8033B176 })
+        ));
+
+        // Writes can't be partly outside a segment
+        let code = "8132FFFF 0015".parse::<gameshark::Code>().unwrap();
+        assert!(matches!(
+            data.code_to_rom_writes(&code),
+            Err(ToPatchError::NotStaticallyLocated { .. })
+        ));
+
+        let code = "D0246010 0024".parse::<gameshark::Code>().unwrap();
+        assert!(matches!(
+            data.code_to_rom_writes(&code),
+            Err(ToPatchError::RomCheck { addr: 0x80246010 })
+        ));
+    }
+}
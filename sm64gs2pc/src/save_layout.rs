@@ -0,0 +1,176 @@
+//! Checks for codes that assume a different save data layout
+//!
+//! The save buffer layout differs subtly between versions of the game, so a
+//! code written for another layout can resolve to a plausible but wrong field.
+
+use crate::decomp_data::DecompData;
+use crate::decomp_data::ToPatchError;
+use crate::gameshark;
+use crate::left_value::LeftValue;
+use crate::left_value::LeftValueKind;
+use crate::typ::SizeInt;
+use crate::typ::Type;
+use crate::warning::Warning;
+use crate::warning::WarningKind;
+
+/// Number of courses in the game, including castle secret stars and bonus
+/// stages
+const NUM_COURSES: SizeInt = 25;
+
+impl DecompData {
+    /// Warn about writes to save data that are past the last course
+    ///
+    /// A write is past the last course if it resolves to a
+    /// `courseStars`/`courseCoinScores` index of at least 25, or to the save
+    /// file's `signature`, which comes right after the per-course data. These
+    /// are usually codes that assume a different `SaveFile` layout.
+    ///
+    /// ## Return values
+    ///   * `Ok(warnings)` - One warning for each write past the last course
+    ///   * `Err(err)` - The address of a code line can't be resolved
+    pub fn check_save_layout(&self, code: &gameshark::Code) -> Result<Vec<String>, ToPatchError> {
+        let warnings = self.save_layout_warnings(code)?;
+        Ok(warnings
+            .into_iter()
+            .map(|warning| warning.message)
+            .collect())
+    }
+
+    /// Warn about writes to save data that are past the last course, like
+    /// `check_save_layout`, as warnings
+    pub(crate) fn save_layout_warnings(
+        &self,
+        code: &gameshark::Code,
+    ) -> Result<Vec<Warning>, ToPatchError> {
+        let mut warnings = Vec::new();
+
+        for line in &code.expand_repeats().0 {
+            if !line.is_write() {
+                continue;
+            }
+
+            let lvalue = match self.addr_to_lvalue(line.addr() + 0x80000000) {
+                Ok(lvalue) => lvalue,
+                // Memory without symbols isn't save data
+                Err(ToPatchError::UnmappedRegion { .. }) => continue,
+                Err(err) => return Err(err),
+            };
+
+            let past_last_course = match save_file_access(&lvalue) {
+                Some(("courseStars", Some(index))) | Some(("courseCoinScores", Some(index))) => {
+                    index >= NUM_COURSES
+                }
+                Some(("signature", _)) => true,
+                _ => false,
+            };
+
+            if past_last_course {
+                warnings.push(Warning {
+                    kind: WarningKind::SaveLayout,
+                    message: format!(
+                        "{}: {} is past the last course; the code may assume a different save \
+                         layout",
+                        line, lvalue.kind
+                    ),
+                    line: *line,
+                });
+            }
+        }
+
+        Ok(warnings)
+    }
+
+    /// Recommend applying writes to save data once, in a hook that runs after
+    /// a save file is loaded, instead of every frame
+    ///
+    /// Writes to `gSaveBuffer` before a save file is loaded are overwritten by
+    /// the load, and writes every frame fight with the game's own saving. So
+    /// cheats that set save data, like initial inventory, usually only make
+    /// sense once. This is a heuristic, since it doesn't know when the cheat
+    /// is meant to run.
+    ///
+    /// ## Return values
+    ///   * `Ok(warnings)` - One warning for each write to `gSaveBuffer`
+    ///   * `Err(err)` - The address of a code line can't be resolved
+    pub fn check_save_writes(&self, code: &gameshark::Code) -> Result<Vec<String>, ToPatchError> {
+        let warnings = self.save_write_warnings(code)?;
+        Ok(warnings
+            .into_iter()
+            .map(|warning| warning.message)
+            .collect())
+    }
+
+    /// Recommend applying writes to save data once, like `check_save_writes`,
+    /// as warnings
+    pub(crate) fn save_write_warnings(
+        &self,
+        code: &gameshark::Code,
+    ) -> Result<Vec<Warning>, ToPatchError> {
+        let mut warnings = Vec::new();
+
+        for line in &code.expand_repeats().0 {
+            if !line.is_write() {
+                continue;
+            }
+
+            let lvalue = match self.addr_to_lvalue(line.addr() + 0x80000000) {
+                Ok(lvalue) => lvalue,
+                // Memory without symbols isn't save data
+                Err(ToPatchError::UnmappedRegion { .. }) => continue,
+                Err(err) => return Err(err),
+            };
+
+            if root_name(&lvalue) == "gSaveBuffer" {
+                warnings.push(Warning {
+                    kind: WarningKind::SaveWrite,
+                    message: format!(
+                        "{}: {} is save data; consider applying the write once in a hook that \
+                         runs after a save file is loaded, instead of every frame in \
+                         run_gameshark_cheats()",
+                        line, lvalue.kind
+                    ),
+                    line: *line,
+                });
+            }
+        }
+
+        Ok(warnings)
+    }
+}
+
+/// Get the name of the declaration that an lvalue is part of
+fn root_name(lvalue: &LeftValue) -> &str {
+    match &lvalue.kind {
+        LeftValueKind::Ident { name } => name,
+        LeftValueKind::ArrayIndex { array, .. } => root_name(array),
+        LeftValueKind::StructField { struct_, .. } => root_name(struct_),
+    }
+}
+
+/// Get the `SaveFile` field that an lvalue accesses, and the index into the
+/// field if it's an array
+fn save_file_access(lvalue: &LeftValue) -> Option<(&str, Option<SizeInt>)> {
+    let is_save_file =
+        |lvalue: &LeftValue| matches!(&lvalue.typ, Type::Struct { name } if name == "SaveFile");
+
+    match &lvalue.kind {
+        LeftValueKind::Ident { .. } => None,
+        LeftValueKind::ArrayIndex { array, index } => match &array.kind {
+            LeftValueKind::StructField {
+                struct_,
+                field_name,
+            } if is_save_file(struct_) => Some((field_name, Some(*index))),
+            _ => save_file_access(array),
+        },
+        LeftValueKind::StructField {
+            struct_,
+            field_name,
+        } => {
+            if is_save_file(struct_) {
+                Some((field_name, None))
+            } else {
+                save_file_access(struct_)
+            }
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Splitting patches with several cheats into one patch for each cheat
+
+use crate::decomp_data::toggle_definition;
+use crate::decomp_data::DecompData;
+use crate::decomp_data::METADATA_TAG;
+use crate::gameshark::CodeLine;
+
+use snafu::ensure;
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+pub enum SplitPatchError {
+    #[snafu(display("Patch parse error: {}", message))]
+    PatchParse { message: String },
+
+    #[snafu(display("No cheats found in patch"))]
+    NoCheats,
+}
+
+/// Which cheat an added line of a patch belongs to
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Owner {
+    /// The line is kept in the patch of every cheat
+    All,
+
+    /// The line is only kept in the patch of the cheat with this index
+    Cheat(usize),
+
+    /// The line is left out of every patch, like a section comment
+    Dropped,
+}
+
+impl DecompData {
+    /// Split a patch with several cheats, created by this tool, into a patch
+    /// for each cheat
+    ///
+    /// Each cheat's added lines are found by the name comment that this tool
+    /// puts before each cheat, and the metadata comment before it, if any.
+    /// The patch of a cheat keeps the combined patch's context and removed
+    /// lines, and added lines that aren't part of any cheat, like includes.
+    /// Section comments are left out. This lets one cheat of a set be
+    /// redistributed on its own.
+    ///
+    /// ## Parameters
+    ///   * `combined` - Patch in the unified diff format
+    ///
+    /// ## Return values
+    ///   * `Ok(patches)` - Name and patch of each cheat, in the order of the
+    ///     combined patch
+    ///   * `Err(err)` - The patch can't be parsed, or has no cheats
+    pub fn split_patch(&self, combined: &str) -> Result<Vec<(String, String)>, SplitPatchError> {
+        // The parser expects the patch to end with a newline
+        let combined = if combined.ends_with('\n') {
+            combined.to_owned()
+        } else {
+            format!("{}\n", combined)
+        };
+
+        let patches =
+            patch::Patch::from_multiple(&combined).map_err(|err| SplitPatchError::PatchParse {
+                message: err.to_string(),
+            })?;
+
+        // Names of cheats, in order
+        let names = patches
+            .iter()
+            .flat_map(|patch| patch.hunks.iter())
+            .flat_map(|hunk| hunk.lines.iter())
+            .filter_map(|line| match line {
+                patch::Line::Add(line) => cheat_name(line),
+                _ => None,
+            })
+            .collect::<Vec<&str>>();
+
+        ensure!(!names.is_empty(), NoCheatsSnafu);
+
+        // Owner of each line of each hunk of each patch
+        let mut cheat_index = 0;
+        let owners = patches
+            .iter()
+            .map(|patch| {
+                patch
+                    .hunks
+                    .iter()
+                    .map(|hunk| line_owners(&hunk.lines, &names, &mut cheat_index))
+                    .collect::<Vec<Vec<Owner>>>()
+            })
+            .collect::<Vec<Vec<Vec<Owner>>>>();
+
+        let split_patches = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let is_kept = |owner: Owner| owner == Owner::All || owner == Owner::Cheat(i);
+
+                let cheat_patches = patches
+                    .iter()
+                    .zip(&owners)
+                    .map(|(patch, patch_owners)| {
+                        // Amount of added lines left out of earlier hunks,
+                        // which moves the start of later hunks
+                        let mut num_dropped = 0;
+
+                        let hunks = patch
+                            .hunks
+                            .iter()
+                            .zip(patch_owners)
+                            .map(|(hunk, hunk_owners)| {
+                                let lines = hunk
+                                    .lines
+                                    .iter()
+                                    .zip(hunk_owners)
+                                    .filter(|(_, owner)| is_kept(**owner))
+                                    .map(|(line, _)| line.clone())
+                                    .collect::<Vec<patch::Line>>();
+
+                                let num_new_lines = lines
+                                    .iter()
+                                    .filter(|line| !matches!(line, patch::Line::Remove(_)))
+                                    .count()
+                                    as u64;
+
+                                let split_hunk = patch::Hunk {
+                                    old_range: hunk.old_range.clone(),
+                                    new_range: patch::Range {
+                                        start: hunk.new_range.start - num_dropped,
+                                        count: num_new_lines,
+                                    },
+                                    lines,
+                                };
+
+                                num_dropped += hunk.new_range.count - num_new_lines;
+                                split_hunk
+                            })
+                            .collect::<Vec<patch::Hunk>>();
+
+                        patch::Patch {
+                            old: patch.old.clone(),
+                            new: patch.new.clone(),
+                            hunks,
+                            end_newline: patch.end_newline,
+                        }
+                        .to_string()
+                    })
+                    .collect::<Vec<String>>();
+
+                (name.to_string(), cheat_patches.join("\n"))
+            })
+            .collect::<Vec<(String, String)>>();
+
+        Ok(split_patches)
+    }
+}
+
+/// Find which cheat each line of a hunk belongs to
+///
+/// ## Parameters
+///   * `lines` - Lines of the hunk
+///   * `names` - Names of all cheats in the patch
+///   * `cheat_index` - Index of the next cheat in `names`, which is updated
+///     for each cheat in the hunk
+fn line_owners(lines: &[patch::Line], names: &[&str], cheat_index: &mut usize) -> Vec<Owner> {
+    let mut owners = Vec::with_capacity(lines.len());
+
+    // Owner of lines that aren't comments, and whether its cheat is wrapped
+    // in a function
+    let mut current = Owner::All;
+    let mut wrapped = false;
+    // Whether the function of the current cheat has been closed
+    let mut fn_closed = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        let line = match line {
+            patch::Line::Add(line) => *line,
+            // Context and removed lines are part of every patch
+            _ => {
+                owners.push(Owner::All);
+                continue;
+            }
+        };
+
+        // Next added line that isn't a metadata comment
+        let next_line = lines[i + 1..]
+            .iter()
+            .map(|line| match line {
+                patch::Line::Add(line) => Some(*line),
+                _ => None,
+            })
+            .find(|line| !matches!(line, Some(line) if is_metadata(line)))
+            .flatten();
+        let next_is_indented = next_line.is_some_and(|line| line.starts_with(' '));
+
+        // Cheats inserted into the hook end at the first unindented line
+        if !wrapped && !line.is_empty() && !line.starts_with(' ') {
+            current = Owner::All;
+        }
+
+        let owner = if line.trim().is_empty() {
+            match next_line {
+                // Cheats and sections inserted into the hook are each
+                // preceded by a blank line
+                Some(next_line) if next_is_indented && is_section(next_line) => Owner::Dropped,
+                Some(next_line) if next_is_indented && cheat_name(next_line).is_some() => {
+                    Owner::Cheat(*cheat_index)
+                }
+
+                // Functions of wrapped cheats are followed by a blank line
+                _ if wrapped && fn_closed => {
+                    let owner = current;
+                    current = Owner::All;
+                    wrapped = false;
+                    owner
+                }
+
+                _ if !wrapped && !next_is_indented => Owner::All,
+                _ => current,
+            }
+        } else if is_metadata(line) {
+            // Metadata comments describe the next cheat
+            Owner::Cheat(*cheat_index)
+        } else if is_section(line) {
+            Owner::Dropped
+        } else if cheat_name(line).is_some() {
+            current = Owner::Cheat(*cheat_index);
+            wrapped = !line.starts_with(' ');
+            fn_closed = false;
+            *cheat_index += 1;
+            current
+        } else if let Some(i) = names
+            .iter()
+            .position(|name| line.trim() == toggle_definition(name))
+        {
+            // Toggle variables of cheats in the hook are defined before it
+            Owner::Cheat(i)
+        } else {
+            if wrapped && line == "}" {
+                fn_closed = true;
+            }
+            current
+        };
+
+        owners.push(owner);
+    }
+
+    owners
+}
+
+/// Get the cheat name from a comment that starts a cheat, like
+/// `/* Moon Jump */` or `/* Moon Jump: register cheat_moon_jump */`
+fn cheat_name(line: &str) -> Option<&str> {
+    let comment = line.trim().strip_prefix("/* ")?.strip_suffix(" */")?;
+
+    let is_code_line = comment
+        .split(" -> ")
+        .next()
+        .is_some_and(|code_line| code_line.parse::<CodeLine>().is_ok());
+
+    if comment.contains("*/") || is_code_line || is_section(line) || is_metadata(line) {
+        return None;
+    }
+
+    match comment.split_once(": register cheat_") {
+        Some((name, _)) => Some(name),
+        None => Some(comment),
+    }
+}
+
+/// Check if a line is a section comment, like `/* ===== Movement ===== */`
+fn is_section(line: &str) -> bool {
+    let line = line.trim();
+    line.starts_with("/* ===== ") && line.ends_with(" ===== */")
+}
+
+/// Check if a line is a metadata comment
+fn is_metadata(line: &str) -> bool {
+    line.trim()
+        .strip_prefix("/* ")
+        .is_some_and(|comment| comment.starts_with(METADATA_TAG))
+}
@@ -0,0 +1,387 @@
+//! Description of the PC port source that patches are applied to
+
+use crate::gameshark;
+use crate::gameshark::CodeLine;
+use crate::options::PatchOptions;
+
+use snafu::Snafu;
+
+/// Line that starts the cheat hook function, by default
+pub(crate) const HOOK: &str = "void run_gameshark_cheats(void) {";
+
+#[derive(Debug, Snafu)]
+pub enum TargetError {
+    #[snafu(display("Source doesn't have the cheat hook `{}`", hook))]
+    MissingHook { hook: String },
+
+    #[snafu(display(
+        "Line after the cheat hook is {:?}, but the trailing context is {:?}",
+        found,
+        expected
+    ))]
+    TrailingContextMismatch { expected: String, found: String },
+}
+
+/// The cheat hook function in a PC port's source
+///
+/// Cheats are inserted at the start of the hook's body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchTarget {
+    /// Path of the file with the hook, relative to the port's root
+    ///
+    /// Defaults to `"src/game/gameshark.c"`, for forks that put the hook in
+    /// another file. `PatchOptions::self_contained` ignores this, since it
+    /// creates `src/game/gameshark.c` itself.
+    pub path: String,
+
+    /// Line that starts the hook function, which the patch uses as context
+    ///
+    /// Defaults to `"void run_gameshark_cheats(void) {"`, for ports that
+    /// rename the hook. `PatchOptions::self_contained` ignores this, since it
+    /// defines the default hook itself.
+    pub hook: String,
+
+    /// Line number of `hook` in the unpatched source, counting from 1, which
+    /// the patch's hunk starts at
+    ///
+    /// Defaults to `4`, which is only a hint. `patch` and `git apply` search
+    /// the file for the hunk's context and correct the line, so it only has
+    /// to be exact if the hook appears more than once. `BasePatch::target`
+    /// has the real line of each base patch, and `PatchTarget::locate_hook`
+    /// sets it from the source.
+    pub hook_line: u64,
+
+    /// The first statement in the hook's body, including indentation, if it
+    /// has one
+    ///
+    /// For example, `"    run_other_cheats();"`.
+    pub first_statement: Option<String>,
+
+    /// The line after the hook's opening line in the unpatched source, which
+    /// the patch uses as trailing context
+    ///
+    /// Defaults to a blank line, `""`. This is replaced by `first_statement`
+    /// with `PatchOptions::compact`.
+    pub trailing_context: String,
+
+    /// C expression that is true only during gameplay, used by
+    /// `PatchOptions::in_game_guard`
+    ///
+    /// Defaults to `"is_in_game()"`.
+    pub in_game_check: String,
+
+    /// C expression that is true while the GameShark button is held, which
+    /// guards button-activated writes, like `88XXXXXX 00YY`
+    ///
+    /// Defaults to `"GAMESHARK_BUTTON_HELD"`, a macro that the port defines.
+    pub button_check: String,
+
+    /// Line in `src/game/game_init.c`, including indentation, after which
+    /// `PatchOptions::self_contained` calls the hook, with the same
+    /// indentation
+    ///
+    /// Defaults to `"        display_and_vsync();"`, in
+    /// `game_loop_one_iteration` of sm64-port.
+    pub call_site: String,
+}
+
+impl PatchTarget {
+    /// Target a hook that's stubbed with an empty body, like
+    ///
+    /// ```c
+    /// void run_gameshark_cheats(void) {
+    /// }
+    /// ```
+    ///
+    /// The closing brace is the trailing context, so cheats are inserted
+    /// between the braces.
+    ///
+    /// ```
+    /// let target = sm64gs2pc::PatchTarget::empty_hook();
+    /// target
+    ///     .check_trailing_context("void run_gameshark_cheats(void) {\n}\n")
+    ///     .unwrap();
+    /// ```
+    pub fn empty_hook() -> Self {
+        PatchTarget {
+            trailing_context: String::from("}"),
+            ..Default::default()
+        }
+    }
+
+    /// Set `hook_line` to the line of the hook in the source that the patch is
+    /// applied to
+    ///
+    /// ## Parameters
+    ///   * `source` - Source of the unpatched `gameshark.c`
+    ///
+    /// ## Errors
+    /// This function fails if the source doesn't have the cheat hook.
+    pub fn locate_hook(&mut self, source: &str) -> Result<(), TargetError> {
+        let index = source
+            .lines()
+            .position(|line| line == self.hook)
+            .ok_or_else(|| TargetError::MissingHook {
+                hook: self.hook.clone(),
+            })?;
+        self.hook_line = index as u64 + 1;
+
+        Ok(())
+    }
+
+    /// Check that the trailing context matches the source that the patch is
+    /// applied to, so the patch doesn't reject
+    ///
+    /// ## Parameters
+    ///   * `source` - Source of the unpatched `gameshark.c`
+    ///
+    /// ## Errors
+    /// This function fails if
+    ///   * The source doesn't have the cheat hook
+    ///   * The line after the hook isn't `trailing_context`
+    pub fn check_trailing_context(&self, source: &str) -> Result<(), TargetError> {
+        let mut lines = source.lines().skip_while(|line| *line != self.hook);
+        if lines.next().is_none() {
+            return Err(TargetError::MissingHook {
+                hook: self.hook.clone(),
+            });
+        }
+
+        let found = lines.next().unwrap_or("");
+        if found != self.trailing_context {
+            return Err(TargetError::TrailingContextMismatch {
+                expected: self.trailing_context.clone(),
+                found: found.to_owned(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for PatchTarget {
+    fn default() -> Self {
+        PatchTarget {
+            path: String::from("src/game/gameshark.c"),
+            hook: String::from(HOOK),
+            hook_line: 4,
+            first_statement: None,
+            trailing_context: String::new(),
+            in_game_check: String::from("is_in_game()"),
+            button_check: String::from("GAMESHARK_BUTTON_HELD"),
+            call_site: String::from("        display_and_vsync();"),
+        }
+    }
+}
+
+/// A runtime cheat manager in a PC port's source, which cheats are registered
+/// with by string ID, like in modern ports
+///
+/// Each cheat is defined in its own function, like with
+/// `PatchOptions::wrap_in_function`, and a call registers the function, like
+/// `CheatManager_Register("Moon Jump", cheat_moon_jump);`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheatManager {
+    /// Function that registers a cheat, called with the cheat's name and
+    /// function
+    ///
+    /// Defaults to `"CheatManager_Register"`.
+    pub register_fn: String,
+
+    /// Line that starts the function where cheats are registered, which the
+    /// calls are inserted at the start of
+    ///
+    /// The port has to call this function once, before the cheat manager is
+    /// used. Defaults to `"void register_gameshark_cheats(void) {"`.
+    pub registration_hook: String,
+}
+
+impl Default for CheatManager {
+    fn default() -> Self {
+        CheatManager {
+            register_fn: String::from("CheatManager_Register"),
+            registration_hook: String::from("void register_gameshark_cheats(void) {"),
+        }
+    }
+}
+
+/// A data-driven cheat table in a PC port's source, which cheats are added to
+/// as rows, like `{ "Moon Jump", true, cheat_moon_jump },`
+///
+/// Each cheat is defined in its own function, like with
+/// `PatchOptions::wrap_in_function`, so adding a cheat is one row that points
+/// to its function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheatTable {
+    /// Line that starts the table's definition, which the rows are inserted
+    /// at the start of
+    ///
+    /// Defaults to `"struct GameSharkCheat gGameSharkCheats[] = {"`.
+    pub table_start: String,
+
+    /// Row of a cheat, where `{name}` is replaced with the cheat's name and
+    /// `{fn}` with its function
+    ///
+    /// The name is escaped for a C string literal. Defaults to
+    /// `"{ \"{name}\", true, {fn} },"`, for a table of
+    /// `{name, enabled, apply_fn}` structs.
+    pub row: String,
+}
+
+impl CheatTable {
+    /// Format the row of a cheat
+    ///
+    /// ## Parameters
+    ///   * `name` - Name of the cheat
+    ///   * `fn_name` - Name of the cheat's function
+    pub(crate) fn format_row(&self, name: &str, fn_name: &str) -> String {
+        self.row
+            .replace("{name}", &name.escape_default().to_string())
+            .replace("{fn}", fn_name)
+    }
+}
+
+impl Default for CheatTable {
+    fn default() -> Self {
+        CheatTable {
+            table_start: String::from("struct GameSharkCheat gGameSharkCheats[] = {"),
+            row: String::from("{ \"{name}\", true, {fn} },"),
+        }
+    }
+}
+
+/// A base patch, which adds the cheat hook to a PC port's source
+///
+/// Cheat patches are applied on top of a base patch, from the repository's
+/// `base-patches` directory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BasePatch {
+    /// Base patch for [sm64-port](https://github.com/sm64-port/sm64-port)
+    Sm64Port,
+    /// Base patch for the nightly branch of
+    /// [sm64ex](https://github.com/sm64pc/sm64ex)
+    Sm64exNightly,
+}
+
+impl BasePatch {
+    /// All base patches
+    pub const ALL: [BasePatch; 2] = [BasePatch::Sm64Port, BasePatch::Sm64exNightly];
+
+    /// File name of the base patch, like `gameshark-base-sm64-port.patch`
+    pub fn file_name(self) -> &'static str {
+        match self {
+            BasePatch::Sm64Port => "gameshark-base-sm64-port.patch",
+            BasePatch::Sm64exNightly => "gameshark-base-sm64ex-nightly.patch",
+        }
+    }
+
+    /// The cheat hook that the base patch adds
+    ///
+    /// Both base patches add an empty hook, with a blank line in its body, on
+    /// line 247 of sm64-port's `gameshark.c` and line 260 of sm64ex's. They
+    /// call the hook after `display_and_vsync();`, which sm64ex indents less.
+    /// They don't define `PatchTarget::in_game_check` or
+    /// `PatchTarget::button_check`.
+    pub fn target(self) -> PatchTarget {
+        match self {
+            BasePatch::Sm64Port => PatchTarget {
+                hook_line: 247,
+                ..Default::default()
+            },
+            BasePatch::Sm64exNightly => PatchTarget {
+                hook_line: 260,
+                call_site: String::from("    display_and_vsync();"),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Standard headers that the base patch's `gameshark.c` includes, which
+    /// some options need
+    ///
+    /// sm64-port's includes `<stdbool.h>`, and sm64ex's includes its own
+    /// `string.h`.
+    pub fn includes(self) -> &'static [&'static str] {
+        match self {
+            BasePatch::Sm64Port => &["stdbool.h"],
+            BasePatch::Sm64exNightly => &["string.h"],
+        }
+    }
+
+    /// Check if a patch of cheats applies on top of the base patch
+    fn is_compatible(self, cheats: &[gameshark::Code], options: &PatchOptions) -> bool {
+        let base_target = self.target();
+
+        // The patch's trailing context has to be the line after the hook
+        let trailing_context = match &options.target.first_statement {
+            Some(first_statement) if options.compact => first_statement,
+            _ => &options.target.trailing_context,
+        };
+        let base_trailing_context = base_target
+            .first_statement
+            .as_ref()
+            .unwrap_or(&base_target.trailing_context);
+
+        // `assert` is needed for bounds checks, and `memcpy` for array
+        // initializers
+        let has_includes = [
+            (options.bounds_asserts, "assert.h"),
+            (options.array_initializers, "string.h"),
+        ]
+        .iter()
+        .all(|(is_needed, header)| !is_needed || self.includes().contains(header));
+
+        // Button-activated writes use the port's button check
+        let has_button_writes = cheats.iter().flat_map(|code| &code.0).any(|line| {
+            matches!(
+                line,
+                CodeLine::Write8OnBtn { .. } | CodeLine::Write16OnBtn { .. }
+            )
+        });
+
+        // Self-contained patches create the file that a base patch adds, and
+        // the base patches have no cheat manager or cheat table
+        !options.self_contained
+            && options.target.path == base_target.path
+            && options.target.hook == base_target.hook
+            && options.cheat_manager.is_none()
+            && options.cheat_table.is_none()
+            && trailing_context == base_trailing_context
+            && !options.in_game_guard
+            && !has_button_writes
+            && has_includes
+    }
+}
+
+/// Find which base patches a patch of cheats applies on top of, so a UI can
+/// pick the base patch
+///
+/// A patch is compatible with a base patch if it inserts the cheats into the
+/// hook that the base patch adds, and only uses what the base patch defines
+/// and includes.
+///
+/// ```
+/// use sm64gs2pc::BasePatch;
+///
+/// let code = "8133B176 0015".parse().unwrap();
+/// let options = sm64gs2pc::PatchOptions::default();
+///
+/// assert_eq!(
+///     sm64gs2pc::compatible_base_patches(&[code], &options),
+///     vec![BasePatch::Sm64Port, BasePatch::Sm64exNightly],
+/// );
+/// ```
+///
+/// ## Parameters
+///   * `cheats` - GameShark codes of the cheats in the patch
+///   * `options` - Options that the patch is converted with
+pub fn compatible_base_patches(
+    cheats: &[gameshark::Code],
+    options: &PatchOptions,
+) -> Vec<BasePatch> {
+    BasePatch::ALL
+        .iter()
+        .copied()
+        .filter(|base_patch| base_patch.is_compatible(cheats, options))
+        .collect()
+}
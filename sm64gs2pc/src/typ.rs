@@ -42,6 +42,12 @@ pub enum Type {
 
     /// Type is ignored by this tool
     Ignored,
+
+    /// The primitive `double` type
+    ///
+    /// This is after `Ignored`, so data serialized before this was added still
+    /// deserializes.
+    Double,
 }
 
 impl Type {
@@ -51,13 +57,13 @@ impl Type {
     ///   * The `clang::Type` is unsupported
     ///   * Internal error converting type
     #[cfg(feature = "loader")]
-    pub fn from_clang(typ: clang::Type) -> Type {
+    pub(crate) fn from_clang(typ: clang::Type) -> Type {
         match typ.get_kind() {
             clang::TypeKind::Void
             | clang::TypeKind::FunctionPrototype
             | clang::TypeKind::Long
-            | clang::TypeKind::IncompleteArray
-            | clang::TypeKind::Double => Type::Ignored,
+            | clang::TypeKind::IncompleteArray => Type::Ignored,
+            clang::TypeKind::Double => Type::Double,
             clang::TypeKind::SChar | clang::TypeKind::CharS => Type::Int {
                 signed: true,
                 num_bytes: 1,
@@ -118,14 +124,25 @@ impl Type {
     }
 }
 
+/// Width and kind of a scalar C type
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScalarInfo {
+    /// Whether the scalar is a signed integer
+    pub signed: bool,
+    /// Size of the scalar in bytes
+    pub num_bytes: SizeInt,
+    /// Whether the scalar is a `float`
+    pub is_float: bool,
+}
+
 /// A C struct field
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StructField {
     /// Amount of bytes between start of struct and this field
     pub offset: SizeInt,
-    // Name of field
+    /// Name of field
     pub name: String,
-    // Type of field
+    /// Type of field
     pub typ: Type,
 }
 
@@ -143,7 +160,7 @@ impl Struct {
     ///   * The `clang::Type` is not a struct
     ///   * Internal error converting struct
     #[cfg(feature = "loader")]
-    pub fn from_clang(typ: clang::Type) -> Self {
+    pub(crate) fn from_clang(typ: clang::Type) -> Self {
         let fields = typ
             .get_fields()
             .unwrap()
@@ -0,0 +1,245 @@
+//! Removing cheats that were added by patches from this tool
+
+use crate::decomp_data::DecompData;
+use crate::decomp_data::METADATA_TAG;
+use crate::gameshark::CodeLine;
+use crate::options::PatchOptions;
+
+use std::borrow::Cow;
+
+use snafu::OptionExt;
+use snafu::Snafu;
+
+#[derive(Debug, Snafu)]
+pub enum UndoError {
+    #[snafu(display("No cheat '{}' found in source", cheat_id))]
+    NoCheat { cheat_id: String },
+
+    #[snafu(display("Cheat '{}' is not terminated in source", cheat_id))]
+    Unterminated { cheat_id: String },
+}
+
+impl DecompData {
+    /// Create a patch that removes a cheat from an already-patched source
+    ///
+    /// The cheat is found by the name comment that this tool puts before each
+    /// cheat, like `/* Moon Jump */`, so `cheat_id` is the name of the cheat.
+    /// The patch removes exactly the lines that were added for the cheat,
+    /// including its metadata comment.
+    ///
+    /// ## Parameters
+    ///   * `cheat_id` - Name of the cheat to remove
+    ///   * `current_source` - Source of the patched file
+    ///   * `options` - Options that the cheat was converted with, whose
+    ///     `target.path` is the path of the file in the patch
+    ///
+    /// ## Errors
+    /// This function fails if
+    ///   * No cheat named `cheat_id` is in `current_source`
+    ///   * The cheat's function or `if` block is never closed
+    pub fn undo_patch(
+        &self,
+        cheat_id: &str,
+        current_source: &str,
+        options: &PatchOptions,
+    ) -> Result<String, UndoError> {
+        let lines = current_source.lines().collect::<Vec<&str>>();
+
+        // Comments before the cheat, either inserted into the hook or wrapped
+        // in a function
+        let hook_comment = format!("/* {} */", cheat_id);
+        let fn_comment = format!("/* {}: register cheat_", cheat_id);
+        let metadata_comment = format!("/* {} ", METADATA_TAG);
+
+        let comment_index = lines
+            .iter()
+            .position(|line| line.trim() == hook_comment || line.starts_with(&fn_comment))
+            .context(NoCheatSnafu { cheat_id })?;
+
+        // Index of the cheat's metadata comment, if it has one, or else the
+        // name comment
+        let metadata_start = |comment_index: usize| match comment_index.checked_sub(1) {
+            Some(i) if lines[i].trim_start().starts_with(&metadata_comment) => i,
+            _ => comment_index,
+        };
+
+        // Index of the line after the first `closing` line from `start`
+        let block_end = |start: usize, closing: &str| {
+            lines[start..]
+                .iter()
+                .position(|line| line.trim_end() == closing)
+                .map(|i| start + i + 1)
+                .context(UnterminatedSnafu { cheat_id })
+        };
+
+        // Range of removed lines
+        let (start, end) = if lines[comment_index].starts_with(&fn_comment) {
+            // The function is followed by a blank line
+            let end = block_end(comment_index + 1, "}")?;
+            let end = match lines.get(end) {
+                Some(&"") => end + 1,
+                _ => end,
+            };
+            (metadata_start(comment_index), end)
+        } else {
+            // The cheat is either guarded by an `if` block, or is code lines,
+            // which may have blocks for merged guards
+            let end = match lines.get(comment_index + 1) {
+                Some(line) if line.trim_end().ends_with('{') && !is_code_line(line) => {
+                    block_end(comment_index + 2, "    }")?
+                }
+                _ => {
+                    let mut depth = 0;
+                    comment_index
+                        + 1
+                        + lines[comment_index + 1..]
+                            .iter()
+                            .take_while(|line| {
+                                let is_cheat_line = depth > 0 || is_code_line(line);
+                                if is_cheat_line {
+                                    depth += line.matches('{').count() as isize;
+                                    depth -= line.matches('}').count() as isize;
+                                }
+                                is_cheat_line
+                            })
+                            .count()
+                }
+            };
+
+            // The cheat is preceded by a blank line
+            let start = match metadata_start(comment_index).checked_sub(1) {
+                Some(i) if lines[i].is_empty() => i,
+                _ => metadata_start(comment_index),
+            };
+
+            (start, end)
+        };
+
+        // Keep a line of context on each side of the removed lines
+        let context_start = start.saturating_sub(1);
+        let context_end = (end + 1).min(lines.len());
+
+        let hunk_lines = (context_start..context_end)
+            .map(|i| {
+                if (start..end).contains(&i) {
+                    patch::Line::Remove(lines[i])
+                } else {
+                    patch::Line::Context(lines[i])
+                }
+            })
+            .collect::<Vec<patch::Line>>();
+
+        let num_context = (context_end - context_start) - (end - start);
+
+        let patch = patch::Patch {
+            old: patch::File {
+                path: Cow::from(format!("a/{}", options.target.path)),
+                meta: None,
+            },
+            new: patch::File {
+                path: Cow::from(format!("b/{}", options.target.path)),
+                meta: None,
+            },
+            hunks: vec![patch::Hunk {
+                old_range: patch::Range {
+                    start: context_start as u64 + 1,
+                    count: hunk_lines.len() as u64,
+                },
+                new_range: patch::Range {
+                    start: context_start as u64 + 1,
+                    count: num_context as u64,
+                },
+                lines: hunk_lines,
+            }],
+            end_newline: true,
+        }
+        .to_string();
+
+        Ok(patch)
+    }
+}
+
+/// Check if a line of source is part of a converted code, either a code line
+/// that starts with a `/* TTXXXXXX YYYY */` comment, a named constant, the
+/// flag of an activation log, or a line without a comment
+///
+/// A line without a comment is writes, like `x = (x & 0xff) | 0x100;`,
+/// `x = (uint16_t) ((x & 0xff) | 0x100);` or `x = 0x15;`, a check, like
+/// `if ((x & 0xff) == 0x20)`, which may guard the rest of the line or a
+/// block, or the comment of a line that isn't converted.
+fn is_code_line(line: &str) -> bool {
+    let line = line.trim();
+    let is_write = line
+        .strip_suffix(';')
+        .is_some_and(|line| line.split("; ").all(is_write_statement));
+    let is_check =
+        strip_check(line).is_some_and(|rest| rest.is_empty() || rest == "{" || is_code_line(rest));
+    let is_unconverted =
+        line.starts_with("/* warning: no symbols in ") || line == "/* ignored: master code */;";
+
+    // Flag of `PatchOptions::activation_log`, whose block follows
+    let is_activation_log = line
+        .strip_prefix("static bool cheat_")
+        .is_some_and(|line| line.ends_with("_logged = false;"))
+        || line
+            .strip_prefix("if (!cheat_")
+            .is_some_and(|line| line.ends_with("_logged) {"));
+
+    is_write
+        || is_check
+        || is_unconverted
+        || is_activation_log
+        || line.starts_with("#define ")
+        || line.starts_with("assert((char *) &")
+        || line
+            .strip_prefix("/* ")
+            .and_then(|line| line.split_once(" */"))
+            .and_then(|(comment, _)| comment.split(" -> ").next())
+            .is_some_and(|code_line| code_line.parse::<CodeLine>().is_ok())
+}
+
+/// Check if a statement, without its `;`, is a masked or plain write
+fn is_write_statement(statement: &str) -> bool {
+    statement.split_once(" = ").is_some_and(|(lhs, rhs)| {
+        let is_masked = |rhs: &str| rhs.starts_with(&format!("({} & ", lhs));
+        // The masked value may be cast to the width of the lvalue
+        is_masked(rhs)
+            || rhs
+                .split_once(") (")
+                .is_some_and(|(cast, rhs)| cast.starts_with('(') && is_masked(rhs))
+            || is_written_value(rhs)
+    })
+}
+
+/// Strip the `if (...)` of a check from the start of a line, and get the rest
+/// of the line
+fn strip_check(line: &str) -> Option<&str> {
+    let condition = line.strip_prefix("if (")?;
+    let mut depth = 1;
+    for (i, c) in condition.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        if depth == 0 {
+            return Some(condition[i + 1..].trim());
+        }
+    }
+    None
+}
+
+/// Check if the value of a plain write is a literal, like `0x15u`, or a named
+/// constant, like `MARIO_STATES_0_FLAGS_VALUE`
+fn is_written_value(value: &str) -> bool {
+    let is_literal = value
+        .strip_prefix("0x")
+        .map(|digits| digits.trim_end_matches(['u', 'l']))
+        .is_some_and(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit()));
+    let is_constant = value.contains("_VALUE")
+        && value
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+
+    is_literal || is_constant
+}
@@ -0,0 +1,186 @@
+//! Versions of Super Mario 64
+
+use crate::decomp_data::DecompData;
+use crate::gameshark;
+use crate::options::PatchOptions;
+
+use std::collections::BTreeSet;
+
+/// A regional version of Super Mario 64
+///
+/// GameShark codes are written for one version, since symbols are at different
+/// addresses in each version.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Version {
+    /// Japanese version
+    Jp,
+    /// North American version
+    Us,
+    /// European version
+    Eu,
+    /// Japanese Shindou (Rumble Pak) version
+    Sh,
+}
+
+impl Version {
+    /// Name of the version in the decompilation, like `us`
+    ///
+    /// ```
+    /// use sm64gs2pc::Version;
+    ///
+    /// assert_eq!(Version::Us.name(), "us");
+    /// assert_eq!(Version::Sh.name(), "sh");
+    /// ```
+    pub fn name(self) -> &'static str {
+        match self {
+            Version::Jp => "jp",
+            Version::Us => "us",
+            Version::Eu => "eu",
+            Version::Sh => "sh",
+        }
+    }
+}
+
+/// Guess which version a GameShark code was written for
+///
+/// This picks the version whose decompilation data can convert the most lines
+/// of the code. If versions tie, the first one in `versions` is picked.
+///
+/// ## Parameters
+///   * `code` - GameShark code to check
+///   * `versions` - Decompilation data of each version to consider
+///
+/// ## Return values
+///   * `Some(version)` - The version that converts the most lines
+///   * `None` - No version can convert any line of the code
+pub fn detect_version(
+    code: &gameshark::Code,
+    versions: &[(&Version, &DecompData)],
+) -> Option<Version> {
+    versions
+        .iter()
+        .map(|(version, decomp_data)| {
+            let num_converted = code
+                .0
+                .iter()
+                .filter(|code_line| decomp_data.validate_line(**code_line).is_ok())
+                .count();
+            (**version, num_converted)
+        })
+        .filter(|(_, num_converted)| *num_converted > 0)
+        // `max_by_key` picks the last maximum, so reverse to prefer the first
+        .rev()
+        .max_by_key(|(_, num_converted)| *num_converted)
+        .map(|(version, _)| version)
+}
+
+/// Find which versions a GameShark code is compatible with
+///
+/// A code is compatible with a version if the version's decompilation data
+/// converts every line of the code without error or dropped writes, so a UI
+/// can label a code like "US only".
+///
+/// ## Parameters
+///   * `code` - GameShark code to check
+///   * `versions` - Decompilation data of each version to consider
+pub fn compatible_versions(
+    code: &gameshark::Code,
+    versions: &[(&Version, &DecompData)],
+) -> BTreeSet<Version> {
+    versions
+        .iter()
+        .filter(|(_, decomp_data)| {
+            decomp_data
+                .gs_code_to_patch_with_options("", code.clone(), &PatchOptions::default())
+                .is_ok()
+                && decomp_data.validate(code).is_empty()
+        })
+        .map(|(version, _)| **version)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::decl::Decl;
+    use crate::decl::DeclKind;
+    use crate::typ::SizeInt;
+    use crate::typ::Type;
+
+    fn add_decl(decomp_data: &mut DecompData, addr: SizeInt, kind: DeclKind, name: &str) {
+        decomp_data.decls.insert(
+            addr,
+            Decl {
+                addr,
+                kind,
+                name: name.to_owned(),
+            },
+        );
+    }
+
+    fn int() -> DeclKind {
+        DeclKind::Var {
+            typ: Type::Int {
+                signed: false,
+                num_bytes: 2,
+            },
+        }
+    }
+
+    #[test]
+    fn test_detect_version() {
+        // `gCoins` is at a different address in each version
+        let mut us = DecompData::default();
+        add_decl(&mut us, 0x80300000, DeclKind::Fn, "func");
+        add_decl(&mut us, 0x80310000, int(), "gCoins");
+        add_decl(&mut us, 0x80320000, int(), "gLives");
+        let mut eu = DecompData::default();
+        add_decl(&mut eu, 0x80300000, int(), "gCoins");
+        add_decl(&mut eu, 0x80310000, DeclKind::Fn, "func");
+        add_decl(&mut eu, 0x80320000, int(), "gLives");
+
+        let versions = [(&Version::Us, &us), (&Version::Eu, &eu)];
+
+        let code = "81300000 0064".parse::<gameshark::Code>().unwrap();
+        assert_eq!(detect_version(&code, &versions), Some(Version::Eu));
+
+        let code = "81310000 0064".parse::<gameshark::Code>().unwrap();
+        assert_eq!(detect_version(&code, &versions), Some(Version::Us));
+
+        // Both versions convert the code, so the first wins
+        let code = "81320000 0004".parse::<gameshark::Code>().unwrap();
+        assert_eq!(detect_version(&code, &versions), Some(Version::Us));
+
+        // No version converts the code
+        let code = "81200000 0064".parse::<gameshark::Code>().unwrap();
+        assert_eq!(detect_version(&code, &versions), None);
+    }
+
+    #[test]
+    fn test_compatible_versions() {
+        let mut us = DecompData::default();
+        add_decl(&mut us, 0x80300000, int(), "gCoins");
+        add_decl(&mut us, 0x80310000, int(), "gLives");
+        let mut eu = DecompData::default();
+        add_decl(&mut eu, 0x80300000, DeclKind::Fn, "func");
+        add_decl(&mut eu, 0x80310000, int(), "gLives");
+
+        let versions = [(&Version::Us, &us), (&Version::Eu, &eu)];
+
+        // Only the US version converts both lines
+        let code = "81300000 0064\n81310000 0004"
+            .parse::<gameshark::Code>()
+            .unwrap();
+        assert_eq!(
+            compatible_versions(&code, &versions),
+            BTreeSet::from([Version::Us])
+        );
+
+        let code = "81310000 0004".parse::<gameshark::Code>().unwrap();
+        assert_eq!(
+            compatible_versions(&code, &versions),
+            BTreeSet::from([Version::Us, Version::Eu])
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! Advisory diagnostics collected while converting codes
+
+use crate::decomp_data::DecompData;
+use crate::decomp_data::ToPatchError;
+use crate::gameshark;
+use crate::gameshark::CodeLine;
+use crate::options::PatchOptions;
+
+use std::fmt;
+
+/// Kind of a `Warning`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WarningKind {
+    /// A write to memory without symbols, which is spilled into the patch as a
+    /// commented-out raw write
+    UnmappedWrite,
+    /// A line that trivially does nothing, from `Code::dead_lines`
+    DeadLine,
+    /// A write past the last course of save data, from
+    /// `DecompData::check_save_layout`
+    SaveLayout,
+    /// A write to save data, from `DecompData::check_save_writes`
+    SaveWrite,
+    /// A write of an unknown action, from `DecompData::check_actions`
+    UnknownAction,
+}
+
+impl WarningKind {
+    /// Get a stable, machine-readable code for the kind of warning, like
+    /// `"dead-line"`
+    pub fn code(&self) -> &'static str {
+        match self {
+            WarningKind::UnmappedWrite => "unmapped-write",
+            WarningKind::DeadLine => "dead-line",
+            WarningKind::SaveLayout => "save-layout",
+            WarningKind::SaveWrite => "save-write",
+            WarningKind::UnknownAction => "unknown-action",
+        }
+    }
+}
+
+/// An advisory diagnostic about a code that converts, but may not work as
+/// expected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// Kind of warning
+    pub kind: WarningKind,
+    /// Description of the problem
+    pub message: String,
+    /// Code line that the warning is about
+    pub line: CodeLine,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// A converted patch and the warnings about its code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionResult {
+    /// Patch in the format of `PatchOptions::patch_format`
+    pub patch: String,
+    /// Warnings about the code, in the order of the checks that found them
+    pub warnings: Vec<Warning>,
+}
+
+impl DecompData {
+    /// Convert GameShark code to a patch, with options, and collect warnings
+    /// about the code
+    ///
+    /// The warnings are those of `Code::dead_lines`, `check_save_layout`,
+    /// `check_save_writes`, and `check_actions`, and a warning for each write
+    /// to memory without symbols, which the patch only has as a comment.
+    /// Warnings are advisory, so lines that only convert with `options`, like
+    /// with `raw_ignored_writes`, are skipped by the checks instead of failing
+    /// the conversion.
+    ///
+    /// ## Parameters
+    ///   * `name` - Name of cheat to be included in comment in patch
+    ///   * `code` - GameShark code to convert
+    ///   * `options` - Options for the conversion
+    pub fn gs_code_to_patch_warn(
+        &self,
+        name: &str,
+        code: gameshark::Code,
+        options: &PatchOptions,
+    ) -> Result<ConversionResult, ToPatchError> {
+        let patch = self.gs_code_to_patch_with_options(name, code.clone(), options)?;
+
+        let mut warnings = self.unmapped_write_warnings(&code);
+        warnings.extend(code.dead_line_warnings());
+
+        // Only check lines that resolve without options
+        let resolved = gameshark::Code(
+            code.expand_repeats()
+                .0
+                .into_iter()
+                .filter(|line| self.line_resolves(*line))
+                .collect(),
+        );
+        warnings.extend(self.save_layout_warnings(&resolved)?);
+        warnings.extend(self.save_write_warnings(&resolved)?);
+        warnings.extend(self.action_warnings(&resolved)?);
+
+        Ok(ConversionResult { patch, warnings })
+    }
+
+    /// Check if each byte that a code line reads or writes resolves to an
+    /// lvalue, or is in memory without symbols
+    fn line_resolves(&self, line: CodeLine) -> bool {
+        line.bytes().into_iter().all(|(addr, _)| {
+            matches!(
+                self.addr_to_lvalue(addr + 0x80000000),
+                Ok(_) | Err(ToPatchError::UnmappedRegion { .. })
+            )
+        })
+    }
+
+    /// Warn about writes to memory without symbols
+    fn unmapped_write_warnings(&self, code: &gameshark::Code) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+
+        for line in &code.expand_repeats().0 {
+            if !line.is_write() {
+                continue;
+            }
+
+            if let Err(ToPatchError::UnmappedRegion { region, .. }) =
+                self.addr_to_lvalue(line.addr() + 0x80000000)
+            {
+                warnings.push(Warning {
+                    kind: WarningKind::UnmappedWrite,
+                    message: format!(
+                        "{}: no symbols in {}, so the write is only kept as a comment",
+                        line, region
+                    ),
+                    line: *line,
+                });
+            }
+        }
+
+        warnings
+    }
+}
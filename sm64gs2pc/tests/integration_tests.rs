@@ -8,6 +8,79 @@ fn gs_to_patch(decomp_data: &DecompData, name: &str, code: &str) -> String {
     patch
 }
 
+/// Helper for applying patches created by this tool to a source
+///
+/// Like `patch`, each hunk is applied where its old lines are found nearest to
+/// the hunk's line number.
+fn apply_patch(source: &str, patch: &str) -> String {
+    let patch = format!("{}\n", patch);
+    let patch = patch::Patch::from_single(&patch).unwrap();
+    let mut lines = source.lines().map(String::from).collect::<Vec<String>>();
+
+    // Apply hunks from the end, so earlier line numbers stay valid
+    for hunk in patch.hunks.iter().rev() {
+        let old_lines = hunk
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                patch::Line::Context(line) | patch::Line::Remove(line) => Some(*line),
+                patch::Line::Add(_) => None,
+            })
+            .collect::<Vec<&str>>();
+        let new_lines = hunk.lines.iter().filter_map(|line| match line {
+            patch::Line::Context(line) | patch::Line::Add(line) => Some(line.to_string()),
+            patch::Line::Remove(_) => None,
+        });
+
+        let expected_start = hunk.old_range.start as usize - 1;
+        let start = (0..=lines.len() - old_lines.len())
+            .filter(|&start| lines[start..start + old_lines.len()] == old_lines[..])
+            .min_by_key(|&start| (start as isize - expected_start as isize).abs())
+            .expect("hunk doesn't apply");
+        lines.splice(start..start + old_lines.len(), new_lines);
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Helper for checking if two patches add the same C statements
+///
+/// Comments and whitespace are ignored, so patches that only differ
+/// cosmetically are equivalent. Statements are compared in order.
+fn patches_equivalent(a: &str, b: &str) -> bool {
+    fn statements(patch: &str) -> Vec<String> {
+        let patch = format!("{}\n", patch);
+        let patches = patch::Patch::from_multiple(&patch).unwrap();
+
+        let source = patches
+            .iter()
+            .flat_map(|patch| patch.hunks.iter())
+            .flat_map(|hunk| hunk.lines.iter())
+            .filter_map(|line| match line {
+                patch::Line::Add(line) => Some(*line),
+                _ => None,
+            })
+            .collect::<Vec<&str>>()
+            .join("\n");
+
+        // Remove comments
+        let mut code = String::new();
+        let mut rest = source.as_str();
+        while let Some((before, after)) = rest.split_once("/*") {
+            code.push_str(before);
+            rest = after.split_once("*/").map_or("", |(_, after)| after);
+        }
+        code.push_str(rest);
+
+        code.split([';', '\n'])
+            .map(|statement| statement.split_whitespace().collect::<String>())
+            .filter(|statement| !statement.is_empty())
+            .collect()
+    }
+
+    statements(a) == statements(b)
+}
+
 /// Helper to run test cases with a decomp data
 fn patch_convert_test_cases(decomp_data: &DecompData) {
     // Sources for tests:
@@ -38,19 +111,19 @@ fn patch_convert_test_cases(decomp_data: &DecompData) {
  void run_gameshark_cheats(void) {
 +
 +    /* Have 180 Stars */
-+    /* 8120770C FFFF */ gSaveBuffer.files[0][0].courseStars[0] = (gSaveBuffer.files[0][0].courseStars[0] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseStars[1] = (gSaveBuffer.files[0][0].courseStars[1] & 0xffffffffffffff00) | 0xff;
-+    /* 8120770E FFFF */ gSaveBuffer.files[0][0].courseStars[2] = (gSaveBuffer.files[0][0].courseStars[2] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseStars[3] = (gSaveBuffer.files[0][0].courseStars[3] & 0xffffffffffffff00) | 0xff;
-+    /* 81207710 FFFF */ gSaveBuffer.files[0][0].courseStars[4] = (gSaveBuffer.files[0][0].courseStars[4] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseStars[5] = (gSaveBuffer.files[0][0].courseStars[5] & 0xffffffffffffff00) | 0xff;
-+    /* 81207712 FFFF */ gSaveBuffer.files[0][0].courseStars[6] = (gSaveBuffer.files[0][0].courseStars[6] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseStars[7] = (gSaveBuffer.files[0][0].courseStars[7] & 0xffffffffffffff00) | 0xff;
-+    /* 81207714 FFFF */ gSaveBuffer.files[0][0].courseStars[8] = (gSaveBuffer.files[0][0].courseStars[8] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseStars[9] = (gSaveBuffer.files[0][0].courseStars[9] & 0xffffffffffffff00) | 0xff;
-+    /* 81207716 FFFF */ gSaveBuffer.files[0][0].courseStars[10] = (gSaveBuffer.files[0][0].courseStars[10] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseStars[11] = (gSaveBuffer.files[0][0].courseStars[11] & 0xffffffffffffff00) | 0xff;
-+    /* 81207718 FFFF */ gSaveBuffer.files[0][0].courseStars[12] = (gSaveBuffer.files[0][0].courseStars[12] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseStars[13] = (gSaveBuffer.files[0][0].courseStars[13] & 0xffffffffffffff00) | 0xff;
-+    /* 8120771A FFFF */ gSaveBuffer.files[0][0].courseStars[14] = (gSaveBuffer.files[0][0].courseStars[14] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseStars[15] = (gSaveBuffer.files[0][0].courseStars[15] & 0xffffffffffffff00) | 0xff;
-+    /* 8120771C FFFF */ gSaveBuffer.files[0][0].courseStars[16] = (gSaveBuffer.files[0][0].courseStars[16] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseStars[17] = (gSaveBuffer.files[0][0].courseStars[17] & 0xffffffffffffff00) | 0xff;
-+    /* 8120771E FFFF */ gSaveBuffer.files[0][0].courseStars[18] = (gSaveBuffer.files[0][0].courseStars[18] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseStars[19] = (gSaveBuffer.files[0][0].courseStars[19] & 0xffffffffffffff00) | 0xff;
-+    /* 81207720 FFFF */ gSaveBuffer.files[0][0].courseStars[20] = (gSaveBuffer.files[0][0].courseStars[20] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseStars[21] = (gSaveBuffer.files[0][0].courseStars[21] & 0xffffffffffffff00) | 0xff;
-+    /* 81207722 FFFF */ gSaveBuffer.files[0][0].courseStars[22] = (gSaveBuffer.files[0][0].courseStars[22] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseStars[23] = (gSaveBuffer.files[0][0].courseStars[23] & 0xffffffffffffff00) | 0xff;
-+    /* 81207724 FFFF */ gSaveBuffer.files[0][0].courseStars[24] = (gSaveBuffer.files[0][0].courseStars[24] & 0xffffffffffffff00) | 0xff; gSaveBuffer.files[0][0].courseCoinScores[0] = (gSaveBuffer.files[0][0].courseCoinScores[0] & 0xffffffffffffff00) | 0xff;
++    /* 8120770C FFFF */ gSaveBuffer.files[0][0].courseStars[0] = 0xff; gSaveBuffer.files[0][0].courseStars[1] = 0xff;
++    /* 8120770E FFFF */ gSaveBuffer.files[0][0].courseStars[2] = 0xff; gSaveBuffer.files[0][0].courseStars[3] = 0xff;
++    /* 81207710 FFFF */ gSaveBuffer.files[0][0].courseStars[4] = 0xff; gSaveBuffer.files[0][0].courseStars[5] = 0xff;
++    /* 81207712 FFFF */ gSaveBuffer.files[0][0].courseStars[6] = 0xff; gSaveBuffer.files[0][0].courseStars[7] = 0xff;
++    /* 81207714 FFFF */ gSaveBuffer.files[0][0].courseStars[8] = 0xff; gSaveBuffer.files[0][0].courseStars[9] = 0xff;
++    /* 81207716 FFFF */ gSaveBuffer.files[0][0].courseStars[10] = 0xff; gSaveBuffer.files[0][0].courseStars[11] = 0xff;
++    /* 81207718 FFFF */ gSaveBuffer.files[0][0].courseStars[12] = 0xff; gSaveBuffer.files[0][0].courseStars[13] = 0xff;
++    /* 8120771A FFFF */ gSaveBuffer.files[0][0].courseStars[14] = 0xff; gSaveBuffer.files[0][0].courseStars[15] = 0xff;
++    /* 8120771C FFFF */ gSaveBuffer.files[0][0].courseStars[16] = 0xff; gSaveBuffer.files[0][0].courseStars[17] = 0xff;
++    /* 8120771E FFFF */ gSaveBuffer.files[0][0].courseStars[18] = 0xff; gSaveBuffer.files[0][0].courseStars[19] = 0xff;
++    /* 81207720 FFFF */ gSaveBuffer.files[0][0].courseStars[20] = 0xff; gSaveBuffer.files[0][0].courseStars[21] = 0xff;
++    /* 81207722 FFFF */ gSaveBuffer.files[0][0].courseStars[22] = 0xff; gSaveBuffer.files[0][0].courseStars[23] = 0xff;
++    /* 81207724 FFFF */ gSaveBuffer.files[0][0].courseStars[24] = 0xff; gSaveBuffer.files[0][0].courseCoinScores[0] = 0xff;
  ",
     );
 
@@ -142,3 +215,2104 @@ fn patch_convert_loader() {
 
     patch_convert_test_cases(&decomp_data)
 }
+
+/// Check that the static decomp data has no unexpected dangling struct names
+#[test]
+fn static_references_valid() {
+    // These are unions and enums, which the loader records as named types but
+    // doesn't load as structs. Codes touching them fail with `NoStruct`.
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC.validate_references(),
+        vec![
+            "DynListBankFlag",
+            "DynUnion",
+            "GdAnimations",
+            "GdViewFlags",
+            "NotePool",
+            "ObjTypeFlag",
+            "ObjVarVal",
+            "SpTaskState",
+            "Unk801B9E68",
+            "ValPtrType",
+        ]
+    );
+}
+
+/// Use the hook's first statement as trailing context
+#[test]
+fn patch_compact() {
+    let code = "8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let mut options = sm64gs2pc::PatchOptions::default();
+    options.target.first_statement = Some(String::from("    run_other_cheats();"));
+    options.compact = true;
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Always have Metal Cap", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,5 @@
+ void run_gameshark_cheats(void) {
++
++    /* Always have Metal Cap */
++    /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
+     run_other_cheats();",
+    );
+}
+
+/// Use a non-blank line after the hook as trailing context
+#[test]
+fn patch_trailing_context() {
+    let code = "8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let mut options = sm64gs2pc::PatchOptions::default();
+    options.target.trailing_context = String::from("    // Cheats");
+
+    let source = "#include \"gameshark.h\"
+
+void run_gameshark_cheats(void) {
+    // Cheats
+}
+";
+    options.target.check_trailing_context(source).unwrap();
+    assert!(sm64gs2pc::PatchTarget::default()
+        .check_trailing_context(source)
+        .is_err());
+
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Always have Metal Cap", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,5 @@
+ void run_gameshark_cheats(void) {
++
++    /* Always have Metal Cap */
++    /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
+     // Cheats",
+    );
+}
+
+/// Patch a port that ships the hook stubbed with an empty body
+#[test]
+fn patch_empty_hook() {
+    let code = "8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        target: sm64gs2pc::PatchTarget::empty_hook(),
+        ..Default::default()
+    };
+
+    let source = "#include <PR/ultratypes.h>
+
+#include \"gameshark.h\"
+void run_gameshark_cheats(void) {
+}
+";
+    options.target.check_trailing_context(source).unwrap();
+
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Always have Metal Cap", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,5 @@
+ void run_gameshark_cheats(void) {
++
++    /* Always have Metal Cap */
++    /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
+ }",
+    );
+    assert_eq!(
+        apply_patch(source, &patch),
+        "#include <PR/ultratypes.h>
+
+#include \"gameshark.h\"
+void run_gameshark_cheats(void) {
+
+    /* Always have Metal Cap */
+    /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
+}
+"
+    );
+}
+
+/// Patch a fork that has the hook in another file
+#[test]
+fn patch_target_path() {
+    let code = "8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let mut options = sm64gs2pc::PatchOptions::default();
+    options.target.path = String::from("src/pc/cheats.c");
+
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Always have Metal Cap", code.clone(), &options)
+        .unwrap();
+
+    assert!(patch.starts_with("--- a/src/pc/cheats.c\n+++ b/src/pc/cheats.c\n"));
+
+    // The base patches don't have the hook in another file
+    assert_eq!(
+        sm64gs2pc::compatible_base_patches(&[code], &options),
+        vec![]
+    );
+}
+
+/// Patch a port that renamed the hook
+#[test]
+fn patch_hook() {
+    let code = "8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let mut options = sm64gs2pc::PatchOptions::default();
+    options.target.hook = String::from("void cheats_update(void) {");
+
+    let source = "#include \"gameshark.h\"
+
+void cheats_update(void) {
+
+}
+";
+    options.target.check_trailing_context(source).unwrap();
+    assert!(sm64gs2pc::PatchTarget::default()
+        .check_trailing_context(source)
+        .is_err());
+
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Always have Metal Cap", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,5 @@
+ void cheats_update(void) {
++
++    /* Always have Metal Cap */
++    /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
+ ",
+    );
+}
+
+/// Patch a source whose hook isn't on line 4
+#[test]
+fn patch_hook_line() {
+    let code = "8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let source = "#include <PR/ultratypes.h>
+
+#include \"sm64.h\"
+#include \"gameshark.h\"
+
+/* Cheats are inserted into the hook */
+
+void run_gameshark_cheats(void) {
+
+}
+";
+    let mut options = sm64gs2pc::PatchOptions::default();
+    options.target.locate_hook(source).unwrap();
+    assert_eq!(options.target.hook_line, 8);
+
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Always have Metal Cap", code.clone(), &options)
+        .unwrap();
+    assert!(patch.contains("\n@@ -8,2 +8,5 @@\n"));
+
+    // The hunk is found by its context, even with the default line number
+    let default_patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch("Always have Metal Cap", code)
+        .unwrap();
+    assert_eq!(
+        apply_patch(source, &patch),
+        apply_patch(source, &default_patch)
+    );
+    assert!(apply_patch(source, &patch)
+        .contains("void run_gameshark_cheats(void) {\n\n    /* Always have Metal Cap */\n"));
+
+    options.target.hook = String::from("void cheats_update(void) {");
+    assert!(options.target.locate_hook(source).is_err());
+}
+
+/// Check the offset and size of each written lvalue before the write
+#[test]
+fn patch_bounds_asserts() {
+    let code = "D033AFA1 0020
+8133B176 0015
+8133B1BC 4220"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        bounds_asserts: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,9 @@
+ void run_gameshark_cheats(void) {
++
++    /* Moon Jump */
++    assert((char *) &gMarioStates[0].flags - (char *) &gMarioStates == 0x4 && sizeof(gMarioStates[0].flags) == 4);
++    /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20)
++    /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
++    assert((char *) &gMarioStates[0].vel[1] - (char *) &gMarioStates == 0x4c && sizeof(gMarioStates[0].vel[1]) == 4);
++    /* 8133B1BC 4220 */ *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
+ ",
+    );
+}
+
+/// Guard button-activated writes with the GameShark button
+#[test]
+fn patch_button_write() {
+    let code = "8933B1BC 4220"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch("Moon Jump", code)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,5 @@
+ void run_gameshark_cheats(void) {
++
++    /* Moon Jump */
++    /* 8933B1BC 4220 */ if (GAMESHARK_BUTTON_HELD) *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
+ ",
+    );
+}
+
+/// Expand a repeater into a write for each repetition, even past the end of an
+/// array
+#[test]
+fn patch_repeat() {
+    let code = "50000201 0001
+80207724 0003"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch("Stars", code)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,6 @@
+ void run_gameshark_cheats(void) {
++
++    /* Stars */
++    /* 80207724 0003 */ gSaveBuffer.files[0][0].courseStars[24] = 0x3;
++    /* 80207725 0004 */ gSaveBuffer.files[0][0].courseCoinScores[0] = 0x4;
+ ",
+    );
+}
+
+/// Writes to the uncached mirror of memory convert like cached writes
+#[test]
+fn patch_uncached_write() {
+    assert_eq!(
+        gs_to_patch(
+            &sm64gs2pc::DECOMP_DATA_STATIC,
+            "Always have Metal Cap",
+            "A033B176 0015"
+        ),
+        gs_to_patch(
+            &sm64gs2pc::DECOMP_DATA_STATIC,
+            "Always have Metal Cap",
+            "8033B176 0015"
+        ),
+    );
+}
+
+/// Ignore a master code at the start of a cheat
+#[test]
+fn patch_master_code() {
+    let patch = gs_to_patch(
+        &sm64gs2pc::DECOMP_DATA_STATIC,
+        "Always have Metal Cap",
+        "FF000220 0000
+8133B176 0015",
+    );
+
+    assert!(patch.contains("\n+    /* FF000220 0000 */ /* ignored: master code */;\n"));
+    assert!(patch.contains("\n+    /* 8133B176 0015 */ gMarioStates[0].flags = "));
+}
+
+/// Convert cheats with options that merge and group lines the same way every
+/// time
+#[test]
+fn patch_deterministic() {
+    let cheats = || {
+        vec![
+            (
+                Some(String::from("Stars")),
+                String::from("Have 180 Stars"),
+                "50000D02 0000
+8120770C FFFF"
+                    .parse::<sm64gs2pc::gameshark::Code>()
+                    .unwrap(),
+            ),
+            (
+                None,
+                String::from("Moon Jump"),
+                "D033AFA1 0020
+8133B1BC 4220
+D033AFA1 0020
+8133B17C 0300
+D033AFA1 0020
+D033B17D 000C
+8133B176 0015"
+                    .parse::<sm64gs2pc::gameshark::Code>()
+                    .unwrap(),
+            ),
+            (
+                Some(String::from("Mario")),
+                String::from("Always have Metal Cap"),
+                "8133B176 0015"
+                    .parse::<sm64gs2pc::gameshark::Code>()
+                    .unwrap(),
+            ),
+        ]
+    };
+    let options = sm64gs2pc::PatchOptions {
+        section_comments: true,
+        named_constants: true,
+        merge_guards: true,
+        fold_checks: true,
+        array_fill_loops: true,
+        toggle_variables: true,
+        ..Default::default()
+    };
+
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_codes_to_patch_multi(&cheats(), &options)
+        .unwrap();
+    let decomp_data = sm64gs2pc::DECOMP_DATA_STATIC.clone();
+    for _ in 0..3 {
+        assert_eq!(
+            decomp_data
+                .gs_codes_to_patch_multi(&cheats(), &options)
+                .unwrap(),
+            patch
+        );
+    }
+}
+
+/// Insert cheats into several target files with one patch
+#[test]
+fn patch_files() {
+    let metal_cap = "8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let moon_jump = "D033AFA1 0020
+8133B1BC 4220"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_codes_to_patch_files(
+            &[
+                (
+                    String::from("src/game/cheats/mario.c"),
+                    None,
+                    String::from("Always have Metal Cap"),
+                    metal_cap,
+                ),
+                (
+                    String::from("src/game/cheats/movement.c"),
+                    None,
+                    String::from("Moon Jump"),
+                    moon_jump,
+                ),
+            ],
+            &sm64gs2pc::PatchOptions::default(),
+        )
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/cheats/mario.c
++++ b/src/game/cheats/mario.c
+@@ -4,2 +4,5 @@
+ void run_gameshark_cheats(void) {
++
++    /* Always have Metal Cap */
++    /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
+ 
+--- a/src/game/cheats/movement.c
++++ b/src/game/cheats/movement.c
+@@ -4,2 +4,6 @@
+ void run_gameshark_cheats(void) {
++
++    /* Moon Jump */
++    /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20)
++    /* 8133B1BC 4220 */ *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
+ ",
+    );
+
+    let patch = format!("{}\n", patch);
+    assert_eq!(patch::Patch::from_multiple(&patch).unwrap().len(), 2);
+}
+
+/// Only run a cheat during gameplay
+#[test]
+fn patch_in_game_guard() {
+    let code = "D033AFA1 0020
+8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let mut options = sm64gs2pc::PatchOptions::default();
+    options.target.in_game_check = String::from("gCurrLevelNum != LEVEL_NONE");
+    options.in_game_guard = true;
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Always have Metal Cap", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,8 @@
+ void run_gameshark_cheats(void) {
++
++    /* Always have Metal Cap */
++    if (gCurrLevelNum != LEVEL_NONE) {
++        /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20)
++        /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
++    }
+ ",
+    );
+}
+
+/// Refuse to convert a code that expands past the limit
+#[test]
+fn patch_expansion_too_large() {
+    let code = "D033AFA1 0020
+8133B1BC 4220
+8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        max_expansion: 2,
+        ..Default::default()
+    };
+
+    let err = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code.clone(), &options)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Code expands to more than 2 operations");
+
+    let options = sm64gs2pc::PatchOptions {
+        max_expansion: 3,
+        ..Default::default()
+    };
+    assert!(sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code, &options)
+        .is_ok());
+}
+
+/// Group cheats in one patch under section comments
+#[test]
+fn patch_sections() {
+    let cheats = vec![
+        (
+            None,
+            String::from("Always have Metal Cap"),
+            "8133B176 0015".parse().unwrap(),
+        ),
+        (
+            Some(String::from("Movement")),
+            String::from("Moon Jump"),
+            "D033AFA1 0020\n8133B1BC 4220".parse().unwrap(),
+        ),
+    ];
+    let options = sm64gs2pc::PatchOptions {
+        section_comments: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_codes_to_patch_multi(&cheats, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,13 @@
+ void run_gameshark_cheats(void) {
++
++    /* ===== Movement ===== */
++
++    /* Moon Jump */
++    /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20)
++    /* 8133B1BC 4220 */ *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
++
++    /* ===== Uncategorized ===== */
++
++    /* Always have Metal Cap */
++    /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
+ ",
+    );
+
+    // Section comments aren't mistaken for cheat names
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC.patch_to_code(&patch).unwrap(),
+        vec![
+            (cheats[1].1.clone(), cheats[1].2.clone()),
+            (cheats[0].1.clone(), cheats[0].2.clone()),
+        ]
+    );
+}
+
+/// Remove cheats from a patched source
+#[test]
+fn undo_patch() {
+    let source = "#include <PR/ultratypes.h>
+
+#include \"gameshark.h\"
+void run_gameshark_cheats(void) {
+
+}
+";
+    let metal_cap = "8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let moon_jump = "D033AFA1 0020
+8133B1BC 4220
+D033AFA1 0020
+8133B17C 0300"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+
+    for options in [
+        sm64gs2pc::PatchOptions::default(),
+        sm64gs2pc::PatchOptions {
+            in_game_guard: true,
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            wrap_in_function: true,
+            in_game_guard: true,
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            metadata_headers: true,
+            merge_guards: true,
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            wrap_in_function: true,
+            metadata_headers: true,
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            clang_format: true,
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            bounds_asserts: true,
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            activation_log: Some(String::from("printf")),
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            line_comments: false,
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            line_comments: false,
+            in_game_guard: true,
+            merge_guards: true,
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            line_comments: false,
+            clang_format: true,
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            target: sm64gs2pc::PatchTarget {
+                path: String::from("src/game/cheats.c"),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    ] {
+        let with_metal_cap = apply_patch(
+            source,
+            &sm64gs2pc::DECOMP_DATA_STATIC
+                .gs_code_to_patch_with_options("Metal Cap", metal_cap.clone(), &options)
+                .unwrap(),
+        );
+        let with_both = apply_patch(
+            &with_metal_cap,
+            &sm64gs2pc::DECOMP_DATA_STATIC
+                .gs_code_to_patch_with_options("Moon Jump", moon_jump.clone(), &options)
+                .unwrap(),
+        );
+
+        let undo = sm64gs2pc::DECOMP_DATA_STATIC
+            .undo_patch("Moon Jump", &with_both, &options)
+            .unwrap();
+        assert!(undo.starts_with(&format!("--- a/{}\n", options.target.path)));
+        assert_eq!(apply_patch(&with_both, &undo), with_metal_cap);
+
+        let undo = sm64gs2pc::DECOMP_DATA_STATIC
+            .undo_patch("Metal Cap", &with_metal_cap, &options)
+            .unwrap();
+        assert_eq!(apply_patch(&with_metal_cap, &undo), source);
+    }
+
+    assert!(sm64gs2pc::DECOMP_DATA_STATIC
+        .undo_patch("Moon Jump", source, &sm64gs2pc::PatchOptions::default())
+        .is_err());
+}
+
+/// Write values as named constants
+#[test]
+fn patch_named_constants() {
+    let code = "8133B176 0015
+8133B17C 0300
+8133B17E 0880"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        named_constants: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Metal Cap", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,10 @@
+ void run_gameshark_cheats(void) {
++
++    /* Metal Cap */
++    #define MARIO_STATES_0_FLAGS_VALUE 0x15
++    #define MARIO_STATES_0_ACTION_VALUE 0x3000000
++    #define MARIO_STATES_0_ACTION_VALUE_2 0x880
++    /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | MARIO_STATES_0_FLAGS_VALUE;
++    /* 8133B17C 0300 */ gMarioStates[0].action = (gMarioStates[0].action & 0xffffffff0000ffff) | MARIO_STATES_0_ACTION_VALUE;
++    /* 8133B17E 0880 */ gMarioStates[0].action = (gMarioStates[0].action & 0xffffffffffff0000) | MARIO_STATES_0_ACTION_VALUE_2;
+ ",
+    );
+}
+
+/// Include resolved addresses in comments
+#[test]
+fn patch_verbose_comments() {
+    let code = "8133B176 0015
+8138F800 ABCD"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        verbose_comments: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Always have Metal Cap", code.clone(), &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,6 @@
+ void run_gameshark_cheats(void) {
++
++    /* Always have Metal Cap */
++    /* 8133B176 0015 -> 0x8033B176 gMarioStates[0].flags */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
++    /* 8138F800 ABCD -> 0x8038F800 */ /* warning: no symbols in framebuffers: *(uint16_t *) 0x8038f800 = 0xabcd; */;
+ ",
+    );
+
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC.patch_to_code(&patch).unwrap(),
+        vec![(String::from("Always have Metal Cap"), code)]
+    );
+}
+
+/// A check before a write to memory without symbols only guards that write
+#[test]
+fn patch_check_before_unmapped_write() {
+    let code = "D033AFA1 0020
+8138F800 ABCD
+8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch("Metal Cap", code.clone())
+        .unwrap();
+
+    assert!(patch.contains(
+        "/* 8138F800 ABCD */ /* warning: no symbols in framebuffers: \
+         *(uint16_t *) 0x8038f800 = 0xabcd; */;\n"
+    ));
+    assert!(patch.contains("\n+    /* 8133B176 0015 */ gMarioStates[0].flags = "));
+
+    // The write is dropped, so the code isn't fully converted
+    assert_eq!(sm64gs2pc::DECOMP_DATA_STATIC.validate(&code).len(), 1);
+    assert!(sm64gs2pc::compatible_versions(
+        &code,
+        &[(&sm64gs2pc::Version::Us, &*sm64gs2pc::DECOMP_DATA_STATIC)]
+    )
+    .is_empty());
+}
+
+/// Omit code line comments
+#[test]
+fn patch_no_line_comments() {
+    let code = "D033AFA1 0020
+8133B1BC 4220"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        line_comments: false,
+        ..Default::default()
+    };
+
+    let commented = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch("Moon Jump", code.clone())
+        .unwrap();
+    let uncommented = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        uncommented,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,6 @@
+ void run_gameshark_cheats(void) {
++
++    /* Moon Jump */
++    if ((gControllers[0].buttonDown & 0xff) == 0x20)
++    *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
+ ",
+    );
+
+    // The comments are on the same lines as the C source code
+    assert_eq!(commented.lines().count(), uncommented.lines().count());
+    assert!(uncommented.len() < commented.len());
+}
+
+/// Only run cheats if their toggle variables are set
+#[test]
+fn patch_toggle_variables() {
+    let code = "D033AFA1 0020
+8133B1BC 4220"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        toggle_variables: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code.clone(), &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,10 @@
++static bool cheat_moon_jump_enabled = true;
++
+ void run_gameshark_cheats(void) {
++
++    /* Moon Jump */
++    if (cheat_moon_jump_enabled) {
++        /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20)
++        /* 8133B1BC 4220 */ *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
++    }
+ ",
+    );
+
+    // Functions are preceded by their toggle variable
+    let options = sm64gs2pc::PatchOptions {
+        toggle_variables: true,
+        wrap_in_function: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code, &options)
+        .unwrap();
+    assert!(patch.contains(
+        "
++static bool cheat_moon_jump_enabled = true;
++void cheat_moon_jump(void) {
++    if (cheat_moon_jump_enabled) {
+"
+    ));
+}
+
+/// Log the first time that each cheat runs
+#[test]
+fn patch_activation_log() {
+    let code = "D033AFA1 0020
+8133B1BC 4220"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        activation_log: Some(String::from("osSyncPrintf")),
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon \"Jump\"", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,11 @@
+ void run_gameshark_cheats(void) {
++
++    /* Moon \"Jump\" */
++    static bool cheat_moon_jump_logged = false;
++    if (!cheat_moon_jump_logged) {
++        cheat_moon_jump_logged = true;
++        osSyncPrintf(\"GameShark cheat activated: %s\\n\", \"Moon \\\"Jump\\\"\");
++    }
++    /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20)
++    /* 8133B1BC 4220 */ *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
+ ",
+    );
+}
+
+/// Put a metadata comment before each cheat
+#[test]
+fn patch_metadata_headers() {
+    let code = "D033AFA1 0020
+8133B1BC 4220"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        metadata_headers: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon \"Jump\"", code.clone(), &options)
+        .unwrap();
+
+    let header = patch
+        .lines()
+        .find_map(|line| line.strip_prefix("+    /* @sm64gs2pc "))
+        .unwrap();
+    assert_eq!(
+        header,
+        format!(
+            "name=\"Moon \\\"Jump\\\"\" lines=2 fingerprint={:016x} */",
+            code.fingerprint()
+        )
+    );
+
+    // The metadata comment isn't a cheat name
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC.patch_to_code(&patch).unwrap(),
+        vec![(String::from("Moon \"Jump\""), code)]
+    );
+}
+
+/// Create the hook's files instead of patching them
+#[test]
+fn patch_self_contained() {
+    let code = "8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        self_contained: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Always have Metal Cap", code.clone(), &options)
+        .unwrap();
+
+    // The hook is called from `game_init.c`
+    assert_eq!(
+        patch,
+        "--- a/src/game/game_init.c
++++ b/src/game/game_init.c
+@@ -21,2 +21,3 @@
+ #include \"thread6.h\"
++#include \"gameshark.h\"
+ #include <prevent_bss_reordering.h>
+@@ -647,2 +648,3 @@
+         display_and_vsync();
++        run_gameshark_cheats();
+ 
+--- /dev/null
++++ b/src/game/gameshark.h
+@@ -0,0 +1,6 @@
++#ifndef GAMESHARK_H
++#define GAMESHARK_H
++
++void run_gameshark_cheats(void);
++
++#endif // GAMESHARK_H
+--- /dev/null
++++ b/src/game/gameshark.c
+@@ -0,0 +1,18 @@
++#include <PR/ultratypes.h>
++
++#include \"sm64.h\"
++#include \"area.h\"
++#include \"camera.h\"
++#include \"game_init.h\"
++#include \"gameshark.h\"
++#include \"level_update.h\"
++#include \"mario.h\"
++#include \"object_list_processor.h\"
++#include \"save_file.h\"
++
++void run_gameshark_cheats(void) {
++
++    /* Always have Metal Cap */
++    /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
++
++}",
+    );
+
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC.patch_to_code(&patch).unwrap(),
+        vec![(String::from("Always have Metal Cap"), code.clone())]
+    );
+
+    // Toggle variables need `bool`
+    let toggle_options = sm64gs2pc::PatchOptions {
+        toggle_variables: true,
+        ..options.clone()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Always have Metal Cap", code.clone(), &toggle_options)
+        .unwrap();
+    assert!(patch.contains("\n+#include <stdbool.h>\n"));
+
+    // The created files don't define the in-game and button checks
+    let guard_options = sm64gs2pc::PatchOptions {
+        in_game_guard: true,
+        ..options.clone()
+    };
+    let err = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Always have Metal Cap", code, &guard_options)
+        .unwrap_err();
+    assert_eq!(err.code(), "self-contained-undefined");
+    let button_code = "8933B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let err = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Metal Cap on Button", button_code, &options)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Self-contained patches don't define `GAMESHARK_BUTTON_HELD`"
+    );
+}
+
+/// Check a repeated activator once
+#[test]
+fn patch_merge_guards() {
+    let code = "D033AFA1 0020
+8133B1BC 4220
+D033AFA1 0020
+8133B17C 0300
+D033AFA1 0020
+8133B17E 0880
+D033B1BD 0002
+8133B1BC 0000"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        merge_guards: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,11 @@
+ void run_gameshark_cheats(void) {
++
++    /* Moon Jump */
++    /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20) {
++        /* 8133B1BC 4220 */ *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
++        /* 8133B17C 0300 */ gMarioStates[0].action = (gMarioStates[0].action & 0xffffffff0000ffff) | 0x3000000;
++        /* 8133B17E 0880 */ gMarioStates[0].action = (gMarioStates[0].action & 0xffffffffffff0000) | 0x880;
++    }
++    /* D033B1BD 0002 */ if ((*(uint32_t *) &gMarioStates[0].vel[1] & 0xff0000) == 0x20000)
++    /* 8133B1BC 0000 */ *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x0;
+ ",
+    );
+}
+
+/// Fold a chain of checks into one condition
+#[test]
+fn patch_fold_checks() {
+    let code = "D033AFA1 0020
+D033B17D 000C
+8133B1BC 4220"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        fold_checks: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code.clone(), &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,6 @@
+ void run_gameshark_cheats(void) {
++
++    /* Moon Jump */
++    /* D033AFA1 0020 */ /* D033B17D 000C */ if ((gControllers[0].buttonDown & 0xff) == 0x20 && (gMarioStates[0].action & 0xff0000) == 0xc0000)
++    /* 8133B1BC 4220 */ *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
+ ",
+    );
+
+    let cheats = sm64gs2pc::DECOMP_DATA_STATIC.patch_to_code(&patch).unwrap();
+    assert_eq!(cheats, vec![(String::from("Moon Jump"), code)]);
+}
+
+/// Fill an array with a loop
+#[test]
+fn patch_array_fill_loops() {
+    let code = "8120770C FFFF
+8120770E FFFF
+81207710 FFFF
+81207712 FFFF
+81207714 FFFF
+81207716 FFFF
+81207718 FFFF
+8120771A FFFF
+8120771C FFFF
+8120771E FFFF
+81207720 FFFF
+81207722 FFFF
+81207724 FFFF"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        array_fill_loops: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Have 180 Stars", code.clone(), &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,5 @@
+ void run_gameshark_cheats(void) {
++
++    /* Have 180 Stars */
++    /* 8120770C FFFF */ /* 8120770E FFFF */ /* 81207710 FFFF */ /* 81207712 FFFF */ /* 81207714 FFFF */ /* 81207716 FFFF */ /* 81207718 FFFF */ /* 8120771A FFFF */ /* 8120771C FFFF */ /* 8120771E FFFF */ /* 81207720 FFFF */ /* 81207722 FFFF */ /* 81207724 FFFF */ for (int i = 0; i < 25; i++) gSaveBuffer.files[0][0].courseStars[i] = 0xff; gSaveBuffer.files[0][0].courseCoinScores[0] = 0xff;
+ ",
+    );
+
+    let cheats = sm64gs2pc::DECOMP_DATA_STATIC.patch_to_code(&patch).unwrap();
+    assert_eq!(cheats, vec![(String::from("Have 180 Stars"), code)]);
+}
+
+/// Fill an array with a loop, with named constants
+#[test]
+fn patch_array_fill_loops_named_constants() {
+    let code = "8120770C FFFF
+8120770E FFFF
+81207710 FFFF
+81207712 FFFF
+81207714 FFFF
+81207716 FFFF
+81207718 FFFF
+8120771A FFFF
+8120771C FFFF
+8120771E FFFF
+81207720 FFFF
+81207722 FFFF
+81207724 FFFF"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        array_fill_loops: true,
+        named_constants: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Have 180 Stars", code, &options)
+        .unwrap();
+
+    // Each constant is defined, including the one for the byte past the end
+    // of the array
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,7 @@
+ void run_gameshark_cheats(void) {
++
++    /* Have 180 Stars */
++    #define SAVE_BUFFER_FILES_0_0_COURSE_STARS_VALUE 0xff
++    #define SAVE_BUFFER_FILES_0_0_COURSE_COIN_SCORES_0_VALUE 0xff
++    /* 8120770C FFFF */ /* 8120770E FFFF */ /* 81207710 FFFF */ /* 81207712 FFFF */ /* 81207714 FFFF */ /* 81207716 FFFF */ /* 81207718 FFFF */ /* 8120771A FFFF */ /* 8120771C FFFF */ /* 8120771E FFFF */ /* 81207720 FFFF */ /* 81207722 FFFF */ /* 81207724 FFFF */ for (int i = 0; i < 25; i++) gSaveBuffer.files[0][0].courseStars[i] = SAVE_BUFFER_FILES_0_0_COURSE_STARS_VALUE; gSaveBuffer.files[0][0].courseCoinScores[0] = SAVE_BUFFER_FILES_0_0_COURSE_COIN_SCORES_0_VALUE;
+ ",
+    );
+}
+
+/// Set a small array with a compound literal
+#[test]
+fn patch_array_initializers() {
+    let code = "80207725 0001
+81207726 0203
+81207728 0405
+8120772A 0607
+8120772C 0809
+8120772E 0A0B
+81207730 0C0D
+81207732 0E0F"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        array_initializers: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Coin Scores", code.clone(), &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,5 @@
+ void run_gameshark_cheats(void) {
++
++    /* Coin Scores */
++    /* 80207725 0001 */ /* 81207726 0203 */ /* 81207728 0405 */ /* 8120772A 0607 */ /* 8120772C 0809 */ /* 8120772E 0A0B */ /* 81207730 0C0D */ /* 81207732 0E0F */ memcpy(&gSaveBuffer.files[0][0].courseCoinScores, (uint8_t[]){0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x8, 0x9, 0xa, 0xb, 0xc, 0xd, 0xe, 0xf}, sizeof(gSaveBuffer.files[0][0].courseCoinScores));
+ ",
+    );
+
+    let cheats = sm64gs2pc::DECOMP_DATA_STATIC.patch_to_code(&patch).unwrap();
+    assert_eq!(cheats, vec![(String::from("Coin Scores"), code)]);
+}
+
+/// Lay out C source like `clang-format`
+#[test]
+fn patch_clang_format() {
+    // Two checks guarding a write to two fields
+    let code = "D033AFA1 0020
+D033B17D 000C
+81207724 FFFF"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        clang_format: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Star Jump", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,8 @@
+ void run_gameshark_cheats(void) {
++
++    /* Star Jump */
++    /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20)
++        /* D033B17D 000C */ if ((gMarioStates[0].action & 0xff0000) == 0xc0000)
++            /* 81207724 FFFF */ gSaveBuffer.files[0][0].courseStars[24] = 0xff;
++    gSaveBuffer.files[0][0].courseCoinScores[0] = 0xff;
+ ",
+    );
+
+    // Check the style of each added line
+    for line in patch.lines().filter_map(|line| line.strip_prefix('+')) {
+        let code = match line.rsplit_once("*/") {
+            Some((_, code)) => code,
+            None => line,
+        };
+        let indent = line.len() - line.trim_start().len();
+
+        assert_eq!(line.trim_end(), line, "trailing whitespace: {}", line);
+        assert_eq!(indent % 4, 0, "indent isn't 4 spaces: {}", line);
+        assert!(
+            code.matches(';').count() <= 1,
+            "several statements: {}",
+            line
+        );
+        assert!(!code.trim().contains("  "), "double space: {}", line);
+
+        // Binary operators have spaces around them
+        let chars = code.chars().collect::<Vec<char>>();
+        for (i, c) in chars.iter().enumerate() {
+            if *c == '=' || *c == '|' {
+                let is_spaced = |c: Option<&char>| matches!(c, Some(' ') | Some('='));
+                assert!(
+                    is_spaced(chars.get(i - 1)) && is_spaced(chars.get(i + 1)),
+                    "no space around {}: {}",
+                    c,
+                    line
+                );
+            }
+        }
+    }
+
+    // The write on its own line is part of the cheat
+    let source = apply_patch(
+        "void run_gameshark_cheats(void) {\n\n}\n",
+        &patch.replace("@@ -4,2 +4,", "@@ -1,2 +1,"),
+    );
+    let undo = sm64gs2pc::DECOMP_DATA_STATIC
+        .undo_patch("Star Jump", &source, &sm64gs2pc::PatchOptions::default())
+        .unwrap();
+    assert_eq!(
+        apply_patch(&source, &undo),
+        "void run_gameshark_cheats(void) {\n\n}\n"
+    );
+}
+
+/// Split a patch with several cheats into a patch for each cheat
+#[test]
+fn patch_split() {
+    let cheats = vec![
+        (
+            Some(String::from("Mario")),
+            String::from("Always have Metal Cap"),
+            "8133B176 0015"
+                .parse::<sm64gs2pc::gameshark::Code>()
+                .unwrap(),
+        ),
+        (
+            Some(String::from("Movement")),
+            String::from("Moon Jump"),
+            "D033AFA1 0020\n8133B1BC 4220"
+                .parse::<sm64gs2pc::gameshark::Code>()
+                .unwrap(),
+        ),
+    ];
+
+    let all_options = [
+        sm64gs2pc::PatchOptions::default(),
+        sm64gs2pc::PatchOptions {
+            metadata_headers: true,
+            toggle_variables: true,
+            in_game_guard: true,
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            wrap_in_function: true,
+            metadata_headers: true,
+            toggle_variables: true,
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            self_contained: true,
+            toggle_variables: true,
+            ..Default::default()
+        },
+        sm64gs2pc::PatchOptions {
+            self_contained: true,
+            wrap_in_function: true,
+            ..Default::default()
+        },
+    ];
+
+    for options in &all_options {
+        // Section comments are left out of the split patches
+        for section_comments in [false, true] {
+            let combined_options = sm64gs2pc::PatchOptions {
+                section_comments,
+                ..options.clone()
+            };
+            let combined = sm64gs2pc::DECOMP_DATA_STATIC
+                .gs_codes_to_patch_multi(&cheats, &combined_options)
+                .unwrap();
+
+            let expected = cheats
+                .iter()
+                .map(|(_, name, code)| {
+                    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+                        .gs_code_to_patch_with_options(name, code.clone(), options)
+                        .unwrap();
+                    (name.clone(), patch)
+                })
+                .collect::<Vec<(String, String)>>();
+
+            assert_eq!(
+                sm64gs2pc::DECOMP_DATA_STATIC
+                    .split_patch(&combined)
+                    .unwrap(),
+                expected,
+                "{:?}",
+                combined_options,
+            );
+        }
+    }
+}
+
+/// Patches that only differ in comments and whitespace are equivalent
+#[test]
+fn patch_equivalent_formats() {
+    let code = "D033AFA1 0020\n8133B1BC 4220";
+    let patch = gs_to_patch(&sm64gs2pc::DECOMP_DATA_STATIC, "Moon Jump", code);
+
+    // Patch in an older format, without code line comments
+    let old_patch = "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,5 @@
+ void run_gameshark_cheats(void) {
++    /* Moon Jump */
++    if ((gControllers[0].buttonDown & 0xff) == 0x20)
++        *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
+ ";
+    assert!(patches_equivalent(&patch, old_patch));
+
+    let verbose_patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options(
+            "Moon Jump",
+            code.parse().unwrap(),
+            &sm64gs2pc::PatchOptions {
+                verbose_comments: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert!(patches_equivalent(&patch, &verbose_patch));
+
+    // Different literals aren't equivalent
+    let unsigned_patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options(
+            "Moon Jump",
+            code.parse().unwrap(),
+            &sm64gs2pc::PatchOptions {
+                unsigned_literals: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert!(!patches_equivalent(&patch, &unsigned_patch));
+}
+
+/// Convert several cheats from one file
+#[test]
+fn patch_multi() {
+    let file = "; Always have Metal Cap
+8133B176 0015
+
+; Moon Jump
+; Hold L to jump
+D033AFA1 0020
+8133B1BC 4220
+";
+    let named_cheats = sm64gs2pc::gameshark::parse_multi(file, None).unwrap();
+    let cheats = named_cheats
+        .iter()
+        .cloned()
+        .map(|(name, code)| (None, name, code))
+        .collect::<Vec<_>>();
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_codes_to_patch_multi(&cheats, &Default::default())
+        .unwrap();
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC
+            .gs_cheats_to_patch(&named_cheats)
+            .unwrap(),
+        patch
+    );
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,2 +4,9 @@
+ void run_gameshark_cheats(void) {
++
++    /* Always have Metal Cap */
++    /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
++
++    /* Moon Jump */
++    /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20)
++    /* 8133B1BC 4220 */ *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
+ ",
+    );
+
+    // Blank lines don't separate cheats if there's a separator
+    let file = file
+        .replace("\n\n", "\n----\n")
+        .replace("; Hold", "\n; Hold");
+    assert_eq!(
+        sm64gs2pc::gameshark::parse_multi(&file, Some("----"))
+            .unwrap()
+            .into_iter()
+            .map(|(name, code)| (None, name, code))
+            .collect::<Vec<_>>(),
+        cheats
+    );
+}
+
+/// Create a context diff instead of a unified diff
+#[test]
+fn patch_context_diff() {
+    let code = "8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        patch_format: sm64gs2pc::PatchFormat::Context,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Always have Metal Cap", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "*** a/src/game/gameshark.c
+--- b/src/game/gameshark.c
+***************
+*** 4,5 ****
+--- 4,8 ----
+  void run_gameshark_cheats(void) {
++ 
++     /* Always have Metal Cap */
++     /* 8133B176 0015 */ gMarioStates[0].flags = (gMarioStates[0].flags & 0xffffffffffff0000) | 0x15;
+  ",
+    );
+}
+
+/// Create a patch for a file without a trailing newline
+#[test]
+fn patch_end_newline() {
+    let code = "8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Metal Cap", code.clone(), &Default::default())
+        .unwrap();
+    assert!(patch.ends_with("| 0x15;\n "));
+
+    let options = sm64gs2pc::PatchOptions {
+        end_newline: false,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Metal Cap", code, &options)
+        .unwrap();
+    assert!(patch.ends_with("| 0x15;\n \n\\ No newline at end of file"));
+}
+
+/// Explain a code, with a `float` written as two halves
+#[test]
+fn explain_float_halves() {
+    let code = "D033AFA1 0020
+8133B1BC 4220
+8133B1BE 0000
+8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC.explain(&code).unwrap(),
+        vec![
+            "D033AFA1 0020: if gControllers[0].buttonDown at byte offset 1 is 0x20",
+            "8133B1BC 4220, 8133B1BE 0000: set gMarioStates[0].vel[1] to 40.0",
+            "8133B176 0015: write 0x0015 to gMarioStates[0].flags at byte offset 2",
+        ]
+    );
+}
+
+/// List the structs that Moon Jump touches
+#[test]
+fn touched_structs_moon_jump() {
+    let code = "D033AFA1 0020
+8133B1BC 4220
+D033B1BD 0020
+8133B17C 0300
+D033B1BD 0020
+8133B17E 0880"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC
+            .touched_structs(&code)
+            .unwrap(),
+        vec!["Controller", "MarioState"]
+    );
+}
+
+/// Get the values that Always have Metal Cap stores
+#[test]
+fn written_values_metal_cap() {
+    let code = "D033AFA1 0020
+8133B176 0015
+8133B1BC 4220"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC.written_values(&code).unwrap(),
+        vec![
+            (String::from("gMarioStates[0].flags"), 0x15),
+            (String::from("gMarioStates[0].vel[1]"), 0x42200000),
+        ]
+    );
+}
+
+/// Describe a cheat as TOML, for ports that load cheats from config
+#[test]
+fn toml_metal_cap() {
+    let code = "D033AFA1 0020
+8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC
+            .gs_code_to_toml("Always have Metal Cap", &code)
+            .unwrap(),
+        "name = \"Always have Metal Cap\"
+
+[[writes]]
+field = \"gMarioStates[0].flags\"
+value = 21
+shift = 0
+bits = 16
+on_button = false
+
+[[writes.conditions]]
+field = \"gControllers[0].buttonDown\"
+value = 32
+shift = 0
+check_eq = true
+",
+    );
+}
+
+/// Warn about a save write past the last course
+#[test]
+fn save_layout_past_last_course() {
+    // Sets course 4's stars, then walks past the coin scores into the save
+    // file's signature
+    let code = "8020770F 00FF
+81207734 1234"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC
+            .check_save_layout(&code)
+            .unwrap(),
+        vec![
+            "81207734 1234: gSaveBuffer.files[0][0].signature.magic is past the last course; \
+             the code may assume a different save layout"
+        ]
+    );
+}
+
+/// Collect warnings while converting a code
+#[test]
+fn patch_warn() {
+    let code = "8138F800 ABCD
+8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let result = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_warn("Spill", code.clone(), &sm64gs2pc::PatchOptions::default())
+        .unwrap();
+
+    assert_eq!(
+        result.patch,
+        sm64gs2pc::DECOMP_DATA_STATIC
+            .gs_code_to_patch("Spill", code.clone())
+            .unwrap()
+    );
+    assert_eq!(
+        result.warnings,
+        vec![sm64gs2pc::Warning {
+            kind: sm64gs2pc::WarningKind::UnmappedWrite,
+            message: String::from(
+                "8138F800 ABCD: no symbols in framebuffers, so the write is only kept as a comment"
+            ),
+            line: code.0[0],
+        }]
+    );
+}
+
+/// Recommend applying save data writes once
+#[test]
+fn check_save_writes() {
+    let code = "D033AFA1 0020
+8020770F 00FF
+8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC
+            .check_save_writes(&code)
+            .unwrap(),
+        vec![
+            "8020770F 00FF: gSaveBuffer.files[0][0].courseStars[3] is save data; consider \
+             applying the write once in a hook that runs after a save file is loaded, instead \
+             of every frame in run_gameshark_cheats()"
+        ]
+    );
+}
+
+/// Warn about setting Mario's action to an unknown value
+#[test]
+fn check_actions() {
+    // `ACT_JUMP`
+    let code = "8133B17C 0300
+8133B17E 0880"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    assert!(sm64gs2pc::DECOMP_DATA_STATIC
+        .check_actions(&code)
+        .unwrap()
+        .is_empty());
+
+    let code = "8133B17C 1234
+8133B17E 5678"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC.check_actions(&code).unwrap(),
+        vec![
+            "gMarioStates[0].action is set to 0x12345678, which isn't a known action; the game \
+              may crash"
+        ]
+    );
+
+    // Only some bytes are written
+    let code = "8033B17C 00FF"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC.check_actions(&code).unwrap(),
+        vec![
+            "gMarioStates[0].action is set to 0xff??????, which isn't a known action; the game \
+              may crash"
+        ]
+    );
+}
+
+/// Get the signedness and width of scalars
+#[test]
+fn scalar_info() {
+    // `gSaveFileModified` is an `s8`
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC
+            .scalar_info_at(0x8033B4A6)
+            .unwrap(),
+        sm64gs2pc::ScalarInfo {
+            signed: true,
+            num_bytes: 1,
+            is_float: false,
+        }
+    );
+
+    // `gMarioStates[0].vel[1]` is a `f32`
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC
+            .scalar_info_at(0x8033B1BE)
+            .unwrap(),
+        sm64gs2pc::ScalarInfo {
+            signed: true,
+            num_bytes: 4,
+            is_float: true,
+        }
+    );
+}
+
+/// Describe a code in plain English
+#[test]
+fn describe_metal_cap() {
+    let code = "D033AFA1 0020
+8133B176 0015
+8020770F 00FF"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC.describe(&code).unwrap(),
+        vec![
+            "If byte 1 of controller 1's buttonDown is 0x20",
+            "Set Mario's flags to 0x15 (metal cap)",
+            "Write 0xFF to save file 1 course 3 stars",
+        ]
+    );
+}
+
+/// Define a cheat in its own function
+#[test]
+fn patch_wrap_in_function() {
+    let code = "D033AFA1 0020
+8133B1BC 4220"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        wrap_in_function: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code, &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,1 +4,7 @@
++/* Moon Jump: register cheat_moon_jump() with the port's cheat list */
++void cheat_moon_jump(void) {
++    /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20)
++    /* 8133B1BC 4220 */ *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
++}
++
+ void run_gameshark_cheats(void) {",
+    );
+}
+
+/// Register cheats with a runtime cheat manager
+#[test]
+fn patch_cheat_manager() {
+    let code = "D033AFA1 0020
+8133B1BC 4220"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        cheat_manager: Some(Default::default()),
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code.clone(), &options)
+        .unwrap();
+
+    assert_eq!(
+        patch,
+        "--- a/src/game/gameshark.c
++++ b/src/game/gameshark.c
+@@ -4,1 +4,7 @@
++/* Moon Jump: register cheat_moon_jump() with the port's cheat list */
++void cheat_moon_jump(void) {
++    /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20)
++    /* 8133B1BC 4220 */ *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
++}
++
+ void run_gameshark_cheats(void) {
+@@ -5,1 +11,2 @@
+ void register_gameshark_cheats(void) {
++    CheatManager_Register(\"Moon Jump\", cheat_moon_jump);",
+    );
+
+    let source = "void run_gameshark_cheats(void) {
+}
+
+void register_gameshark_cheats(void) {
+}
+";
+    assert_eq!(
+        apply_patch(source, &patch),
+        "/* Moon Jump: register cheat_moon_jump() with the port's cheat list */
+void cheat_moon_jump(void) {
+    /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20)
+    /* 8133B1BC 4220 */ *(uint32_t *) &gMarioStates[0].vel[1] = (*(uint32_t *) &gMarioStates[0].vel[1] & 0xffffffff0000ffff) | 0x42200000;
+}
+
+void run_gameshark_cheats(void) {
+}
+
+void register_gameshark_cheats(void) {
+    CheatManager_Register(\"Moon Jump\", cheat_moon_jump);
+}
+"
+    );
+
+    // The self-contained patch defines the registration function
+    let options = sm64gs2pc::PatchOptions {
+        self_contained: true,
+        ..options
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code, &options)
+        .unwrap();
+    assert!(patch.contains("\n+void register_gameshark_cheats(void);\n"));
+    assert!(patch.ends_with(
+        "
++void run_gameshark_cheats(void) {
++
++}
++
++void register_gameshark_cheats(void) {
++    CheatManager_Register(\"Moon Jump\", cheat_moon_jump);
++}"
+    ));
+}
+
+/// Add cheats as rows of a data-driven cheat table
+#[test]
+fn patch_cheat_table() {
+    let code = "D033AFA1 0020
+8133B1BC 4220"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+    let options = sm64gs2pc::PatchOptions {
+        cheat_table: Some(Default::default()),
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code.clone(), &options)
+        .unwrap();
+
+    assert!(patch.contains(
+        "
++void cheat_moon_jump(void) {
++    /* D033AFA1 0020 */ if ((gControllers[0].buttonDown & 0xff) == 0x20)
+"
+    ));
+    assert!(patch.ends_with(
+        "
+@@ -5,1 +11,2 @@
+ struct GameSharkCheat gGameSharkCheats[] = {
++    { \"Moon Jump\", true, cheat_moon_jump },"
+    ));
+
+    // The table's shape is configurable
+    let options = sm64gs2pc::PatchOptions {
+        cheat_table: Some(sm64gs2pc::CheatTable {
+            table_start: String::from("CheatEntry cheats[] = {"),
+            row: String::from("{ .apply = {fn}, .name = \"{name}\" },"),
+        }),
+        self_contained: true,
+        ..Default::default()
+    };
+    let patch = sm64gs2pc::DECOMP_DATA_STATIC
+        .gs_code_to_patch_with_options("Moon Jump", code, &options)
+        .unwrap();
+    assert!(patch.contains("\n+extern CheatEntry cheats[];\n"));
+    assert!(patch.ends_with(
+        "
++void run_gameshark_cheats(void) {
++
++}
++
++CheatEntry cheats[] = {
++    { .apply = cheat_moon_jump, .name = \"Moon Jump\" },
++};"
+    ));
+}
+
+/// Find the base patches that a patch of cheats applies on top of
+#[test]
+fn compatible_base_patches() {
+    use sm64gs2pc::BasePatch;
+
+    let mut cheats = vec!["8133B176 0015"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap()];
+    let options = sm64gs2pc::PatchOptions::default();
+
+    assert_eq!(
+        sm64gs2pc::compatible_base_patches(&cheats, &options),
+        vec![BasePatch::Sm64Port, BasePatch::Sm64exNightly]
+    );
+
+    // Self-contained patches are for sources without a base patch
+    let self_contained = sm64gs2pc::PatchOptions {
+        self_contained: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        sm64gs2pc::compatible_base_patches(&cheats, &self_contained),
+        vec![]
+    );
+
+    // Only sm64ex's base patch includes `string.h` for array initializers, and
+    // neither includes `assert.h` for bounds checks
+    let array_initializers = sm64gs2pc::PatchOptions {
+        array_initializers: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        sm64gs2pc::compatible_base_patches(&cheats, &array_initializers),
+        vec![BasePatch::Sm64exNightly]
+    );
+    let bounds_asserts = sm64gs2pc::PatchOptions {
+        bounds_asserts: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        sm64gs2pc::compatible_base_patches(&cheats, &bounds_asserts),
+        vec![]
+    );
+
+    // The base patches don't define the button check
+    cheats.push("8933B176 0015".parse().unwrap());
+    assert_eq!(
+        sm64gs2pc::compatible_base_patches(&cheats, &options),
+        vec![]
+    );
+
+    // Each base patch adds the hook that its target describes
+    for base_patch in BasePatch::ALL {
+        let path = format!(
+            "{}/../base-patches/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            base_patch.file_name()
+        );
+        let base = std::fs::read_to_string(path).unwrap();
+        let target = base_patch.target();
+        let hook = format!(
+            "\n+void run_gameshark_cheats(void) {{\n+{}\n",
+            target.trailing_context
+        );
+        assert!(base.contains(&hook));
+
+        // The hook is on `hook_line` of the created `gameshark.c`
+        let gameshark_c = base
+            .split("+++ b/src/game/gameshark.c\n")
+            .nth(1)
+            .unwrap()
+            .lines()
+            .skip(1)
+            .map(|line| line.strip_prefix('+').unwrap_or(line))
+            .collect::<Vec<&str>>();
+        assert_eq!(gameshark_c[target.hook_line as usize - 1], target.hook);
+
+        // The hook is called after the call site
+        assert!(base.contains(&format!(
+            "\n {}\n+{}run_gameshark_cheats();\n",
+            target.call_site,
+            &target.call_site[..target.call_site.len() - target.call_site.trim_start().len()]
+        )));
+
+        for header in base_patch.includes() {
+            assert!(base
+                .lines()
+                .any(|line| line.starts_with("+#include <") && line.contains(header)));
+        }
+    }
+}
+
+/// Recover codes from patches created by this tool
+#[test]
+fn patch_round_trip() {
+    let code = "D033AFA1 0020
+8133B1BC 4220
+8138F800 ABCD"
+        .parse::<sm64gs2pc::gameshark::Code>()
+        .unwrap();
+
+    for wrap_in_function in [false, true] {
+        let options = sm64gs2pc::PatchOptions {
+            wrap_in_function,
+            ..Default::default()
+        };
+        let patch = sm64gs2pc::DECOMP_DATA_STATIC
+            .gs_code_to_patch_with_options("Moon Jump", code.clone(), &options)
+            .unwrap();
+
+        assert_eq!(
+            sm64gs2pc::DECOMP_DATA_STATIC.patch_to_code(&patch).unwrap(),
+            vec![(String::from("Moon Jump"), code.clone())]
+        );
+    }
+}
+
+/// Get each scope enclosing an address
+#[test]
+fn resolution_path_course_stars() {
+    let step = |expr: &str, type_name: &str, addr| sm64gs2pc::ResolutionStep {
+        expr: expr.to_owned(),
+        type_name: type_name.to_owned(),
+        addr,
+    };
+
+    assert_eq!(
+        sm64gs2pc::DECOMP_DATA_STATIC
+            .resolution_path(0x8020770F)
+            .unwrap(),
+        vec![
+            step("gSaveBuffer", "struct SaveBuffer", 0x80207700),
+            step("gSaveBuffer.files", "struct SaveFile[4][2]", 0x80207700),
+            step("gSaveBuffer.files[0]", "struct SaveFile[2]", 0x80207700),
+            step("gSaveBuffer.files[0][0]", "struct SaveFile", 0x80207700),
+            step("gSaveBuffer.files[0][0].courseStars", "u8[25]", 0x8020770C),
+            step("gSaveBuffer.files[0][0].courseStars[3]", "u8", 0x8020770F),
+        ]
+    );
+}
+
+/// Check that a code converts with the command-line tool, without a patch
+#[test]
+fn cli_check_only() {
+    let check = |code: &str| {
+        let path = std::env::temp_dir().join(format!(
+            "sm64gs2pc-check-only-{}.txt",
+            code.replace(' ', "-")
+        ));
+        std::fs::write(&path, code).unwrap();
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_sm64gs2pc"))
+            .arg("--check-only")
+            .arg("--code")
+            .arg(&path)
+            .output()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        output
+    };
+
+    let output = check("8133B176 0015");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+
+    // Functions can't be patched
+    let output = check("81246000 0000");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(!output.stderr.is_empty());
+}